@@ -0,0 +1,236 @@
+// 批量处理：逐行读取大量题目，多线程检查有效性、解的个数并评估难度，按输入顺序输出CSV或JSON Lines
+// 输入按块读取，内存占用与文件大小无关
+// wasm32-unknown-unknown不能创建线程，也没有系统时钟，在浏览器中退化为单线程处理，耗时记为0
+
+use std::{
+    io::{self, BufRead, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::Instant,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    inferences::{InferenceSet, Technique},
+    types::{Difficulty, Sudoku},
+};
+
+// 每次读入并并行处理的行数
+const CHUNK_SIZE: usize = 4096;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BatchFormat {
+    Csv,
+    JsonLines,
+}
+
+impl std::str::FromStr for BatchFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "csv" => Ok(BatchFormat::Csv),
+            "jsonl" | "json" => Ok(BatchFormat::JsonLines),
+            _ => Err(format!("Invalid Batch Format: {}", s)),
+        }
+    }
+}
+
+/// 一道题目的处理结果
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BatchRecord {
+    // 在输入中的行号，从1开始
+    pub line: usize,
+    pub puzzle: String,
+    // 能够解析、没有冲突且解唯一
+    pub valid: bool,
+    // 解的个数，最多统计到2
+    pub solutions: usize,
+    // 只靠推理策略解出，用到暴力破解时为false
+    pub solved: bool,
+    pub steps: usize,
+    pub score: u32,
+    pub hardest: Option<Technique>,
+    pub difficulty: Option<Difficulty>,
+    // 处理耗时，单位微秒，wasm中为0
+    pub time_us: u64,
+    pub error: Option<String>,
+}
+
+/// 整个批次的统计
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub total: usize,
+    pub valid: usize,
+    pub time_us: u64,
+}
+
+impl BatchRecord {
+    pub const CSV_HEADER: &'static str =
+        "line,puzzle,valid,solutions,solved,steps,score,hardest,difficulty,time_us,error";
+
+    /// 处理一道题目，puzzle为81位字符，空格用0或.表示
+    pub fn process(line: usize, puzzle: &str) -> BatchRecord {
+        let start = now();
+        let mut record = BatchRecord {
+            line,
+            puzzle: puzzle.to_string(),
+            valid: false,
+            solutions: 0,
+            solved: false,
+            steps: 0,
+            score: 0,
+            hardest: None,
+            difficulty: None,
+            time_us: 0,
+            error: None,
+        };
+
+        match Sudoku::from_line(puzzle) {
+            Err(e) => record.error = Some(e),
+            Ok(field) if field.find_conflict().is_some() => {
+                record.error = Some("Puzzle Has Conflicts.".to_string());
+            }
+            Ok(field) => {
+                record.solutions = field.sovle().len();
+                record.valid = record.solutions == 1;
+                if record.valid {
+                    let rating = InferenceSet::new().rate(&field);
+                    record.solved = rating.solved;
+                    record.steps = rating.steps;
+                    record.score = rating.score;
+                    record.hardest = rating.hardest;
+                    record.difficulty = rating.difficulty;
+                }
+            }
+        }
+
+        record.time_us = elapsed_us(start);
+        record
+    }
+
+    pub fn to_csv(&self) -> String {
+        fn csv_field(s: &str) -> String {
+            if s.contains([',', '"', '\n']) {
+                format!("\"{}\"", s.replace('"', "\"\""))
+            } else {
+                s.to_string()
+            }
+        }
+
+        [
+            self.line.to_string(),
+            csv_field(&self.puzzle),
+            self.valid.to_string(),
+            self.solutions.to_string(),
+            self.solved.to_string(),
+            self.steps.to_string(),
+            self.score.to_string(),
+            self.hardest.map_or(String::new(), |t| format!("{:?}", t)),
+            self.difficulty
+                .map_or(String::new(), |d| format!("{:?}", d)),
+            self.time_us.to_string(),
+            csv_field(self.error.as_deref().unwrap_or_default()),
+        ]
+        .join(",")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("BatchRecord Must Be Serializable.")
+    }
+}
+
+/// 用threads个线程并行处理，返回结果的顺序与输入一致
+pub fn process_all(puzzles: &[(usize, String)], threads: usize) -> Vec<BatchRecord> {
+    // 单线程或wasm中直接在当前线程处理
+    if cfg!(target_arch = "wasm32") || threads <= 1 {
+        return puzzles
+            .iter()
+            .map(|(line, puzzle)| BatchRecord::process(*line, puzzle))
+            .collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut records: Vec<(usize, BatchRecord)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                s.spawn(|| {
+                    let mut records = vec![];
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some((line, puzzle)) = puzzles.get(i) else {
+                            break;
+                        };
+                        records.push((i, BatchRecord::process(*line, puzzle)));
+                    }
+                    records
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("Batch Worker Panicked."))
+            .collect()
+    });
+    records.sort_by_key(|(i, _)| *i);
+    records.into_iter().map(|(_, record)| record).collect()
+}
+
+// 计时的起点，wasm32-unknown-unknown中调用Instant::now会panic，此时不计时
+fn now() -> Option<Instant> {
+    (!cfg!(target_arch = "wasm32")).then(Instant::now)
+}
+
+fn elapsed_us(start: Option<Instant>) -> u64 {
+    start.map_or(0, |start| start.elapsed().as_micros() as u64)
+}
+
+/// 默认线程数，等于可用的CPU核数
+pub fn default_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// 从input逐行读取题目，结果写入output，空行和以#开头的行会被跳过
+pub fn run<R: BufRead, W: Write>(
+    input: R,
+    mut output: W,
+    format: BatchFormat,
+    threads: usize,
+) -> io::Result<BatchSummary> {
+    let start = now();
+    let mut summary = BatchSummary::default();
+    if format == BatchFormat::Csv {
+        writeln!(output, "{}", BatchRecord::CSV_HEADER)?;
+    }
+
+    let mut lines = input.lines().enumerate();
+    loop {
+        let mut chunk = Vec::with_capacity(CHUNK_SIZE);
+        for (index, line) in lines.by_ref() {
+            let line = line?;
+            let line = line.trim();
+            if !line.is_empty() && !line.starts_with('#') {
+                chunk.push((index + 1, line.to_string()));
+                if chunk.len() == CHUNK_SIZE {
+                    break;
+                }
+            }
+        }
+        if chunk.is_empty() {
+            break;
+        }
+
+        for record in process_all(&chunk, threads) {
+            summary.total += 1;
+            summary.valid += record.valid as usize;
+            match format {
+                BatchFormat::Csv => writeln!(output, "{}", record.to_csv())?,
+                BatchFormat::JsonLines => writeln!(output, "{}", record.to_json())?,
+            }
+        }
+    }
+
+    output.flush()?;
+    summary.time_us = elapsed_us(start);
+    Ok(summary)
+}
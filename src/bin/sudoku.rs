@@ -0,0 +1,543 @@
+// 命令行工具，从文件或标准输入逐行读取题目，每行一个，结果输出到标准输出，方便在管道中使用
+// 支持的输入格式：81位字符（空格用0或.表示）、紧凑字符串、toJS/serde_json导出的JSON
+// 一行字符的长度为16、36、144、256、625时分别为4x4、6x6、12x12、16x16、25x25数独
+// 锯齿数独的区域图用--regions指定，对角线数独用--diagonal指定，窗口数独用--windows指定，
+// 杀手数独的笼子用--cages指定，无马、无王数独用--anti-knight、--anti-king指定，
+// 非连续数独用--non-consecutive指定，克罗普基圆点用--dots指定，温度计、箭头等线条用--lines指定，
+// XV标记用--xv指定，三明治提示用--sandwich指定，
+// 一行字符的题目按指定的规则解析
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read, Write},
+    process::{Command, ExitCode, Stdio},
+};
+
+use lib_sudoku_solver::{
+    batch::{self, BatchFormat},
+    book::{Book, BookOptions},
+    game::Game,
+    inferences::InferenceSet,
+    locale::Catalog,
+    multi::{LayoutKind, MultiSudoku},
+    rules::Rules,
+    svg::SvgOptions,
+    tui::{Key, Tui},
+    types::{Difficulty, Sudoku},
+};
+
+const USAGE: &str = "\
+用法: sudoku <命令> [选项] [文件]
+
+命令:
+  solve                     暴力求解，输出答案
+  explain                   使用推理策略逐步求解，输出每一步的说明
+  generate                  按难度生成题目
+  rate                      评估难度，输出 题目<TAB>难度<TAB>分值<TAB>步数<TAB>最难策略
+  convert                   转换格式
+  batch                     批量检查并评估大量题目，多线程处理，按输入顺序输出
+  play                      在终端中游玩，指定文件时使用其中的第一道题目，否则按难度和规则生成
+  book                      排版为可打印的LaTeX题集，附答案，指定文件时使用其中的题目，否则按难度生成
+
+选项:
+  --difficulty <难度>       generate、play和book使用，easy/normal/medium/hard/expert，默认easy
+                            可以用逗号分隔多个难度，题目按顺序平均分配
+  --count <数量>            generate和book使用，默认1
+  --box <行x列>             宫的形状，如2x2、2x3、3x4、4x4、5x5，默认3x3
+                            generate和play按该形状生成，读取一行字符的题目时也按该形状解析
+  --regions <区域图>        锯齿数独的区域图，每格一个字符，相同字符属于同一区域，用法同--box
+  --diagonal                对角线数独，两条对角线内的数字也不能重复，可以与--box、--regions同时使用
+  --windows                 窗口数独，与宫形状相同、和边缘及宫之间各隔一格的窗口内的数字也不能重复，用法同--diagonal
+  --anti-knight             无马数独，相隔国际象棋马步的格子不能填写相同的数字，用法同--diagonal
+  --anti-king               无王数独，八个方向相邻的格子不能填写相同的数字，用法同--diagonal
+  --non-consecutive         非连续数独，上下左右相邻的格子不能相差1，用法同--diagonal
+  --dots <圆点列表>         克罗普基圆点，逗号分隔，如r1c1-r1c2=w,r2c1-r3c1=b，白点(w)两数相差1，
+                            黑点(b)一个数是另一个数的两倍，用法同--diagonal
+  --negative-dots           圆点的反向约束，没有圆点的相邻格子既不能相差1也不能是两倍关系
+  --lines <线条列表>        线条约束，逗号分隔，每条线的格子按方向用-连接，如r1c1-r1c2-r2c3=t，
+                            t为温度计（从圆头开始递增），a为箭头（圆圈等于箭身之和），
+                            w为德国悄悄话（相邻两数至少相差边长的一半，向上取整，9x9为5），
+                            r为连号线（数字连续），用法同--diagonal
+  --xv <标记列表>           XV标记，逗号分隔，如r1c1-r1c2=x,r2c1-r3c1=v，X两数之和为10，V两数之和为5，
+                            用法同--diagonal
+  --negative-xv             XV的反向约束，没有标记的相邻格子之和既不能是10也不能是5
+  --sandwich <行提示/列提示> 三明治提示，行、列分别逗号分隔，.表示没有提示，如35,.,…/.,10,…，
+                            每行（列）中1和最大数字之间的数字之和等于提示，用法同--diagonal
+  --cages <笼子图>=<和>     杀手数独的笼子，笼子图每格一个字符，相同字符属于同一笼子，.表示不在笼子内，
+                            和按字符首次出现的顺序用逗号分隔，如AAB.…=3,17，可以与其他规则同时使用
+  --killer                  generate使用，生成杀手数独，按难度决定笼子大小，输出包含笼子的JSON
+  --layout <拼接方式>       generate使用，生成多重数独，samurai/twin/butterfly，输出包含每个盘面的JSON，
+                            武士数独的格子较多，生成较慢
+  --per-page <数量>         book使用，每页的题目数，1-12，默认4
+  --title <标题>            book使用，默认Sudoku
+  --lang <语言>             explain使用，zh或en，默认zh
+  --notation                explain使用，输出通用记法而不是文字说明
+  --to <格式>               convert使用，line/compact/json/grid/svg/svg-step/ss/sdk/sdx/hodoku/
+                            fpuzzles/sudokuwiki，默认line，svg-step在盘面上标出下一步推理
+  --from <格式>             输入为多行的ss/sdk/sdx文件时指定，整个输入作为一道题目
+  --format <格式>           batch使用，csv或jsonl，默认csv
+  --threads <线程数>        batch使用，默认为CPU核数
+  -h, --help                显示帮助
+
+没有指定文件或文件为-时从标准输入读取，空行和以#开头的行会被忽略
+单行输入自动识别81位字符、紧凑字符串、JSON、HoDoKu题库行和f-puzzles/SudokuWiki链接";
+
+struct Options {
+    command: String,
+    input: Option<String>,
+    difficulties: Vec<Difficulty>,
+    count: usize,
+    rules: Rules,
+    killer: bool,
+    layout: Option<LayoutKind>,
+    catalog: Catalog,
+    notation: bool,
+    to: String,
+    from: Option<String>,
+    format: BatchFormat,
+    threads: usize,
+    book: BookOptions,
+}
+
+impl Options {
+    fn parse(args: &[String]) -> Result<Options, String> {
+        let mut options = Options {
+            command: String::new(),
+            input: None,
+            difficulties: vec![Difficulty::EASY],
+            count: 1,
+            rules: Rules::classic(),
+            killer: false,
+            layout: None,
+            catalog: Catalog::default(),
+            notation: false,
+            to: "line".to_string(),
+            from: None,
+            format: BatchFormat::Csv,
+            threads: batch::default_threads(),
+            book: BookOptions::default(),
+        };
+        let mut diagonal = false;
+        let mut windows = false;
+        let mut anti_knight = false;
+        let mut anti_king = false;
+        let mut cages: Option<String> = None;
+        let mut non_consecutive = false;
+        let mut dots = String::new();
+        let mut negative_dots = false;
+        let mut lines = String::new();
+        let mut xv = String::new();
+        let mut negative_xv = false;
+        let mut sandwich = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| {
+                args.next()
+                    .cloned()
+                    .ok_or_else(|| format!("Missing Value For {}.", name))
+            };
+            match arg.as_str() {
+                "--difficulty" => {
+                    options.difficulties = value(arg)?
+                        .split(',')
+                        .map(str::parse)
+                        .collect::<Result<Vec<Difficulty>, String>>()?;
+                }
+                "--count" => {
+                    let count = value(arg)?;
+                    options.count = count
+                        .parse()
+                        .map_err(|_| format!("Invalid Count: {}", count))?;
+                }
+                "--box" => {
+                    let shape = value(arg)?;
+                    let (rows, cols) = shape
+                        .split_once('x')
+                        .and_then(|(r, c)| Some((r.parse().ok()?, c.parse().ok()?)))
+                        .ok_or_else(|| format!("Invalid Box Shape: {}", shape))?;
+                    options.rules = Rules::new(rows, cols)?;
+                }
+                "--regions" => options.rules = Rules::jigsaw(&value(arg)?)?,
+                "--diagonal" => diagonal = true,
+                "--windows" => windows = true,
+                "--anti-knight" => anti_knight = true,
+                "--anti-king" => anti_king = true,
+                "--cages" => cages = Some(value(arg)?),
+                "--non-consecutive" => non_consecutive = true,
+                "--dots" => dots = value(arg)?,
+                "--negative-dots" => negative_dots = true,
+                "--lines" => lines = value(arg)?,
+                "--xv" => xv = value(arg)?,
+                "--negative-xv" => negative_xv = true,
+                "--sandwich" => sandwich = Some(value(arg)?),
+                "--killer" => options.killer = true,
+                "--layout" => options.layout = Some(value(arg)?.parse()?),
+                "--lang" => {
+                    let language = value(arg)?;
+                    options.catalog = Catalog::builtin(&language)
+                        .ok_or_else(|| format!("Unknown Language: {}", language))?;
+                }
+                "--notation" => options.notation = true,
+                "--to" => options.to = value(arg)?,
+                "--from" => options.from = Some(value(arg)?),
+                "--format" => options.format = value(arg)?.parse()?,
+                "--per-page" => {
+                    let per_page = value(arg)?;
+                    options.book.per_page = per_page
+                        .parse()
+                        .map_err(|_| format!("Invalid Puzzles Per Page: {}", per_page))?;
+                }
+                "--title" => options.book.title = value(arg)?,
+                "--threads" => {
+                    let threads = value(arg)?;
+                    options.threads = threads
+                        .parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| format!("Invalid Threads: {}", threads))?;
+                }
+                "-h" | "--help" => options.command = "help".to_string(),
+                _ if arg.starts_with("--") => return Err(format!("Unknown Option: {}", arg)),
+                _ if options.command.is_empty() => options.command = arg.clone(),
+                _ if options.input.is_none() => options.input = Some(arg.clone()),
+                _ => return Err(format!("Unexpected Argument: {}", arg)),
+            }
+        }
+        if diagonal {
+            options.rules = options.rules.with_diagonals();
+        }
+        if windows {
+            options.rules = options.rules.with_windows();
+        }
+        if anti_knight {
+            options.rules = options.rules.with_anti_knight();
+        }
+        if anti_king {
+            options.rules = options.rules.with_anti_king();
+        }
+        if non_consecutive {
+            options.rules = options.rules.with_non_consecutive();
+        }
+        if !dots.is_empty() || negative_dots {
+            options.rules = options.rules.with_dot_list(&dots, negative_dots)?;
+        }
+        if !lines.is_empty() {
+            options.rules = options.rules.with_line_list(&lines)?;
+        }
+        if !xv.is_empty() || negative_xv {
+            options.rules = options.rules.with_xv_list(&xv, negative_xv)?;
+        }
+        if let Some(sandwich) = sandwich {
+            options.rules = options.rules.with_sandwich_list(&sandwich)?;
+        }
+        if let Some(cages) = cages {
+            options.rules = options.rules.with_cage_map(&cages)?;
+        }
+        Ok(options)
+    }
+}
+
+fn read_lines(input: &Option<String>) -> Result<Box<dyn BufRead>, String> {
+    match input.as_deref() {
+        None | Some("-") => Ok(Box::new(BufReader::new(io::stdin()))),
+        Some(path) => File::open(path)
+            .map(|f| Box::new(BufReader::new(f)) as Box<dyn BufRead>)
+            .map_err(|e| format!("Cannot Open {}: {}", path, e)),
+    }
+}
+
+// 根据内容自动识别输入格式，指定了规则时一行字符的题目按该规则解析
+fn parse_puzzle(line: &str, rules: &Rules) -> Result<Sudoku, String> {
+    if line.starts_with('{') {
+        serde_json::from_str(line).map_err(|e| e.to_string())
+    } else if line.starts_with("http") {
+        Sudoku::from_url(line)
+    } else if line.starts_with(':') {
+        Sudoku::from_hodoku_line(line)
+    } else if line.contains(':') && *rules == Rules::classic() {
+        Sudoku::from_compact_string(line)
+    } else if line.contains(':') {
+        Sudoku::from_compact_string_with(line, rules.clone())
+    } else if *rules == Rules::classic() {
+        Sudoku::from_line(line)
+    } else {
+        Sudoku::from_line_with(line, rules.clone())
+    }
+}
+
+fn solve(field: &Sudoku) -> Result<String, String> {
+    let mut solutions = field.sovle();
+    match solutions.len() {
+        0 => Err("No Solution.".to_string()),
+        1 => Ok(solutions.remove(0).to_value_string()),
+        _ => Err("Multiple Solutions.".to_string()),
+    }
+}
+
+fn explain(field: &Sudoku, options: &Options) -> Result<String, String> {
+    let ifs = InferenceSet::new();
+    let mut field = field.clone();
+    let mut lines = vec![field.to_value_string()];
+    while !field.check_if_finish() {
+        let old_field = field.clone();
+        let Some(result) = ifs.analyze(&old_field) else {
+            lines.push("No Further Inference.".to_string());
+            break;
+        };
+        lines.push(if options.notation {
+            result.notation()
+        } else {
+            result.explanation_with(&options.catalog)
+        });
+        InferenceSet::apply(&mut field, result);
+    }
+    lines.push(String::new());
+    Ok(lines.join("\n"))
+}
+
+fn rate(field: &Sudoku) -> Result<String, String> {
+    let rating = InferenceSet::new().rate(field);
+    if !rating.solved {
+        return Err("Cannot Be Solved By Inference.".to_string());
+    }
+    Ok(format!(
+        "{}\t{:?}\t{}\t{}\t{:?}",
+        field.to_value_string(),
+        rating.difficulty.unwrap_or(Difficulty::EXPERT),
+        rating.score,
+        rating.steps,
+        rating
+            .hardest
+            .expect("Solved Rating Must Have A Technique.")
+    ))
+}
+
+fn convert(field: &Sudoku, options: &Options) -> Result<String, String> {
+    match options.to.as_str() {
+        "line" => Ok(field.to_value_string()),
+        "compact" => Ok(field.to_compact_string()),
+        "json" => serde_json::to_string(field).map_err(|e| e.to_string()),
+        "grid" => Ok(field.to_grid_string()),
+        "ss" => field.to_ss(),
+        "sdk" => field.to_sdk(),
+        "sdx" => field.to_sdx(),
+        "hodoku" => field.to_hodoku_line(),
+        "fpuzzles" => field.to_fpuzzles_url(),
+        "sudokuwiki" => field.to_sudokuwiki_url(),
+        "svg" => Ok(field.to_svg()),
+        // 叠加下一步推理
+        "svg-step" => {
+            let ifs = InferenceSet::new();
+            let result = ifs
+                .analyze(field)
+                .ok_or_else(|| "No Further Inference.".to_string())?;
+            Ok(result.to_svg(field, &SvgOptions::default()))
+        }
+        _ => Err(format!("Unknown Format: {}", options.to)),
+    }
+}
+
+// 终端原始模式：关闭行缓冲和回显，Ctrl-C作为普通按键读入，结束时恢复原来的设置
+// 没有stty的平台上保持原样，每次按键后需要回车
+struct RawMode(Option<String>);
+
+impl RawMode {
+    fn stty(args: &[&str]) -> Option<String> {
+        let output = Command::new("stty")
+            .args(args)
+            .stdin(Stdio::inherit())
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    fn enable() -> RawMode {
+        let saved = RawMode::stty(&["-g"]);
+        if saved.is_some() {
+            RawMode::stty(&["-icanon", "-echo", "-isig", "min", "1"]);
+        }
+        RawMode(saved)
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Some(saved) = &self.0 {
+            RawMode::stty(&[saved]);
+        }
+    }
+}
+
+fn play(options: &Options) -> Result<bool, String> {
+    let game = match &options.input {
+        Some(_) => {
+            let line = read_lines(&options.input)?
+                .lines()
+                .map_while(Result::ok)
+                .find(|line| !line.trim().is_empty() && !line.trim().starts_with('#'))
+                .ok_or_else(|| "No Puzzle Found.".to_string())?;
+            Game::new(parse_puzzle(line.trim(), &options.rules)?)?
+        }
+        None => Game::generate_with(options.rules.clone(), options.difficulties[0]),
+    };
+    let mut tui = Tui::new(game, options.difficulties[0]);
+
+    let _raw_mode = RawMode::enable();
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    // 使用备用屏幕并隐藏光标，退出后恢复原来的终端内容
+    write!(stdout, "\x1b[?1049h\x1b[?25l").map_err(|e| e.to_string())?;
+    let mut buf = [0u8; 64];
+    while !tui.is_quit() {
+        writeln!(stdout, "\x1b[H\x1b[2J{}", tui.render()).map_err(|e| e.to_string())?;
+        stdout.flush().map_err(|e| e.to_string())?;
+        let n = stdin.read(&mut buf).map_err(|e| e.to_string())?;
+        if n == 0 {
+            break;
+        }
+        for key in Key::parse(&buf[..n]) {
+            tui.handle_key(key);
+        }
+    }
+    write!(stdout, "\x1b[?25h\x1b[?1049l").map_err(|e| e.to_string())?;
+    stdout.flush().map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+// 下游关闭管道（如 | head）时直接结束，不当作错误
+fn write_line(stdout: &mut impl Write, output: &str) -> Result<(), String> {
+    match writeln!(stdout, "{}", output) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        result => result.map_err(|e| e.to_string()),
+    }
+}
+
+fn run(options: &Options) -> Result<bool, String> {
+    if options.command == "play" {
+        return play(options);
+    }
+    let mut stdout = io::stdout().lock();
+    let handler: fn(&Sudoku, &Options) -> Result<String, String> = match options.command.as_str() {
+        "generate" => {
+            let difficulties = &options.difficulties;
+            for i in 0..options.count {
+                let difficulty = difficulties[i * difficulties.len() / options.count];
+                if let Some(layout) = options.layout {
+                    let puzzle = MultiSudoku::generate(layout, difficulty);
+                    let json = serde_json::to_string(&puzzle).map_err(|e| e.to_string())?;
+                    write_line(&mut stdout, &json)?;
+                    continue;
+                }
+                if options.killer {
+                    let puzzle = Sudoku::generate_killer(options.rules.clone(), difficulty);
+                    let json = serde_json::to_string(&puzzle).map_err(|e| e.to_string())?;
+                    write_line(&mut stdout, &json)?;
+                    continue;
+                }
+                let puzzle = Sudoku::generate(options.rules.clone(), difficulty);
+                write_line(&mut stdout, &puzzle.to_value_string())?;
+            }
+            return Ok(true);
+        }
+        "book" => {
+            let book = match &options.input {
+                Some(_) => {
+                    let mut puzzles = vec![];
+                    for line in read_lines(&options.input)?.lines() {
+                        let line = line.map_err(|e| e.to_string())?;
+                        let line = line.trim();
+                        if !line.is_empty() && !line.starts_with('#') {
+                            puzzles.push(parse_puzzle(line, &options.rules)?);
+                        }
+                    }
+                    Book::from_puzzles(puzzles)?
+                }
+                None => Book::generate(options.count, &options.difficulties),
+            };
+            write_line(&mut stdout, &book.to_latex(&options.book)?)?;
+            return Ok(true);
+        }
+        "batch" => {
+            let input = read_lines(&options.input)?;
+            let summary = match batch::run(input, &mut stdout, options.format, options.threads) {
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(true),
+                result => result.map_err(|e| e.to_string())?,
+            };
+            eprintln!(
+                "{} puzzles, {} valid, {:.3}s",
+                summary.total,
+                summary.valid,
+                summary.time_us as f64 / 1e6
+            );
+            return Ok(true);
+        }
+        "solve" => |field, _| solve(field),
+        "explain" => explain,
+        "rate" => |field, _| rate(field),
+        "convert" => convert,
+        "" | "help" => {
+            println!("{}", USAGE);
+            return Ok(true);
+        }
+        command => {
+            eprintln!("Unknown Command: {}\n\n{}", command, USAGE);
+            return Ok(false);
+        }
+    };
+
+    // 多行格式的整个输入是一道题目
+    if let Some(from) = &options.from {
+        let mut input = String::new();
+        read_lines(&options.input)?
+            .read_to_string(&mut input)
+            .map_err(|e| e.to_string())?;
+        let field = match from.as_str() {
+            "ss" => Sudoku::from_ss(&input)?,
+            "sdk" => Sudoku::from_sdk(&input)?,
+            "sdx" => Sudoku::from_sdx(&input)?,
+            _ => return Err(format!("Unknown Format: {}", from)),
+        };
+        write_line(&mut stdout, &handler(&field, options)?)?;
+        return Ok(true);
+    }
+
+    // 单行出错时报告到标准错误并继续处理后面的题目
+    let mut all_ok = true;
+    for (index, line) in read_lines(&options.input)?.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_puzzle(line, &options.rules).and_then(|field| handler(&field, options)) {
+            Ok(output) => write_line(&mut stdout, &output)?,
+            Err(e) => {
+                all_ok = false;
+                eprintln!("Line {}: {}", index + 1, e);
+            }
+        }
+    }
+    Ok(all_ok)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let options = match Options::parse(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&options) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
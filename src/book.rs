@@ -0,0 +1,379 @@
+// 打印题集：把多道题目排版为LaTeX源文件，每页若干道题，最后附答案
+// 生成的源文件只依赖geometry和tikz，用pdflatex或xelatex编译即可得到PDF
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    inferences::InferenceSet,
+    rules::{DotColor, LineKind},
+    types::{value_to_char, CellStatus, Difficulty, Sudoku},
+};
+
+// A4纸左右各留1.5cm后的版心尺寸，单位为cm
+const TEXT_WIDTH: f64 = 18.0;
+const TEXT_HEIGHT: f64 = 25.5;
+// 每道题上方标题和题目之间的间距占用的高度
+const CAPTION_HEIGHT: f64 = 1.2;
+
+/// 排版选项
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BookOptions {
+    pub title: String,
+    // 每页的题目数，1-12
+    pub per_page: usize,
+    // 答案部分每页的题目数，1-12
+    pub answers_per_page: usize,
+    pub answers_title: String,
+}
+
+impl Default for BookOptions {
+    fn default() -> Self {
+        BookOptions {
+            title: "Sudoku".to_string(),
+            per_page: 4,
+            answers_per_page: 12,
+            answers_title: "Answers".to_string(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct BookEntry {
+    pub puzzle: Sudoku,
+    pub solution: Sudoku,
+    pub difficulty: Difficulty,
+}
+
+#[derive(Clone, Default)]
+pub struct Book {
+    pub entries: Vec<BookEntry>,
+}
+
+impl Book {
+    /// 生成count道题目，按difficulties的顺序平均分配难度，同一难度的题目排在一起
+    pub fn generate(count: usize, difficulties: &[Difficulty]) -> Book {
+        let difficulties = if difficulties.is_empty() {
+            &[Difficulty::EASY][..]
+        } else {
+            difficulties
+        };
+        let entries = (0..count)
+            .map(|i| {
+                let difficulty = difficulties[i * difficulties.len() / count];
+                let puzzle = Sudoku::new(difficulty);
+                let solution = puzzle.sovle().remove(0);
+                BookEntry {
+                    puzzle,
+                    solution,
+                    difficulty,
+                }
+            })
+            .collect();
+        Book { entries }
+    }
+
+    /// 使用已有的题目，题目必须有唯一解，难度按推理评估，无法推理解出的记为EXPERT
+    pub fn from_puzzles(puzzles: Vec<Sudoku>) -> Result<Book, String> {
+        let ifs = InferenceSet::new();
+        let entries = puzzles
+            .into_iter()
+            .enumerate()
+            .map(|(i, puzzle)| {
+                let mut solutions = puzzle.sovle();
+                if solutions.len() != 1 {
+                    return Err(format!("Puzzle {} Must Have A Unique Solution.", i + 1));
+                }
+                let difficulty = ifs.rate(&puzzle).difficulty.unwrap_or(Difficulty::EXPERT);
+                Ok(BookEntry {
+                    puzzle,
+                    solution: solutions.remove(0),
+                    difficulty,
+                })
+            })
+            .collect::<Result<Vec<BookEntry>, String>>()?;
+        Ok(Book { entries })
+    }
+
+    /// 排版为完整的LaTeX文档
+    pub fn to_latex(&self, options: &BookOptions) -> Result<String, String> {
+        for per_page in [options.per_page, options.answers_per_page] {
+            if !(1..=12).contains(&per_page) {
+                return Err(format!("Invalid Puzzles Per Page: {}", per_page));
+            }
+        }
+
+        let mut lines = vec![
+            r"\documentclass[a4paper]{article}".to_string(),
+            r"\usepackage[margin=1.5cm]{geometry}".to_string(),
+            r"\usepackage{tikz}".to_string(),
+            r"\setlength{\parindent}{0pt}".to_string(),
+            r"\begin{document}".to_string(),
+        ];
+        let puzzles: Vec<(usize, &BookEntry)> = self.entries.iter().enumerate().collect();
+        for page in puzzles.chunks(options.per_page) {
+            lines.extend(latex_page(&options.title, page, options.per_page, false));
+        }
+        for page in puzzles.chunks(options.answers_per_page) {
+            let heading = format!("{} -- {}", options.title, options.answers_title);
+            lines.extend(latex_page(&heading, page, options.answers_per_page, true));
+        }
+        lines.push(r"\end{document}".to_string());
+        lines.push(String::new());
+        Ok(lines.join("\n"))
+    }
+}
+
+// 每页的列数和行数
+fn page_layout(per_page: usize) -> (usize, usize) {
+    let columns = match per_page {
+        1 | 2 => 1,
+        3..=6 => 2,
+        _ => 3,
+    };
+    (columns, per_page.div_ceil(columns))
+}
+
+fn latex_page(
+    heading: &str,
+    entries: &[(usize, &BookEntry)],
+    per_page: usize,
+    answers: bool,
+) -> Vec<String> {
+    let (columns, rows) = page_layout(per_page);
+    let width = TEXT_WIDTH / columns as f64;
+    let size = (width - 0.8).min(TEXT_HEIGHT / rows as f64 - CAPTION_HEIGHT);
+    let first = entries.first().map_or(0, |(i, _)| i + 1);
+    let last = entries.last().map_or(0, |(i, _)| i + 1);
+
+    let mut lines = vec![format!(
+        r"{{\large\textbf{{{}}}}}\hfill {}--{}\par\vfill",
+        escape_latex(heading),
+        first,
+        last
+    )];
+    for row in entries.chunks(columns) {
+        for (i, entry) in row {
+            lines.push(format!(
+                r"\begin{{minipage}}[t]{{{:.2}cm}}\centering",
+                width
+            ));
+            lines.push(format!(
+                r"\makebox[{:.2}cm]{{\textbf{{{}}}\hfill {}}}\\[1mm]",
+                size,
+                i + 1,
+                entry.difficulty.name()
+            ));
+            let solution = answers.then_some(&entry.solution);
+            lines.push(latex_grid(&entry.puzzle, solution, size));
+            lines.push(r"\end{minipage}%".to_string());
+        }
+        lines.push(r"\par\vfill".to_string());
+    }
+    lines.push(r"\newpage".to_string());
+    lines
+}
+
+// 用tikz绘制一道题目，size为整个盘面的边长（cm），给出solution时绘制答案，原有的数字加粗
+fn latex_grid(puzzle: &Sudoku, solution: Option<&Sudoku>, size: f64) -> String {
+    let cells = puzzle.size();
+    let cell = size / cells as f64;
+    // 1cm约为28.45pt，数字高度取格子的六成
+    let font = cell * 28.45 * 0.6;
+    let mut lines = vec![format!(
+        r"\begin{{tikzpicture}}[x={:.3}cm,y=-{:.3}cm,font=\fontsize{{{:.1}pt}}{{{:.1}pt}}\selectfont\sffamily]",
+        cell, cell, font, font
+    )];
+    let rules = puzzle.rules();
+    // 窗口的底色要在网格线下面
+    if rules.has_windows() {
+        for (r, c) in rules.window_origins() {
+            lines.push(format!(
+                r"\fill[gray!15] ({},{}) rectangle ({},{});",
+                c,
+                r,
+                c + rules.box_cols(),
+                r + rules.box_rows()
+            ));
+        }
+    }
+    lines.push(format!(
+        r"\draw[gray!60,thin] {};",
+        grid_segments(cells, 1, 1)
+    ));
+    if rules.has_diagonals() {
+        lines.push(format!(
+            r"\draw[gray!40,thick] (0,0) -- ({n},{n}) ({n},0) -- (0,{n});",
+            n = cells
+        ));
+    }
+    if rules.is_jigsaw() {
+        // 不规则区域逐段画出边界
+        lines.push(format!(
+            r"\draw[very thick] (0,0) rectangle ({},{});",
+            cells, cells
+        ));
+        for rc in puzzle.iter_rc() {
+            if rules.thick_right(rc) && rc.c + 1 < cells {
+                let (x, y) = (rc.c + 1, rc.r);
+                lines.push(format!(
+                    r"\draw[very thick] ({},{}) -- ({},{});",
+                    x,
+                    y,
+                    x,
+                    y + 1
+                ));
+            }
+            if rules.thick_below(rc) && rc.r + 1 < cells {
+                let (x, y) = (rc.c, rc.r + 1);
+                lines.push(format!(
+                    r"\draw[very thick] ({},{}) -- ({},{});",
+                    x,
+                    y,
+                    x + 1,
+                    y
+                ));
+            }
+        }
+    } else {
+        lines.push(format!(
+            r"\draw[very thick] {};",
+            grid_segments(cells, rules.box_cols(), rules.box_rows())
+        ));
+    }
+    // 杀手数独的笼子用虚线框出，笼子和写在第一个格子的左上角
+    for (i, cage) in rules.cages().iter().enumerate() {
+        for ((x1, y1), (x2, y2)) in rules.cage_outline(i, 0.1) {
+            lines.push(format!(
+                r"\draw[densely dashed,thin] ({:.2},{:.2}) -- ({:.2},{:.2});",
+                x1, y1, x2, y2
+            ));
+        }
+        let first = rules.rc(cage.cells.iter().min().copied().unwrap_or_default());
+        lines.push(format!(
+            r"\node[anchor=north west,inner sep=0.5pt,font=\tiny] at ({}.1,{}.1) {{{}}};",
+            first.c, first.r, cage.sum
+        ));
+    }
+    // 线条约束：温度计带圆头，箭头从圆圈边缘出发，德国悄悄话和连号线分别用绿色和紫色
+    for line in rules.lines() {
+        let path: Vec<String> = line
+            .cells
+            .iter()
+            .map(|&idx| {
+                let rc = rules.rc(idx);
+                format!("({}.5,{}.5)", rc.c, rc.r)
+            })
+            .collect();
+        let width = |ratio: f64| format!("line width={:.2}cm", cell * ratio);
+        let style = match line.kind {
+            LineKind::Thermometer => format!("gray!40,{}", width(0.25)),
+            LineKind::Arrow => format!("gray,thick,->,shorten <={:.2}cm", cell * 0.4),
+            LineKind::Whisper => format!("green!60!black,{}", width(0.12)),
+            LineKind::Renban => format!("violet!40,{}", width(0.2)),
+        };
+        lines.push(format!(
+            r"\draw[{},line cap=round,line join=round] {};",
+            style,
+            path.join(" -- ")
+        ));
+        match line.kind {
+            LineKind::Thermometer => {
+                lines.push(format!(r"\fill[gray!40] {} circle (0.35);", path[0]))
+            }
+            LineKind::Arrow => lines.push(format!(r"\draw[gray,thick] {} circle (0.4);", path[0])),
+            _ => {}
+        }
+    }
+    // 克罗普基圆点画在两个格子相邻的边的中点
+    for dot in rules.dots() {
+        let (a, b) = (rules.rc(dot.cells[0]), rules.rc(dot.cells[1]));
+        let fill = match dot.color {
+            DotColor::White => "white",
+            DotColor::Black => "black",
+        };
+        lines.push(format!(
+            r"\filldraw[fill={},draw=black] ({:.1},{:.1}) circle (0.1);",
+            fill,
+            (a.c + b.c + 1) as f64 / 2.0,
+            (a.r + b.r + 1) as f64 / 2.0
+        ));
+    }
+    // XV标记写在边的中点，白底遮住边框
+    for mark in rules.xv_marks() {
+        let (a, b) = (rules.rc(mark.cells[0]), rules.rc(mark.cells[1]));
+        lines.push(format!(
+            r"\node[fill=white,inner sep=0.5pt,font=\scriptsize\bfseries] at ({:.1},{:.1}) {{{}}};",
+            (a.c + b.c + 1) as f64 / 2.0,
+            (a.r + b.r + 1) as f64 / 2.0,
+            if mark.sum == 5 { "V" } else { "X" }
+        ));
+    }
+    // 三明治提示写在行的左侧和列的上方
+    for (i, sum) in rules.sandwich_rows().iter().enumerate() {
+        if let Some(sum) = sum {
+            lines.push(format!(
+                r"\node[font=\small] at (-0.5,{}.5) {{{}}};",
+                i, sum
+            ));
+        }
+    }
+    for (i, sum) in rules.sandwich_cols().iter().enumerate() {
+        if let Some(sum) = sum {
+            lines.push(format!(
+                r"\node[font=\small] at ({}.5,-0.5) {{{}}};",
+                i, sum
+            ));
+        }
+    }
+    for rc in puzzle.iter_rc() {
+        let given = puzzle.get_cell_ref_by_rc(rc);
+        let given = given.value.filter(|_| given.status == CellStatus::FIXED);
+        let (value, bold) = match (given, solution) {
+            (Some(v), _) => (v, true),
+            (None, Some(solution)) => match solution.get_cell_ref_by_rc(rc).value {
+                Some(v) => (v, false),
+                None => continue,
+            },
+            (None, None) => continue,
+        };
+        lines.push(format!(
+            r"\node at ({}.5,{}.5) {{{}}};",
+            rc.c,
+            rc.r,
+            if bold && solution.is_some() {
+                format!(r"\textbf{{{}}}", value_to_char(value))
+            } else {
+                value_to_char(value).to_string()
+            }
+        ));
+    }
+    lines.push(r"\end{tikzpicture}".to_string());
+    lines.join("\n")
+}
+
+// 网格线逐条写出，竖线每隔xstep列、横线每隔ystep行一条
+// 不用TikZ的grid：它默认的step是页面上的1cm，不随x、y的缩放变化
+fn grid_segments(cells: usize, xstep: usize, ystep: usize) -> String {
+    let vertical = (0..=cells)
+        .step_by(xstep)
+        .map(|x| format!("({x},0) -- ({x},{cells})"));
+    let horizontal = (0..=cells)
+        .step_by(ystep)
+        .map(|y| format!("(0,{y}) -- ({cells},{y})"));
+    vertical
+        .chain(horizontal)
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn escape_latex(s: &str) -> String {
+    s.chars()
+        .map(|ch| match ch {
+            '\\' => r"\textbackslash{}".to_string(),
+            '~' => r"\textasciitilde{}".to_string(),
+            '^' => r"\textasciicircum{}".to_string(),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!(r"\{}", ch),
+            _ => ch.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,395 @@
+// 推理步骤的结构化说明：每个需要高亮的（格子，数字）都带有角色，界面可以直接据此绘制
+// 文字说明也由这里的模型生成，不再由各个策略单独拼接
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    inferences::{InferenceResult, Technique, TheCoordsAndTheValue},
+    locale::{Catalog, Message},
+    rules::{LineKind, Relation},
+    types::{value_to_char, RCCoords, Unit},
+};
+
+/// 高亮的草稿在推理中扮演的角色
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Role {
+    // 构成条件的草稿，如数对、宫内指向的草稿、鱼的基础行列上的草稿
+    Base,
+    // 需要填写的数值
+    Placement,
+    // 需要移除的草稿
+    Elimination,
+}
+
+/// 某个格子的某个数字，value从0开始
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct CellValue {
+    pub coords: RCCoords,
+    pub value: u8,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Highlight {
+    pub coords: RCCoords,
+    pub value: u8,
+    pub role: Role,
+}
+
+/// 一步推理的完整结构化说明
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Explanation {
+    pub technique: Technique,
+    pub highlights: Vec<Highlight>,
+    // 条件所在的单元，如数对所在的行、鱼的基础行
+    pub base_units: Vec<Unit>,
+    // 结论所在的单元，如区块排除的行、鱼的覆盖列
+    pub cover_units: Vec<Unit>,
+    // 相邻格关系类推理所依据的关系，如白点、黑点
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relation: Option<Relation>,
+    // 线条类推理所依据的线条种类，如温度计、箭头
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<LineKind>,
+    // 德国悄悄话线上相邻数字至少相差的值，由盘面边长决定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<u8>,
+    pub placements: Vec<CellValue>,
+    pub eliminations: Vec<CellValue>,
+}
+
+impl Explanation {
+    /// 由推理结果生成，条件中的草稿标记为condition_role，结论中的填写值和移除的草稿分别标记为Placement和Elimination
+    pub(crate) fn new(
+        result: &InferenceResult,
+        condition_role: Role,
+        base_units: Vec<Unit>,
+        cover_units: Vec<Unit>,
+    ) -> Explanation {
+        let conditions = flatten(result.condition());
+        let placements = flatten(result.conclusion_set_value().unwrap_or_default());
+        let eliminations = flatten(result.conclusion_remove_drafts().unwrap_or_default());
+
+        let mut highlights: Vec<Highlight> = vec![];
+        let roles = [
+            (&conditions, condition_role),
+            (&placements, Role::Placement),
+            (&eliminations, Role::Elimination),
+        ];
+        for (cvs, role) in roles {
+            for cv in cvs {
+                let highlight = Highlight {
+                    coords: cv.coords,
+                    value: cv.value,
+                    role,
+                };
+                if !highlights.contains(&highlight) {
+                    highlights.push(highlight);
+                }
+            }
+        }
+
+        Explanation {
+            technique: result.technique(),
+            highlights,
+            base_units,
+            cover_units,
+            relation: None,
+            line: None,
+            gap: None,
+            placements,
+            eliminations,
+        }
+    }
+
+    /// 推理时需要观察的单元，先条件后结论
+    pub fn units(&self) -> Vec<Unit> {
+        let mut units = self.base_units.clone();
+        for unit in &self.cover_units {
+            if !units.contains(unit) {
+                units.push(*unit);
+            }
+        }
+        units
+    }
+
+    /// 指定角色的所有高亮
+    pub fn with_role(&self, role: Role) -> Vec<CellValue> {
+        self.highlights
+            .iter()
+            .filter(|h| h.role == role)
+            .map(|h| CellValue {
+                coords: h.coords,
+                value: h.value,
+            })
+            .collect()
+    }
+
+    /// 根据模型生成默认语言（中文）的文字说明
+    pub fn describe(&self) -> String {
+        self.describe_with(&Catalog::default())
+    }
+
+    /// 根据模型和指定语言的文字表生成文字说明
+    pub fn describe_with(&self, catalog: &Catalog) -> String {
+        let base = self.with_role(Role::Base);
+        let cells = join_cells(catalog, &base);
+        let values = join_values(&base);
+        let base_units = join_units(catalog, &self.base_units);
+        let cover_units = join_units(catalog, &self.cover_units);
+        let eliminations = self.describe_eliminations(catalog);
+
+        match self.technique {
+            Technique::NakedSingle | Technique::HiddenSingle => {
+                let Some(placement) = self.placements.first() else {
+                    return String::new();
+                };
+                let cell = catalog.format_coords(placement.coords);
+                let value = value_to_char(placement.value).to_string();
+                let message = if self.technique == Technique::NakedSingle {
+                    Message::NakedSingle
+                } else {
+                    Message::HiddenSingle
+                };
+                let mut r = catalog.message(
+                    message,
+                    &[("cell", &cell), ("value", &value), ("units", &base_units)],
+                );
+                if !self.eliminations.is_empty() {
+                    let removed = join_cells(catalog, &self.eliminations);
+                    r.push_str(&catalog.message(
+                        Message::AlsoRemove,
+                        &[("cells", &removed), ("value", &value)],
+                    ));
+                }
+                r
+            }
+            Technique::Pointing | Technique::Claiming => catalog.message(
+                Message::LockedCandidates,
+                &[
+                    ("cells", &cells),
+                    ("values", &values),
+                    ("base_units", &base_units),
+                    ("cover_units", &cover_units),
+                    ("eliminations", &eliminations),
+                ],
+            ),
+            Technique::NakedSubset | Technique::HiddenSubset => catalog.message(
+                if self.technique == Technique::NakedSubset {
+                    Message::NakedSubset
+                } else {
+                    Message::HiddenSubset
+                },
+                &[
+                    ("cells", &cells),
+                    ("values", &values),
+                    ("base_units", &base_units),
+                    ("eliminations", &eliminations),
+                ],
+            ),
+            Technique::Fish => {
+                let fish = match self.base_units.len() {
+                    2 => catalog.message(Message::Fish2, &[]),
+                    3 => catalog.message(Message::Fish3, &[]),
+                    4 => catalog.message(Message::Fish4, &[]),
+                    _ => catalog.technique_name(Technique::Fish),
+                };
+                catalog.message(
+                    Message::Fish,
+                    &[
+                        ("values", &values),
+                        ("base_units", &base_units),
+                        ("cover_units", &cover_units),
+                        ("fish", &fish),
+                        ("eliminations", &eliminations),
+                    ],
+                )
+            }
+            Technique::CageCombination => catalog.message(
+                Message::CageCombination,
+                &[
+                    ("cells", &cells),
+                    ("values", &values),
+                    ("base_units", &base_units),
+                    ("eliminations", &eliminations),
+                ],
+            ),
+            Technique::Rule45 => {
+                // 只差两格时没有填写值，说明两格之和固定
+                let Some(placement) = self.placements.first() else {
+                    return catalog.message(
+                        Message::Rule45Pair,
+                        &[
+                            ("cells", &cells),
+                            ("base_units", &base_units),
+                            ("cover_units", &cover_units),
+                            ("eliminations", &eliminations),
+                        ],
+                    );
+                };
+                let cell = catalog.format_coords(placement.coords);
+                let value = value_to_char(placement.value).to_string();
+                let mut r = catalog.message(
+                    Message::Rule45,
+                    &[
+                        ("cell", &cell),
+                        ("value", &value),
+                        ("base_units", &base_units),
+                        ("cover_units", &cover_units),
+                    ],
+                );
+                if !self.eliminations.is_empty() {
+                    let removed = join_cells(catalog, &self.eliminations);
+                    r.push_str(&catalog.message(
+                        Message::AlsoRemove,
+                        &[("cells", &removed), ("value", &value)],
+                    ));
+                }
+                r
+            }
+            Technique::Kropki | Technique::Xv => {
+                let message = match self.relation {
+                    Some(Relation::Consecutive) => Message::Consecutive,
+                    Some(Relation::Double) => Message::Double,
+                    Some(Relation::NotConsecutive) => Message::NotConsecutive,
+                    Some(Relation::NotDouble) | None => Message::NotDouble,
+                    Some(Relation::Sum(_)) => Message::XvSum,
+                    Some(Relation::NotSum(_)) => Message::NotXvSum,
+                };
+                let sum = match self.relation {
+                    Some(Relation::Sum(sum)) | Some(Relation::NotSum(sum)) => sum,
+                    _ => 0,
+                };
+                let mark = if sum == 5 { "V" } else { "X" };
+                let cell = join_cells(catalog, &self.eliminations);
+                catalog.message(
+                    message,
+                    &[
+                        ("cell", &cell),
+                        ("cells", &cells),
+                        ("values", &values),
+                        ("eliminations", &eliminations),
+                        ("mark", mark),
+                        ("sum", &sum.to_string()),
+                    ],
+                )
+            }
+            Technique::Line => {
+                let message = match self.line {
+                    Some(LineKind::Thermometer) => Message::Thermometer,
+                    Some(LineKind::Arrow) => Message::Arrow,
+                    Some(LineKind::Whisper) => Message::Whisper,
+                    Some(LineKind::Renban) | None => Message::Renban,
+                };
+                let cell = join_cells(catalog, &self.eliminations);
+                // 缺少差值时按9x9盘面
+                let gap = self.gap.unwrap_or(5).to_string();
+                catalog.message(
+                    message,
+                    &[
+                        ("cell", &cell),
+                        ("cells", &cells),
+                        ("eliminations", &eliminations),
+                        ("gap", &gap),
+                    ],
+                )
+            }
+            Technique::Sandwich => catalog.message(
+                Message::Sandwich,
+                &[("base_units", &base_units), ("eliminations", &eliminations)],
+            ),
+            Technique::BruteForce => catalog.message(Message::BruteForce, &[]),
+        }
+    }
+
+    // 将移除的草稿按数字分组，如"R1C1 R1C2 不能填写 5，R1C3 不能填写 6 7"
+    fn describe_eliminations(&self, catalog: &Catalog) -> String {
+        let mut groups: Vec<(Vec<u8>, Vec<RCCoords>)> = vec![];
+        for coords in self.eliminations.iter().map(|cv| cv.coords) {
+            if groups.iter().any(|(_, cells)| cells.contains(&coords)) {
+                continue;
+            }
+            let values: Vec<u8> = self
+                .eliminations
+                .iter()
+                .filter(|cv| cv.coords == coords)
+                .map(|cv| cv.value)
+                .collect();
+            match groups.iter_mut().find(|(vs, _)| *vs == values) {
+                Some((_, cells)) => cells.push(coords),
+                None => groups.push((values, vec![coords])),
+            }
+        }
+        groups
+            .iter()
+            .map(|(values, cells)| {
+                let cells = cells
+                    .iter()
+                    .map(|&rc| catalog.format_coords(rc))
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                let values = values
+                    .iter()
+                    .map(|&v| value_to_char(v).to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                catalog.message(
+                    Message::Elimination,
+                    &[("cells", &cells), ("values", &values)],
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(&catalog.message(Message::Separator, &[]))
+    }
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.describe())
+    }
+}
+
+fn flatten(cvs: &[TheCoordsAndTheValue]) -> Vec<CellValue> {
+    cvs.iter()
+        .flat_map(|cv| {
+            cv.the_value.iter().map(|&value| CellValue {
+                coords: cv.the_coords.into(),
+                value,
+            })
+        })
+        .collect()
+}
+
+// 去重后的格子坐标，保持原有顺序
+fn join_cells(catalog: &Catalog, cvs: &[CellValue]) -> String {
+    let mut cells: Vec<RCCoords> = vec![];
+    for cv in cvs {
+        if !cells.contains(&cv.coords) {
+            cells.push(cv.coords);
+        }
+    }
+    cells
+        .iter()
+        .map(|&rc| catalog.format_coords(rc))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// 去重后的数字，从小到大，与盘面的写法相同
+fn join_values(cvs: &[CellValue]) -> String {
+    let mut values: Vec<u8> = cvs.iter().map(|cv| cv.value).collect();
+    values.sort();
+    values.dedup();
+    values
+        .iter()
+        .map(|&v| value_to_char(v).to_string())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn join_units(catalog: &Catalog, units: &[Unit]) -> String {
+    units
+        .iter()
+        .map(|&u| catalog.format_unit(u))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,502 @@
+// 第三方格式的导入导出：Simple Sudoku的.ss、SadMan Software的.sdk/.sdx、HoDoKu题库行、
+// f-puzzles和SudokuWiki的链接。格式本身不区分的信息（如.ss中的填写值）在导出时丢弃，
+// 导入时没有草稿信息的格式按同行列宫已有的数值重新计算草稿
+// 这些格式都只用于标准9x9数独
+
+use serde_json::{json, Value};
+
+use crate::{
+    explanation::{CellValue, Role},
+    inferences::{InferenceSet, Technique},
+    rules::Rules,
+    types::{Candidate, CellStatus, RCCoords, Sudoku},
+};
+
+const FPUZZLES_URL: &str = "https://www.f-puzzles.com/?load=";
+const SUDOKUWIKI_URL: &str = "https://www.sudokuwiki.org/sudoku.htm?bd=";
+
+// 导入时一个格子的内容，value从0开始，candidates为None时重新计算
+struct CellInput {
+    value: Option<u8>,
+    given: bool,
+    candidates: Option<Candidate>,
+}
+
+impl CellInput {
+    fn given(value: u8) -> CellInput {
+        CellInput {
+            value: Some(value),
+            given: true,
+            candidates: None,
+        }
+    }
+
+    fn empty() -> CellInput {
+        CellInput {
+            value: None,
+            given: false,
+            candidates: None,
+        }
+    }
+}
+
+// 由81个格子的内容构造数独
+fn build(cells: Vec<CellInput>) -> Result<Sudoku, String> {
+    if cells.len() != 81 {
+        return Err(format!("Invalid Cell Count: {}", cells.len()));
+    }
+    let digits = |given: bool| -> String {
+        cells
+            .iter()
+            .map(|cell| match cell.value {
+                Some(v) if cell.given == given => char::from(b'1' + v),
+                _ => '0',
+            })
+            .collect()
+    };
+    let mut field = Sudoku::from_compact_string(&format!("{}:{}", digits(true), digits(false)))?;
+    for (idx, input) in cells.iter().enumerate() {
+        let rc = RCCoords::from_idx(idx);
+        if field.get_cell_ref_by_rc(rc).status == CellStatus::DRAFT {
+            let candidates = input
+                .candidates
+                .unwrap_or_else(|| basic_candidates(&field, rc));
+            field.get_cell_mut_by_rc(rc).candidates = candidates;
+        }
+    }
+    Ok(field)
+}
+
+// 排除同行列宫已有数值后的草稿
+fn basic_candidates(field: &Sudoku, rc: RCCoords) -> Candidate {
+    let mut candidates = Candidate::FULL;
+    for p in field.collect_peers_coords(field.get_cell_ref_by_rc(rc).coords) {
+        let peer = field.get_cell_ref_by_coords(p);
+        if let (CellStatus::FIXED | CellStatus::SOLVE, Some(v)) = (peer.status, peer.value) {
+            candidates.remove(v);
+        }
+    }
+    candidates
+}
+
+// 解析.ss/.sdk的网格部分：1-9为给定数，.、0、X、_为空格，分隔线和其他字符忽略
+fn parse_grid(lines: &[&str]) -> Result<Vec<CellInput>, String> {
+    let cells: Vec<CellInput> = lines
+        .iter()
+        .flat_map(|line| line.chars())
+        .filter_map(|ch| match ch {
+            '1'..='9' => Some(CellInput::given(ch as u8 - b'1')),
+            '.' | '0' | 'X' | 'x' | '_' => Some(CellInput::empty()),
+            _ => None,
+        })
+        .collect();
+    if cells.len() != 81 {
+        return Err(format!("Invalid Cell Count: {}", cells.len()));
+    }
+    Ok(cells)
+}
+
+// 导出的格式都只能表示标准9x9数独，其他尺寸和变体规则导出会丢失信息，直接报错
+fn check_classic(field: &Sudoku) -> Result<(), String> {
+    if *field.rules() != Rules::classic() {
+        return Err("Only Classic 9x9 Grids Are Supported.".to_string());
+    }
+    Ok(())
+}
+
+// 给定数的一行，空格用.表示
+fn givens_row(field: &Sudoku, r: usize) -> Vec<char> {
+    (0..9)
+        .map(|c| {
+            let cell = field.get_cell_ref_by_rc(RCCoords { r, c });
+            match (cell.status, cell.value) {
+                (CellStatus::FIXED, Some(v)) => char::from(b'1' + v),
+                _ => '.',
+            }
+        })
+        .collect()
+}
+
+// HoDoKu中一个草稿的写法：数字、行、列，均从1开始
+fn format_candidate(rc: RCCoords, value: u8) -> String {
+    format!("{}{}{}", value + 1, rc.r + 1, rc.c + 1)
+}
+
+fn hodoku_code(technique: Technique, size: usize) -> Option<&'static str> {
+    Some(match (technique, size) {
+        (Technique::HiddenSingle, _) => "0002",
+        (Technique::NakedSingle, _) => "0003",
+        (Technique::Pointing, _) => "0100",
+        (Technique::Claiming, _) => "0101",
+        (Technique::NakedSubset, 2) => "0200",
+        (Technique::NakedSubset, 3) => "0201",
+        (Technique::NakedSubset, 4) => "0202",
+        (Technique::HiddenSubset, 2) => "0210",
+        (Technique::HiddenSubset, 3) => "0211",
+        (Technique::HiddenSubset, 4) => "0212",
+        (Technique::Fish, 2) => "0300",
+        (Technique::Fish, 3) => "0301",
+        (Technique::Fish, 4) => "0302",
+        _ => return None,
+    })
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = s
+            .get(i + 1..i + 3)
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+impl Sudoku {
+    /// Simple Sudoku的.ss格式，只包含给定数
+    pub fn from_ss(input: &str) -> Result<Sudoku, String> {
+        let lines: Vec<&str> = input.lines().collect();
+        build(parse_grid(&lines)?)
+    }
+
+    pub fn to_ss(&self) -> Result<String, String> {
+        check_classic(self)?;
+        let mut lines = vec![];
+        for r in 0..9 {
+            if r == 3 || r == 6 {
+                lines.push("-----------".to_string());
+            }
+            let row = givens_row(self, r);
+            lines.push(
+                row.chunks(3)
+                    .map(|chunk| chunk.iter().collect::<String>())
+                    .collect::<Vec<String>>()
+                    .join("|"),
+            );
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// SadMan Software的.sdk格式，以#开头的注释行会被忽略，有分段时只读取[Puzzle]段
+    pub fn from_sdk(input: &str) -> Result<Sudoku, String> {
+        let mut section: Option<&str> = None;
+        let mut lines = vec![];
+        for line in input.lines().map(str::trim) {
+            if line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                section = Some(line);
+            } else if section.is_none_or(|s| s.eq_ignore_ascii_case("[Puzzle]")) {
+                lines.push(line);
+            }
+        }
+        build(parse_grid(&lines)?)
+    }
+
+    pub fn to_sdk(&self) -> Result<String, String> {
+        check_classic(self)?;
+        Ok((0..9)
+            .map(|r| givens_row(self, r).into_iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    /// SadMan Software的.sdx格式，每格一项：给定数为单个数字，填写值以u开头，草稿格为所有草稿数字
+    /// 只有一个草稿的格子与给定数写法相同，导入时按给定数处理
+    pub fn from_sdx(input: &str) -> Result<Sudoku, String> {
+        let cells = input
+            .split_whitespace()
+            .map(|token| {
+                let (solved, digits) = match token.strip_prefix('u') {
+                    Some(digits) => (true, digits),
+                    None => (false, token),
+                };
+                let values = digits
+                    .chars()
+                    .map(|ch| match ch {
+                        '1'..='9' => Ok(Some(ch as u8 - b'1')),
+                        '0' => Ok(None),
+                        _ => Err(format!("Invalid Character: {}", ch)),
+                    })
+                    .collect::<Result<Vec<Option<u8>>, String>>()?;
+                Ok(match values[..] {
+                    [Some(v)] => CellInput {
+                        value: Some(v),
+                        given: !solved,
+                        candidates: None,
+                    },
+                    _ if solved => return Err(format!("Invalid Cell: {}", token)),
+                    _ => {
+                        let mut candidates = Candidate::default();
+                        values.into_iter().flatten().for_each(|v| candidates.add(v));
+                        CellInput {
+                            value: None,
+                            given: false,
+                            candidates: Some(candidates),
+                        }
+                    }
+                })
+            })
+            .collect::<Result<Vec<CellInput>, String>>()?;
+        build(cells)
+    }
+
+    pub fn to_sdx(&self) -> Result<String, String> {
+        check_classic(self)?;
+        Ok((0..9)
+            .map(|r| {
+                (0..9)
+                    .map(|c| {
+                        let cell = self.get_cell_ref_by_rc(RCCoords { r, c });
+                        match (cell.status, cell.value) {
+                            (CellStatus::FIXED, Some(v)) => (v + 1).to_string(),
+                            (CellStatus::SOLVE, Some(v)) => format!("u{}", v + 1),
+                            _ if cell.candidates.is_empty() => "0".to_string(),
+                            _ => format!("{:?}", cell.candidates),
+                        }
+                    })
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    /// HoDoKu题库行：`:策略:数字:盘面:已删除的草稿:排除:填写:`，盘面中+号标记填写值，
+    /// 已删除的草稿为同行列宫已有数值之外被删除的草稿，只使用盘面和已删除的草稿两段
+    pub fn from_hodoku_line(input: &str) -> Result<Sudoku, String> {
+        let parts: Vec<&str> = input.trim().split(':').collect();
+        if parts.len() < 5 || !parts[0].is_empty() {
+            return Err("Invalid HoDoKu Line.".to_string());
+        }
+
+        let mut cells = vec![];
+        let mut solved = false;
+        for ch in parts[3].chars() {
+            match ch {
+                '+' => solved = true,
+                '1'..='9' => {
+                    cells.push(CellInput {
+                        value: Some(ch as u8 - b'1'),
+                        given: !solved,
+                        candidates: None,
+                    });
+                    solved = false;
+                }
+                '.' | '0' => cells.push(CellInput::empty()),
+                _ => return Err(format!("Invalid Character: {}", ch)),
+            }
+        }
+        let mut field = build(cells)?;
+
+        for token in parts[4].split_whitespace() {
+            let digits: Vec<usize> = token
+                .chars()
+                .filter_map(|ch| ch.to_digit(10).map(|d| d as usize))
+                .collect();
+            match digits[..] {
+                [v @ 1..=9, r @ 1..=9, c @ 1..=9] if token.len() == 3 => {
+                    let rc = RCCoords { r: r - 1, c: c - 1 };
+                    field.get_cell_mut_by_rc(rc).candidates.remove(v as u8 - 1);
+                }
+                _ => return Err(format!("Invalid Candidate: {}", token)),
+            }
+        }
+        Ok(field)
+    }
+
+    /// 导出为HoDoKu题库行，策略、数字、排除和填写取自下一步推理，无法用HoDoKu的策略表示时这几段为空
+    pub fn to_hodoku_line(&self) -> Result<String, String> {
+        check_classic(self)?;
+        let grid: String = (0..81)
+            .map(|idx| self.get_cell_ref_by_rc(RCCoords::from_idx(idx)))
+            .map(|cell| match (cell.status, cell.value) {
+                (CellStatus::FIXED, Some(v)) => (v + 1).to_string(),
+                (CellStatus::SOLVE, Some(v)) => format!("+{}", v + 1),
+                _ => ".".to_string(),
+            })
+            .collect();
+        let deleted: Vec<String> = (0..81)
+            .map(RCCoords::from_idx)
+            .filter(|&rc| self.get_cell_ref_by_rc(rc).status == CellStatus::DRAFT)
+            .flat_map(|rc| {
+                basic_candidates(self, rc)
+                    .subtract(&self.get_cell_ref_by_rc(rc).candidates)
+                    .to_vec()
+                    .into_iter()
+                    .map(move |v| format_candidate(rc, v))
+            })
+            .collect();
+
+        let ifs = InferenceSet::new();
+        let step = ifs.analyze(self).and_then(|result| {
+            let explanation = result.explain();
+            let mut values: Vec<u8> = match result.technique() {
+                Technique::NakedSingle | Technique::HiddenSingle => {
+                    explanation.placements.iter().map(|cv| cv.value).collect()
+                }
+                _ => explanation
+                    .with_role(Role::Base)
+                    .iter()
+                    .map(|cv| cv.value)
+                    .collect(),
+            };
+            values.sort();
+            values.dedup();
+            let size = match result.technique() {
+                Technique::Fish => explanation.base_units.len(),
+                _ => values.len(),
+            };
+            let code = hodoku_code(result.technique(), size)?;
+            let join = |cvs: &[CellValue]| {
+                cvs.iter()
+                    .map(|cv| format_candidate(cv.coords, cv.value))
+                    .collect::<Vec<String>>()
+                    .join(" ")
+            };
+            Some([
+                code.to_string(),
+                values.iter().map(|v| (v + 1).to_string()).collect(),
+                join(&explanation.eliminations),
+                join(&explanation.placements),
+            ])
+        });
+        let [code, values, eliminations, placements] = step.unwrap_or_default();
+        Ok(format!(
+            ":{}:{}:{}:{}:{}:{}:",
+            code,
+            values,
+            grid,
+            deleted.join(" "),
+            eliminations,
+            placements
+        ))
+    }
+
+    /// 从f-puzzles（?load=）或SudokuWiki（?bd=）的链接导入
+    pub fn from_url(url: &str) -> Result<Sudoku, String> {
+        let param = |name: &str| {
+            url.split(['?', '&'])
+                .find_map(|p| p.strip_prefix(name))
+                .map(percent_decode)
+        };
+        if let Some(data) = param("load=") {
+            let json = lz_str::decompress_from_base64(&data)
+                .and_then(|json| String::from_utf16(&json).ok())
+                .ok_or_else(|| "Invalid F-Puzzles Data.".to_string())?;
+            Sudoku::from_fpuzzles_json(&json)
+        } else if let Some(board) = param("bd=") {
+            let lines = [board.as_str()];
+            build(parse_grid(&lines)?)
+        } else {
+            Err(format!("Unsupported Url: {}", url))
+        }
+    }
+
+    fn from_fpuzzles_json(json: &str) -> Result<Sudoku, String> {
+        let value: Value = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        if value["size"].as_u64() != Some(9) {
+            return Err("Only 9x9 F-Puzzles Grids Are Supported.".to_string());
+        }
+        let rows = value["grid"]
+            .as_array()
+            .filter(|rows| rows.len() == 9)
+            .ok_or_else(|| "Invalid F-Puzzles Grid.".to_string())?;
+
+        let marks = |cell: &Value| -> Option<Candidate> {
+            let marks = cell["centerPencilMarks"]
+                .as_array()
+                .or(cell["candidates"].as_array())?;
+            let mut candidates = Candidate::default();
+            for v in marks.iter().filter_map(Value::as_u64) {
+                if (1..=9).contains(&v) {
+                    candidates.add(v as u8 - 1);
+                }
+            }
+            Some(candidates)
+        };
+        // 任何一格有草稿时按草稿导入，否则重新计算
+        let has_marks = rows
+            .iter()
+            .filter_map(Value::as_array)
+            .flatten()
+            .any(|cell| marks(cell).is_some());
+
+        let mut cells = vec![];
+        for row in rows {
+            let row = row
+                .as_array()
+                .filter(|row| row.len() == 9)
+                .ok_or_else(|| "Invalid F-Puzzles Grid.".to_string())?;
+            for cell in row {
+                let value = match cell["value"].as_u64() {
+                    Some(v @ 1..=9) => Some(v as u8 - 1),
+                    Some(v) => return Err(format!("Invalid Value: {}", v)),
+                    None => None,
+                };
+                cells.push(CellInput {
+                    value,
+                    given: cell["given"].as_bool().unwrap_or(false),
+                    candidates: has_marks.then(|| marks(cell).unwrap_or_default()),
+                });
+            }
+        }
+        build(cells)
+    }
+
+    /// f-puzzles链接，填写值不标记为给定数；草稿与自动计算的结果不同时以中心标记导出
+    pub fn to_fpuzzles_url(&self) -> Result<String, String> {
+        check_classic(self)?;
+        let with_marks = (0..81).map(RCCoords::from_idx).any(|rc| {
+            let cell = self.get_cell_ref_by_rc(rc);
+            cell.status == CellStatus::DRAFT && cell.candidates != basic_candidates(self, rc)
+        });
+        let grid: Vec<Vec<Value>> = (0..9)
+            .map(|r| {
+                (0..9)
+                    .map(|c| {
+                        let cell = self.get_cell_ref_by_rc(RCCoords { r, c });
+                        match (cell.status, cell.value) {
+                            (CellStatus::FIXED, Some(v)) => json!({"value": v + 1, "given": true}),
+                            (CellStatus::SOLVE, Some(v)) => json!({ "value": v + 1 }),
+                            _ if with_marks => {
+                                let marks: Vec<u8> =
+                                    cell.candidates.to_vec().iter().map(|v| v + 1).collect();
+                                json!({ "centerPencilMarks": marks })
+                            }
+                            _ => json!({}),
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        let json = json!({ "size": 9, "grid": grid }).to_string();
+        Ok(format!(
+            "{}{}",
+            FPUZZLES_URL,
+            lz_str::compress_to_base64(json.as_str())
+        ))
+    }
+
+    /// SudokuWiki链接，只包含给定数
+    pub fn to_sudokuwiki_url(&self) -> Result<String, String> {
+        check_classic(self)?;
+        let board: String = (0..9)
+            .flat_map(|r| givens_row(self, r))
+            .map(|ch| if ch == '.' { '0' } else { ch })
+            .collect();
+        Ok(format!("{}{}", SUDOKUWIKI_URL, board))
+    }
+}
@@ -0,0 +1,276 @@
+// 游戏对局：记录玩家的每一步操作，支持撤销/重做，以及保存书签并回到书签处继续
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    inferences::{InferenceSet, Technique},
+    rules::Rules,
+    types::{Cell, CellStatus, Difficulty, RCCoords, Sudoku},
+    validator::Mistake,
+};
+
+/// 玩家的一次操作
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    SetValue { coords: RCCoords, value: u8 },
+    ClearValue { coords: RCCoords },
+    AddCandidate { coords: RCCoords, value: u8 },
+    RemoveCandidate { coords: RCCoords, value: u8 },
+    ApplyHint { technique: Technique },
+}
+
+/// 一次操作引起的单元格变化，撤销时还原为before，重做时设置为after
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CellChange {
+    pub before: Cell,
+    pub after: Cell,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub action: Action,
+    pub changes: Vec<CellChange>,
+    // 开启提前检查时，标记这一步是否让数独从有解变为无解
+    #[serde(default)]
+    pub breaks_solution: bool,
+}
+
+/// 书签，保存当时的盘面和操作记录，回到书签后可以从该处开始新的分支
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    field: Sudoku,
+    undo_stack: Vec<HistoryEntry>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[wasm_bindgen]
+pub struct Game {
+    field: Sudoku,
+    solution: Sudoku,
+    undo_stack: Vec<HistoryEntry>,
+    redo_stack: Vec<HistoryEntry>,
+    bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    detect_early: bool,
+}
+
+impl Game {
+    /// 从题目创建对局，题目必须有唯一解
+    pub fn new(puzzle: Sudoku) -> Result<Game, String> {
+        let mut solutions = puzzle.sovle();
+        if solutions.len() != 1 {
+            return Err("Puzzle Must Have A Unique Solution.".to_string());
+        }
+        Ok(Game {
+            field: puzzle,
+            solution: solutions.remove(0),
+            undo_stack: vec![],
+            redo_stack: vec![],
+            bookmarks: vec![],
+            detect_early: false,
+        })
+    }
+
+    /// 按难度随机生成题目并创建对局
+    pub fn generate(difficulty: Difficulty) -> Game {
+        Game::new(Sudoku::new(difficulty)).expect("Generated Puzzle Must Be Unique.")
+    }
+
+    /// 按指定的规则和难度随机生成题目并创建对局
+    pub fn generate_with(rules: Rules, difficulty: Difficulty) -> Game {
+        Game::new(Sudoku::generate(rules, difficulty)).expect("Generated Puzzle Must Be Unique.")
+    }
+
+    pub fn field(&self) -> &Sudoku {
+        &self.field
+    }
+
+    pub fn solution(&self) -> &Sudoku {
+        &self.solution
+    }
+
+    pub fn history(&self) -> &[HistoryEntry] {
+        &self.undo_stack
+    }
+
+    /// 所有格子都已填写且与答案一致
+    pub fn is_solved(&self) -> bool {
+        self.field.iter_rc().all(|rc| {
+            let cell = self.field.get_cell_ref_by_rc(rc);
+            cell.status != CellStatus::DRAFT
+                && cell.value == self.solution.get_cell_ref_by_rc(rc).value
+        })
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    // 执行一次修改，对比修改前后的盘面记录变化；没有任何变化的操作不记录
+    fn perform<F>(&mut self, action: Action, f: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Sudoku) -> Result<(), String>,
+    {
+        let mut field = self.field.clone();
+        f(&mut field)?;
+
+        let changes: Vec<CellChange> = field
+            .iter_rc()
+            .filter_map(|rc| {
+                let before = *self.field.get_cell_ref_by_rc(rc);
+                let after = *field.get_cell_ref_by_rc(rc);
+                (before != after).then_some(CellChange { before, after })
+            })
+            .collect();
+
+        if !changes.is_empty() {
+            // 题目解唯一，所以与答案对比没有错误等价于仍然有解
+            let breaks_solution = self.detect_early
+                && self.field.check_against(&self.solution).is_empty()
+                && !field.check_against(&self.solution).is_empty();
+            self.field = field;
+            self.undo_stack.push(HistoryEntry {
+                action,
+                changes,
+                breaks_solution,
+            });
+            self.redo_stack.clear();
+        }
+        Ok(())
+    }
+
+    /// 开启后，每一步操作都会检查是否让数独变为无解，并在操作记录中标记
+    pub fn set_detect_early(&mut self, detect_early: bool) {
+        self.detect_early = detect_early;
+    }
+
+    pub fn detect_early(&self) -> bool {
+        self.detect_early
+    }
+
+    /// 检查当前进度，返回填错的格子和排除了正确答案的草稿，不会透露其他格子的答案
+    pub fn check(&self) -> Vec<Mistake> {
+        self.field.check_against(&self.solution)
+    }
+
+    /// 被标记为让数独变为无解的操作在操作记录中的下标
+    pub fn flagged_moves(&self) -> Vec<usize> {
+        self.undo_stack
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.breaks_solution)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// 填写数值，同时更新同行列宫的草稿
+    pub fn set_value(&mut self, coords: RCCoords, value: u8) -> Result<(), String> {
+        self.perform(Action::SetValue { coords, value }, |field| {
+            field.place(coords, value)
+        })
+    }
+
+    /// 清除填写值，重新计算草稿时不恢复手动排除的草稿
+    pub fn clear_value(&mut self, coords: RCCoords) -> Result<(), String> {
+        self.perform(Action::ClearValue { coords }, |field| {
+            field.unplace(coords, false)
+        })
+    }
+
+    pub fn add_candidate(&mut self, coords: RCCoords, value: u8) -> Result<(), String> {
+        self.perform(Action::AddCandidate { coords, value }, |field| {
+            field.restore_candidate(coords, value)
+        })
+    }
+
+    pub fn remove_candidate(&mut self, coords: RCCoords, value: u8) -> Result<(), String> {
+        self.perform(Action::RemoveCandidate { coords, value }, |field| {
+            field.eliminate_candidate(coords, value)
+        })
+    }
+
+    /// 执行下一步提示，返回使用的策略，无法推导时返回None
+    pub fn apply_hint(&mut self) -> Option<Technique> {
+        let ifs = InferenceSet::new();
+        let old_field = self.field.clone();
+        let result = ifs.analyze(&old_field)?;
+        let technique = result.technique();
+        self.perform(Action::ApplyHint { technique }, |field| {
+            InferenceSet::apply(field, result);
+            Ok(())
+        })
+        .ok()?;
+        Some(technique)
+    }
+
+    /// 撤销上一步操作，没有可撤销的操作时返回None
+    pub fn undo(&mut self) -> Option<Action> {
+        let entry = self.undo_stack.pop()?;
+        for change in &entry.changes {
+            restore_cell(&mut self.field, &change.before);
+        }
+        let action = entry.action.clone();
+        self.redo_stack.push(entry);
+        Some(action)
+    }
+
+    /// 重做上一次撤销的操作，没有可重做的操作时返回None
+    pub fn redo(&mut self) -> Option<Action> {
+        let entry = self.redo_stack.pop()?;
+        for change in &entry.changes {
+            restore_cell(&mut self.field, &change.after);
+        }
+        let action = entry.action.clone();
+        self.undo_stack.push(entry);
+        Some(action)
+    }
+
+    /// 在当前位置保存书签，同名书签会被覆盖
+    pub fn add_bookmark(&mut self, name: &str) {
+        self.bookmarks.retain(|b| b.name != name);
+        self.bookmarks.push(Bookmark {
+            name: name.to_string(),
+            field: self.field.clone(),
+            undo_stack: self.undo_stack.clone(),
+        });
+    }
+
+    pub fn remove_bookmark(&mut self, name: &str) -> bool {
+        let len = self.bookmarks.len();
+        self.bookmarks.retain(|b| b.name != name);
+        self.bookmarks.len() != len
+    }
+
+    pub fn bookmarks(&self) -> Vec<&str> {
+        self.bookmarks.iter().map(|b| b.name.as_str()).collect()
+    }
+
+    /// 回到书签处，书签之后的操作不能再重做，但其他书签仍然保留
+    pub fn restore_bookmark(&mut self, name: &str) -> Result<(), String> {
+        let bookmark = self
+            .bookmarks
+            .iter()
+            .find(|b| b.name == name)
+            .ok_or_else(|| format!("Bookmark Not Found: {}", name))?;
+        self.field = bookmark.field.clone();
+        self.undo_stack = bookmark.undo_stack.clone();
+        self.redo_stack.clear();
+        Ok(())
+    }
+}
+
+// 把记录的格子内容写回盘面，只还原状态、数值和草稿，保留盘面自己的坐标：
+// 反序列化得到的记录按9x9重新计算宫号，其他尺寸和锯齿数独的宫号不可靠
+fn restore_cell(field: &mut Sudoku, saved: &Cell) {
+    let cell = field.get_cell_mut_by_coords(saved.coords);
+    cell.status = saved.status;
+    cell.value = saved.value;
+    cell.candidates = saved.candidates;
+    cell.eliminated = saved.eliminated;
+}
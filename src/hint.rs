@@ -0,0 +1,76 @@
+// 分级提示：每次只透露一部分推理结果，玩家可以按需逐级获取更多帮助
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{
+    inferences::{InferenceResult, InferenceSet, Technique, TheCoordsAndTheValue},
+    types::{Sudoku, Unit},
+};
+
+/// 提示等级，后一级包含前一级的全部内容
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[wasm_bindgen]
+pub enum HintLevel {
+    // 需要观察的行/列/宫
+    Unit,
+    // 使用的策略
+    Technique,
+    // 构成条件的格子和数值
+    Condition,
+    // 完整的结论和文字说明
+    Conclusion,
+}
+
+impl HintLevel {
+    pub fn next(&self) -> Option<HintLevel> {
+        match self {
+            HintLevel::Unit => Some(HintLevel::Technique),
+            HintLevel::Technique => Some(HintLevel::Condition),
+            HintLevel::Condition => Some(HintLevel::Conclusion),
+            HintLevel::Conclusion => None,
+        }
+    }
+}
+
+/// 按等级裁剪后的提示，未到等级的部分为None
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Hint {
+    pub level: HintLevel,
+    pub units: Vec<Unit>,
+    pub technique: Option<Technique>,
+    pub condition: Option<Vec<TheCoordsAndTheValue>>,
+    pub conclusion_set_value: Option<Vec<TheCoordsAndTheValue>>,
+    pub conclusion_remove_drafts: Option<Vec<TheCoordsAndTheValue>>,
+    pub explanation: Option<String>,
+}
+
+impl InferenceResult<'_> {
+    pub fn hint(&self, level: HintLevel) -> Hint {
+        let conclusion = level >= HintLevel::Conclusion;
+        Hint {
+            level,
+            units: self.units(),
+            technique: (level >= HintLevel::Technique).then(|| self.technique()),
+            condition: (level >= HintLevel::Condition).then(|| self.condition().to_vec()),
+            conclusion_set_value: self
+                .conclusion_set_value()
+                .filter(|_| conclusion)
+                .map(|v| v.to_vec()),
+            conclusion_remove_drafts: self
+                .conclusion_remove_drafts()
+                .filter(|_| conclusion)
+                .map(|v| v.to_vec()),
+            explanation: conclusion.then(|| self.explanation()),
+        }
+    }
+}
+
+impl Sudoku {
+    /// 下一步推理的提示，无法推导时返回None
+    pub fn hint(&self, level: HintLevel) -> Option<Hint> {
+        InferenceSet::new()
+            .analyze(self)
+            .map(|result| result.hint(level))
+    }
+}
@@ -1,14 +1,19 @@
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
+
 use crate::{
-    types::{Candidate, Cell, CellStatus, Coords, GNCoords, RCCoords, Sudoku},
+    explanation::{Explanation, Role},
+    locale::Catalog,
+    rules::{LineKind, Relation, Rules},
+    types::{Candidate, Cell, CellStatus, Coords, Difficulty, Sudoku, Unit},
     utils::{
-        create_simple_cell_and_value, get_coords_with_direction, get_rc_coord_with_direction,
+        create_simple_cell_and_value, get_rc_coord_with_direction,
         make_simple_conclusion_when_set_value, IterDirection,
     },
 };
 
 /// 某某策略的结论通常可以归纳为：因为【某个地方的某个值】，导致【某个地方的某个值】，需要做一些什么
 /// 这里定义的是【某个地方的某个值】
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct TheCoordsAndTheValue {
     pub the_coords: Coords,
     pub the_value: Vec<u8>,
@@ -22,14 +27,101 @@ pub struct TheCoordsAndTheValue {
 
 pub struct InferenceResult<'a> {
     inference: &'a dyn Inference,
+    // 盘面边长，说明中与尺寸有关的参数由此计算，如德国悄悄话相邻数字的最小差
+    size: usize,
+    // 按规则的单元列表遍历的策略记录用到的单元，顺序为条件所在单元、结论所在单元，其他策略为空
+    units: Vec<Unit>,
     condition: Vec<TheCoordsAndTheValue>,
     conclusion_set_value: Option<Vec<TheCoordsAndTheValue>>,
     conclusion_remove_drafts: Option<Vec<TheCoordsAndTheValue>>,
 }
 
+/// 推理策略的种类，行/列/宫等不同方向的同一种策略归为一类
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Technique {
+    // 唯余法
+    NakedSingle,
+    // 排除法
+    HiddenSingle,
+    // 宫内草稿只在同一行/列，排除该行/列的其他格
+    Pointing,
+    // 行/列内草稿只在同一宫，排除该宫的其他格
+    Claiming,
+    // 显性数对
+    NakedSubset,
+    // 隐性数对
+    HiddenSubset,
+    // n阶鱼
+    Fish,
+    // 杀手数独：笼子和只能由某些数字组合得到
+    CageCombination,
+    // 杀手数独：单元与完全在其内（或覆盖它）的笼子之和只相差一个或两个格子
+    Rule45,
+    // 非连续数独和克罗普基圆点：相邻格子的数字关系
+    Kropki,
+    // 温度计、箭头、德国悄悄话和连号线：线上数字之间的关系
+    Line,
+    // XV数独：相邻两格之和为10或5
+    Xv,
+    // 三明治数独：行（列）中1和9之间的数字之和
+    Sandwich,
+    // 暴力破解
+    BruteForce,
+}
+
+impl Technique {
+    /// 策略的难度分值，用于评估整个数独的难度
+    pub fn score(&self) -> u32 {
+        match self {
+            Technique::HiddenSingle => 10,
+            Technique::NakedSingle => 15,
+            Technique::CageCombination => 20,
+            Technique::Kropki | Technique::Line | Technique::Xv => 25,
+            Technique::Pointing | Technique::Claiming => 30,
+            Technique::Rule45 | Technique::Sandwich => 35,
+            Technique::NakedSubset => 40,
+            Technique::HiddenSubset => 50,
+            Technique::Fish => 80,
+            Technique::BruteForce => 200,
+        }
+    }
+
+    /// 只用到该策略及更简单的策略时，数独对应的难度
+    pub fn difficulty(&self) -> Difficulty {
+        match self {
+            Technique::HiddenSingle => Difficulty::EASY,
+            Technique::NakedSingle
+            | Technique::CageCombination
+            | Technique::Kropki
+            | Technique::Line
+            | Technique::Xv => Difficulty::NORMAL,
+            Technique::Pointing | Technique::Claiming | Technique::Rule45 | Technique::Sandwich => {
+                Difficulty::MIDIUM
+            }
+            Technique::NakedSubset | Technique::HiddenSubset => Difficulty::HARD,
+            Technique::Fish | Technique::BruteForce => Difficulty::EXPERT,
+        }
+    }
+}
+
+/// 用InferenceSet逐步推导整个数独后得到的评分
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rating {
+    // 是否只靠推理策略推导完毕，用到暴力破解、多解或出现矛盾时为false
+    pub solved: bool,
+    pub steps: usize,
+    // 所有步骤分值之和
+    pub score: u32,
+    // 用到的最难策略
+    pub hardest: Option<Technique>,
+    pub difficulty: Option<Difficulty>,
+}
+
 trait Inference {
+    fn technique(&self) -> Technique;
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>>;
-    fn write_result(&self, inference_result: &InferenceResult) -> String;
+    // 结构化说明，文字说明和需要观察的单元都由它得到
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation;
 }
 
 pub struct InferenceSet {
@@ -44,16 +136,60 @@ impl InferenceSet {
                 Box::new(OnlyOneRightInRowInference),
                 Box::new(OnlyOneRightInColInference),
                 Box::new(OnlyOneRightInGridInference),
+                Box::new(OnlyOneRightInExtraUnitInference),
+                Box::new(CageCombinationInference),
+                Box::new(Rule45Inference),
+                Box::new(RelationInference {
+                    relation: Relation::Consecutive,
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::Double,
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::NotConsecutive,
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::NotDouble,
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::Sum(10),
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::Sum(5),
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::NotSum(10),
+                }),
+                Box::new(RelationInference {
+                    relation: Relation::NotSum(5),
+                }),
+                Box::new(LineInference {
+                    kind: LineKind::Thermometer,
+                }),
+                Box::new(LineInference {
+                    kind: LineKind::Arrow,
+                }),
+                Box::new(LineInference {
+                    kind: LineKind::Whisper,
+                }),
+                Box::new(LineInference {
+                    kind: LineKind::Renban,
+                }),
+                Box::new(SandwichInference),
                 Box::new(RowUniqueDraftByGridExclusionInference),
                 Box::new(ColUniqueDraftByGridExclusionInference),
+                Box::new(ExtraUnitLockedCandidatesInference { pointing: true }),
                 Box::new(GridUniqueDraftByRowExclusionInference),
                 Box::new(GridUniqueDraftByColExclusionInference),
+                Box::new(ExtraUnitLockedCandidatesInference { pointing: false }),
                 Box::new(RowExplicitNakedPairExclusionInference),
                 Box::new(ColExplicitNakedPairExclusionInference),
                 Box::new(GridExplicitNakedPairExclusionInference),
+                Box::new(ExtraUnitNakedPairExclusionInference),
                 Box::new(RowExplicitHiddenPairExclusionInference),
                 Box::new(ColExplicitHiddenPairExclusionInference),
                 Box::new(GridExplicitHiddenPairExclusionInference),
+                Box::new(ExtraUnitHiddenPairExclusionInference),
                 Box::new(NStepFishInference),
                 Box::new(ExploitInference),
             ],
@@ -65,29 +201,123 @@ impl InferenceSet {
     }
 
     pub fn apply(field: &mut Sudoku, result: InferenceResult) {
-        if result.conclusion_set_value.is_some() {
-            result.conclusion_set_value.unwrap().iter().for_each(|cv| {
+        if let Some(conclusion_set_value) = result.conclusion_set_value {
+            conclusion_set_value.iter().for_each(|cv| {
                 let p = field.get_cell_mut_by_coords(cv.the_coords);
                 p.value = Some(cv.the_value[0]);
                 p.status = CellStatus::SOLVE;
             })
         };
-        if result.conclusion_remove_drafts.is_some() {
-            result
-                .conclusion_remove_drafts
-                .unwrap()
-                .iter()
-                .for_each(|cv| {
-                    let p = field.get_cell_mut_by_coords(cv.the_coords);
-                    cv.the_value.iter().for_each(|&v| p.candidates.remove(v));
-                })
+        if let Some(conclusion_remove_drafts) = result.conclusion_remove_drafts {
+            conclusion_remove_drafts.iter().for_each(|cv| {
+                let p = field.get_cell_mut_by_coords(cv.the_coords);
+                cv.the_value.iter().for_each(|&v| p.candidates.remove(v));
+            })
         }
     }
 }
 
+impl InferenceSet {
+    /// 从当前盘面开始逐步推导，直到完成或无法继续，统计用到的策略
+    pub fn rate(&self, field: &Sudoku) -> Rating {
+        let mut field = field.clone();
+        let mut rating = Rating {
+            solved: false,
+            steps: 0,
+            score: 0,
+            hardest: None,
+            difficulty: None,
+        };
+        while !field.check_if_finish() {
+            let old_field = field.clone();
+            let Some(result) = self.analyze(&old_field) else {
+                return rating;
+            };
+            let technique = result.technique();
+            rating.steps += 1;
+            rating.score += technique.score();
+            if rating.hardest.is_none_or(|t| t.score() < technique.score()) {
+                rating.hardest = Some(technique);
+            }
+            InferenceSet::apply(&mut field, result);
+            if field.find_conflict().is_some() {
+                return rating;
+            }
+        }
+        // 最后一个策略是暴力破解，用到它就不算靠推理解出
+        rating.solved = rating.hardest != Some(Technique::BruteForce);
+        rating.difficulty = rating.hardest.map(|t| t.difficulty());
+        rating
+    }
+}
+
+impl Default for InferenceSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InferenceResult<'_> {
+    pub fn technique(&self) -> Technique {
+        self.inference.technique()
+    }
+
+    pub fn explain(&self) -> Explanation {
+        self.inference.explain(self)
+    }
+
+    /// 推理时需要观察的单元
+    pub fn units(&self) -> Vec<Unit> {
+        self.explain().units()
+    }
+
+    pub fn condition(&self) -> &[TheCoordsAndTheValue] {
+        &self.condition
+    }
+
+    pub fn conclusion_set_value(&self) -> Option<&[TheCoordsAndTheValue]> {
+        self.conclusion_set_value.as_deref()
+    }
+
+    pub fn conclusion_remove_drafts(&self) -> Option<&[TheCoordsAndTheValue]> {
+        self.conclusion_remove_drafts.as_deref()
+    }
+
+    /// 完整的文字说明
+    pub fn explanation(&self) -> String {
+        self.explain().describe()
+    }
+
+    /// 通用记法，如"X-Wing: 5 r26 c37 => r4c3<>5"
+    pub fn notation(&self) -> String {
+        self.explain().to_notation()
+    }
+
+    /// 指定语言的文字说明
+    pub fn explanation_with(&self, catalog: &Catalog) -> String {
+        self.explain().describe_with(catalog)
+    }
+}
+
+// 推理结果只支持序列化，额外输出策略种类、结构化说明、文字说明和通用记法，方便前端直接展示
+impl Serialize for InferenceResult<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("InferenceResult", 8)?;
+        state.serialize_field("technique", &self.technique())?;
+        state.serialize_field("units", &self.units())?;
+        state.serialize_field("condition", &self.condition)?;
+        state.serialize_field("conclusion_set_value", &self.conclusion_set_value)?;
+        state.serialize_field("conclusion_remove_drafts", &self.conclusion_remove_drafts)?;
+        state.serialize_field("details", &self.explain())?;
+        state.serialize_field("explanation", &self.explanation())?;
+        state.serialize_field("notation", &self.notation())?;
+        state.end()
+    }
+}
+
 impl<'a> std::fmt::Debug for InferenceResult<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self.inference.write_result(&self))
+        write!(f, "{}", self.explanation())
     }
 }
 
@@ -95,6 +325,10 @@ impl<'a> std::fmt::Debug for InferenceResult<'a> {
 #[derive(Clone)]
 struct OnlyOneLeftInference;
 impl Inference for OnlyOneLeftInference {
+    fn technique(&self) -> Technique {
+        Technique::NakedSingle
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.collect_all_drafts_cells().iter().find_map(|&p| {
             p.candidates.get_unique_candidate().map(|cv| {
@@ -104,6 +338,8 @@ impl Inference for OnlyOneLeftInference {
                 };
                 InferenceResult {
                     inference: self,
+                    size: field.size(),
+                    units: vec![],
                     condition: vec![condition.clone()],
                     conclusion_set_value: Some(vec![condition.clone()]),
                     conclusion_remove_drafts: make_simple_conclusion_when_set_value(
@@ -116,35 +352,24 @@ impl Inference for OnlyOneLeftInference {
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let condition = &inference_result.condition[0];
-        let mut r = format!(
-            "{:?} 的可能 {:?} 在格内唯一，因此 {:?} 只能填写 {:?} ",
-            Into::<RCCoords>::into(condition.the_coords),
-            condition.the_value[0],
-            Into::<RCCoords>::into(condition.the_coords),
-            condition.the_value[0]
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            r.push_str(&format!(
-                "，并移除 {} 的可能 {:?}",
-                conclusion_remove_drafts
-                    .iter()
-                    .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                    .collect::<Vec<String>>()
-                    .join(" "),
-                condition.the_value[0]
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Placement,
+            Unit::units_of(coords).to_vec(),
+            vec![],
+        )
     }
 }
 
 /// 按行排除法，每行中如果存在唯一草稿值，则填写该值，同时去除其余同一列宫的草稿值
 struct OnlyOneRightInRowInference;
 impl Inference for OnlyOneRightInRowInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSingle
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_rc().find_map(|vr| {
             vr.iter().find_map(|&p| {
@@ -155,54 +380,44 @@ impl Inference for OnlyOneRightInRowInference {
                         vr.iter()
                             .all(|p_iter| p_iter.coords.c == p.coords.c || !p_iter.candidates.contains(*v))
                     })
-                    .and_then(|&ret| {
+                    .map(|&ret| {
                         let cv = TheCoordsAndTheValue {
                             the_coords: p.coords,
                             the_value: vec![ret],
                         };
-                        Some(InferenceResult {
+                        InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition: vec![cv.clone()],
                             conclusion_set_value: Some(vec![cv]),
                             conclusion_remove_drafts: make_simple_conclusion_when_set_value(
-                                &field, &p.coords, ret,
+                                field, &p.coords, ret,
                             ),
-                        })
+                        }
                     })
             })
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let condition = &inference_result.condition[0];
-        let mut r = format!(
-            "{:?} 的可能 {:?} 在 R{:?} 内唯一，因此 {:?} 只能填写 {:?}",
-            Into::<RCCoords>::into(condition.the_coords),
-            condition.the_value[0],
-            Into::<RCCoords>::into(condition.the_coords).r + 1,
-            Into::<RCCoords>::into(condition.the_coords),
-            condition.the_value[0]
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            r.push_str(&format!(
-                "，并移除 {} 的可能 {:?}",
-                conclusion_remove_drafts
-                    .iter()
-                    .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                    .collect::<Vec<String>>()
-                    .join(" "),
-                condition.the_value[0]
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Placement,
+            vec![Unit::Row(coords.r)],
+            vec![],
+        )
     }
 }
 
 /// 按列排除法，每行中如果存在唯一草稿值，则填写该值，同时去除其余同一列宫的草稿值
 struct OnlyOneRightInColInference;
 impl Inference for OnlyOneRightInColInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSingle
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_cr().find_map(|vc| {
             vc.iter().find_map(|&p| {
@@ -213,54 +428,44 @@ impl Inference for OnlyOneRightInColInference {
                         vc.iter()
                             .all(|p_iter| p_iter.coords.r == p.coords.r || !p_iter.candidates.contains(*v))
                     })
-                    .and_then(|&ret| {
+                    .map(|&ret| {
                         let cv = TheCoordsAndTheValue {
                             the_coords: p.coords,
                             the_value: vec![ret],
                         };
-                        Some(InferenceResult {
+                        InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition: vec![cv.clone()],
                             conclusion_set_value: Some(vec![cv]),
                             conclusion_remove_drafts: make_simple_conclusion_when_set_value(
-                                &field, &p.coords, ret,
+                                field, &p.coords, ret,
                             ),
-                        })
+                        }
                     })
             })
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let condition = &inference_result.condition[0];
-        let mut r = format!(
-            "{:?} 的可能 {:?} 在 C{:?} 内唯一，因此 {:?} 只能填写 {:?}",
-            Into::<RCCoords>::into(condition.the_coords),
-            condition.the_value[0],
-            Into::<RCCoords>::into(condition.the_coords).c + 1,
-            Into::<RCCoords>::into(condition.the_coords),
-            condition.the_value[0]
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            r.push_str(&format!(
-                "，并移除 {} 的可能 {:?}",
-                conclusion_remove_drafts
-                    .iter()
-                    .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                    .collect::<Vec<String>>()
-                    .join(" "),
-                condition.the_value[0]
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Placement,
+            vec![Unit::Column(coords.c)],
+            vec![],
+        )
     }
 }
 
 ///  按宫排除法，每行中如果存在唯一草稿值，则填写该值，同时去除其余同一列宫的草稿值
 struct OnlyOneRightInGridInference;
 impl Inference for OnlyOneRightInGridInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSingle
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_gn().find_map(|vg| {
             vg.iter().find_map(|&p| {
@@ -271,57 +476,47 @@ impl Inference for OnlyOneRightInGridInference {
                         vg.iter()
                             .all(|p_iter| p_iter.coords.n == p.coords.n || !p_iter.candidates.contains(*v))
                     })
-                    .and_then(|&ret| {
+                    .map(|&ret| {
                         let cv = TheCoordsAndTheValue {
                             the_coords: p.coords,
                             the_value: vec![ret],
                         };
-                        Some(InferenceResult {
+                        InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition: vec![cv.clone()],
                             conclusion_set_value: Some(vec![cv]),
                             conclusion_remove_drafts: make_simple_conclusion_when_set_value(
-                                &field, &p.coords, ret,
+                                field, &p.coords, ret,
                             ),
-                        })
+                        }
                     })
             })
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let condition = &inference_result.condition[0];
-        let mut r = format!(
-            "{:?} 的可能 {:?} 在 G{:?} 内唯一，因此 {:?} 只能填写 {:?}",
-            Into::<GNCoords>::into(condition.the_coords),
-            condition.the_value[0],
-            Into::<GNCoords>::into(condition.the_coords).g + 1,
-            Into::<GNCoords>::into(condition.the_coords),
-            condition.the_value[0]
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            r.push_str(&format!(
-                "，并移除 {} 的可能 {:?}",
-                conclusion_remove_drafts
-                    .iter()
-                    .map(|cv| format!("{:?}", Into::<GNCoords>::into(cv.the_coords)))
-                    .collect::<Vec<String>>()
-                    .join(" "),
-                condition.the_value[0]
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Placement,
+            vec![Unit::Grid(coords.g)],
+            vec![],
+        )
     }
 }
 
 /// 当一宫内的某种草稿值当且仅当在同一行时，可以排除该行内其余格子的该草稿值
 struct RowUniqueDraftByGridExclusionInference;
 impl Inference for RowUniqueDraftByGridExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::Pointing
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_gn().find_map(|vg| {
-            (0..9).into_iter().find_map(|v| {
+            (0..field.size() as u8).find_map(|v| {
                 let cells_with_value = vg
                     .iter()
                     .filter(|&p| p.candidates.contains(v))
@@ -357,6 +552,8 @@ impl Inference for RowUniqueDraftByGridExclusionInference {
 
                         Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -371,39 +568,24 @@ impl Inference for RowUniqueDraftByGridExclusionInference {
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let mut r = format!(
-            "{} 的所有可能 {:?} 在 G{:?} 内都只在 R{:?} 中，因此 ",
-            inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<GNCoords>::into(cv.the_coords)))
-                .collect::<Vec<String>>()
-                .join(" "),
-            inference_result.condition[0].the_value[0],
-            Into::<GNCoords>::into(inference_result.condition[0].the_coords).g + 1,
-            Into::<RCCoords>::into(inference_result.condition[0].the_coords).r + 1
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-            r.push_str(&format!(
-                "{} 均不能填写 {:?}",
-                removed_cells.join(" "),
-                inference_result.condition[0].the_value[0]
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Grid(coords.g)],
+            vec![Unit::Row(coords.r)],
+        )
     }
 }
 
 /// 当一宫内的某种草稿值当且仅当在同一列时，可以排除该列内其余格子的该草稿值
 struct ColUniqueDraftByGridExclusionInference;
 impl Inference for ColUniqueDraftByGridExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::Pointing
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_gn().find_map(|vg| {
             (0..0).into_iter().find_map(|v| {
@@ -442,6 +624,8 @@ impl Inference for ColUniqueDraftByGridExclusionInference {
 
                         Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -456,42 +640,27 @@ impl Inference for ColUniqueDraftByGridExclusionInference {
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let mut r = format!(
-            "{} 的所有可能 {:?} 在 G{:?} 内都只在 C{:?} 中，因此 ",
-            inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<GNCoords>::into(cv.the_coords)))
-                .collect::<Vec<String>>()
-                .join(" "),
-            inference_result.condition[0].the_value[0],
-            Into::<GNCoords>::into(inference_result.condition[0].the_coords).g + 1,
-            Into::<RCCoords>::into(inference_result.condition[0].the_coords).c + 1
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-            r.push_str(&format!(
-                "{} 均不能填写 {:?}",
-                removed_cells.join(" "),
-                inference_result.condition[0].the_value[0]
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Grid(coords.g)],
+            vec![Unit::Column(coords.c)],
+        )
     }
 }
 
 /// 当一行的草稿数正好在一宫时，排除该宫的其他草稿数
 struct GridUniqueDraftByRowExclusionInference;
 impl Inference for GridUniqueDraftByRowExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::Claiming
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_rc().find_map(|vr| {
-            (0..9).into_iter().find_map(|v| {
+            (0..field.size() as u8).find_map(|v| {
                 vr.iter()
                     .filter(|&p| p.candidates.contains(v))
                     .find(|&p| {
@@ -526,6 +695,8 @@ impl Inference for GridUniqueDraftByRowExclusionInference {
 
                         InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -535,42 +706,27 @@ impl Inference for GridUniqueDraftByRowExclusionInference {
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let mut r = format!(
-            "{} 的所有可能 {:?} 在 R{:?} 内都只在 G{:?} 中，推导出：",
-            inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect::<Vec<String>>()
-                .join(" "),
-            inference_result.condition[0].the_value,
-            Into::<RCCoords>::into(inference_result.condition[0].the_coords).r + 1,
-            Into::<GNCoords>::into(inference_result.condition[0].the_coords).g + 1
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-            r.push_str(&format!(
-                "{}均不能填写 {:?}",
-                removed_cells.join(" "),
-                inference_result.condition[0].the_value
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Row(coords.r)],
+            vec![Unit::Grid(coords.g)],
+        )
     }
 }
 
 /// 当一列的草稿数正好在一宫时，排除该宫的其他草稿数
 struct GridUniqueDraftByColExclusionInference;
 impl Inference for GridUniqueDraftByColExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::Claiming
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         field.iter_all_drafts_cells_by_cr().find_map(|vc| {
-            (0..9).into_iter().find_map(|v| {
+            (0..field.size() as u8).find_map(|v| {
                 vc.iter()
                     .filter(|&p| p.candidates.contains(v))
                     .find(|&p| {
@@ -605,6 +761,8 @@ impl Inference for GridUniqueDraftByColExclusionInference {
 
                         InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -614,33 +772,14 @@ impl Inference for GridUniqueDraftByColExclusionInference {
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        let mut r = format!(
-            "{} 的所有可能 {:?} 在 C{:?} 内都只在 G{:?} 中，推导出：",
-            inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect::<Vec<String>>()
-                .join(" "),
-            inference_result.condition[0].the_value,
-            Into::<RCCoords>::into(inference_result.condition[0].the_coords).c + 1,
-            Into::<GNCoords>::into(inference_result.condition[0].the_coords).g + 1
-        );
-
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-            r.push_str(&format!(
-                "{}均不能填写 {:?}",
-                removed_cells.join(" "),
-                inference_result.condition[0].the_value
-            ));
-        }
-
-        r
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Column(coords.c)],
+            vec![Unit::Grid(coords.g)],
+        )
     }
 }
 
@@ -648,6 +787,10 @@ impl Inference for GridUniqueDraftByColExclusionInference {
 /// 定义：X个格子内的候选数字的并集，数量正好是X，称之为【数对】，其中 2<=X<=4
 struct RowExplicitNakedPairExclusionInference;
 impl Inference for RowExplicitNakedPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::NakedSubset
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         for vr in field.iter_all_drafts_cells_by_rc() {
             let mut all_combinations = Vec::new();
@@ -665,7 +808,7 @@ impl Inference for RowExplicitNakedPairExclusionInference {
             for (combo, rest) in all_combinations {
                 let union_drafts: Candidate = combo
                     .iter()
-                    .map(|&i| vr[i].candidates.clone())
+                    .map(|&i| vr[i].candidates)
                     .reduce(|a, b| a.union(&b))
                     .unwrap_or_default();
                 let union_drafts_vec = union_drafts.to_vec();
@@ -675,7 +818,7 @@ impl Inference for RowExplicitNakedPairExclusionInference {
                         .iter()
                         .map(|&i| TheCoordsAndTheValue {
                             the_coords: vr[i].coords,
-                            the_value: union_drafts_vec.clone(),
+                            the_value: vr[i].candidates.to_vec(),
                         })
                         .collect();
                     let conclusion: Vec<TheCoordsAndTheValue> = rest
@@ -689,7 +832,7 @@ impl Inference for RowExplicitNakedPairExclusionInference {
                             {
                                 Some(TheCoordsAndTheValue {
                                     the_coords: vr[i].coords,
-                                    the_value: union_drafts_vec.clone(),
+                                    the_value: vr[i].candidates.intersect(&union_drafts).to_vec(),
                                 })
                             } else {
                                 None
@@ -699,6 +842,8 @@ impl Inference for RowExplicitNakedPairExclusionInference {
                     if !conclusion.is_empty() {
                         return Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -710,37 +855,14 @@ impl Inference for RowExplicitNakedPairExclusionInference {
         None
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let removed_values: Vec<String> = conclusion_remove_drafts[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            return format!(
-                "{} 的草稿 {} 在同一 R{:?} 内形成了数对，因此该 R{:?} 内 {} 不能填写 {} ",
-                condition_cells.join(" "),
-                removed_values.join(" "),
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).r + 1,
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).r + 1,
-                removed_cells.join(" "),
-                removed_values.join(" ")
-            );
-        }
-
-        String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Row(coords.r)],
+            vec![],
+        )
     }
 }
 
@@ -748,6 +870,10 @@ impl Inference for RowExplicitNakedPairExclusionInference {
 /// 定义：X个格子内的候选数字的并集，数量正好是X，称之为【数对】，其中 2<=X<=4
 struct ColExplicitNakedPairExclusionInference;
 impl Inference for ColExplicitNakedPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::NakedSubset
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         for vc in field.iter_all_drafts_cells_by_cr() {
             let mut all_combinations = Vec::new();
@@ -765,7 +891,7 @@ impl Inference for ColExplicitNakedPairExclusionInference {
             for (combo, rest) in all_combinations {
                 let union_drafts: Candidate = combo
                     .iter()
-                    .map(|&i| vc[i].candidates.clone())
+                    .map(|&i| vc[i].candidates)
                     .reduce(|a, b| a.union(&b))
                     .unwrap_or_default();
                 let union_drafts_vec = union_drafts.to_vec();
@@ -775,7 +901,7 @@ impl Inference for ColExplicitNakedPairExclusionInference {
                         .iter()
                         .map(|&i| TheCoordsAndTheValue {
                             the_coords: vc[i].coords,
-                            the_value: union_drafts_vec.clone(),
+                            the_value: vc[i].candidates.to_vec(),
                         })
                         .collect();
                     let conclusion: Vec<TheCoordsAndTheValue> = rest
@@ -789,7 +915,7 @@ impl Inference for ColExplicitNakedPairExclusionInference {
                             {
                                 Some(TheCoordsAndTheValue {
                                     the_coords: vc[i].coords,
-                                    the_value: union_drafts_vec.clone(),
+                                    the_value: vc[i].candidates.intersect(&union_drafts).to_vec(),
                                 })
                             } else {
                                 None
@@ -799,6 +925,8 @@ impl Inference for ColExplicitNakedPairExclusionInference {
                     if !conclusion.is_empty() {
                         return Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -810,37 +938,14 @@ impl Inference for ColExplicitNakedPairExclusionInference {
         None
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let removed_values: Vec<String> = conclusion_remove_drafts[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            return format!(
-                "{} 的草稿 {} 在同一 C{:?} 内形成了数对，因此该 C{:?} 内 {} 不能填写 {} ",
-                condition_cells.join(" "),
-                removed_values.join(" "),
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).c + 1,
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).c + 1,
-                removed_cells.join(" "),
-                removed_values.join(" ")
-            );
-        }
-
-        String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Column(coords.c)],
+            vec![],
+        )
     }
 }
 
@@ -848,6 +953,10 @@ impl Inference for ColExplicitNakedPairExclusionInference {
 /// 定义：X个格子内的候选数字的并集，数量正好是X，称之为【数对】，其中 2<=X<=4
 struct GridExplicitNakedPairExclusionInference;
 impl Inference for GridExplicitNakedPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::NakedSubset
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         for vg in field.iter_all_drafts_cells_by_gn() {
             let mut all_combinations = Vec::new();
@@ -865,7 +974,7 @@ impl Inference for GridExplicitNakedPairExclusionInference {
             for (combo, rest) in all_combinations {
                 let union_drafts: Candidate = combo
                     .iter()
-                    .map(|&i| vg[i].candidates.clone())
+                    .map(|&i| vg[i].candidates)
                     .reduce(|a, b| a.union(&b))
                     .unwrap_or_default();
                 let union_drafts_vec = union_drafts.to_vec();
@@ -875,7 +984,7 @@ impl Inference for GridExplicitNakedPairExclusionInference {
                         .iter()
                         .map(|&i| TheCoordsAndTheValue {
                             the_coords: vg[i].coords,
-                            the_value: union_drafts_vec.clone(),
+                            the_value: vg[i].candidates.to_vec(),
                         })
                         .collect();
                     let conclusion: Vec<TheCoordsAndTheValue> = rest
@@ -889,7 +998,7 @@ impl Inference for GridExplicitNakedPairExclusionInference {
                             {
                                 Some(TheCoordsAndTheValue {
                                     the_coords: vg[i].coords,
-                                    the_value: union_drafts_vec.clone(),
+                                    the_value: vg[i].candidates.intersect(&union_drafts).to_vec(),
                                 })
                             } else {
                                 None
@@ -899,6 +1008,8 @@ impl Inference for GridExplicitNakedPairExclusionInference {
                     if !conclusion.is_empty() {
                         return Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -910,37 +1021,14 @@ impl Inference for GridExplicitNakedPairExclusionInference {
         None
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<GNCoords>::into(cv.the_coords)))
-                .collect();
-
-            let removed_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<GNCoords>::into(cv.the_coords)))
-                .collect();
-
-            let removed_values: Vec<String> = conclusion_remove_drafts[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            return format!(
-                "{} 的草稿 {} 在同一 G{:?} 内形成了数对，因此该 G{:?} 内 {} 不能填写 {} ",
-                condition_cells.join(" "),
-                removed_values.join(" "),
-                Into::<GNCoords>::into(conclusion_remove_drafts[0].the_coords).g + 1,
-                Into::<GNCoords>::into(conclusion_remove_drafts[0].the_coords).g + 1,
-                removed_cells.join(" "),
-                removed_values.join(" ")
-            );
-        }
-
-        String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Grid(coords.g)],
+            vec![],
+        )
     }
 }
 
@@ -948,6 +1036,10 @@ impl Inference for GridExplicitNakedPairExclusionInference {
 /// 定义：X个格子内的候选数字的并集，数量正好是总候选数-X，则称剩余候选数组成的集合为【数对】，其中 2<=X<=4
 struct RowExplicitHiddenPairExclusionInference;
 impl Inference for RowExplicitHiddenPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSubset
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         for vr in field.iter_all_drafts_cells_by_rc() {
             let mut all_combinations = Vec::new();
@@ -965,7 +1057,7 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
             for (combo, rest) in all_combinations {
                 let rest_union_drafts: Candidate = rest
                     .iter()
-                    .map(|&i| vr[i].candidates.clone())
+                    .map(|&i| vr[i].candidates)
                     .reduce(|a, b| a.union(&b))
                     .unwrap_or_default();
                 let rest_union_drafts_vec = rest_union_drafts.to_vec();
@@ -973,7 +1065,7 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
                 if rest_union_drafts_vec.len() == vr.len() - combo.len() {
                     let combo_union_drafts = combo
                         .iter()
-                        .map(|&i| vr[i].candidates.clone())
+                        .map(|&i| vr[i].candidates)
                         .reduce(|a, b| a.union(&b))
                         .unwrap_or_default();
                     let hidden_pair_drafts = combo_union_drafts.subtract(&rest_union_drafts);
@@ -981,7 +1073,7 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
                         .iter()
                         .map(|&i| TheCoordsAndTheValue {
                             the_coords: vr[i].coords,
-                            the_value: hidden_pair_drafts.to_vec().clone(),
+                            the_value: vr[i].candidates.intersect(&hidden_pair_drafts).to_vec(),
                         })
                         .collect();
                     let conclusion: Vec<TheCoordsAndTheValue> = combo
@@ -995,7 +1087,10 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
                             {
                                 Some(TheCoordsAndTheValue {
                                     the_coords: vr[i].coords,
-                                    the_value: rest_union_drafts.to_vec().clone(),
+                                    the_value: vr[i]
+                                        .candidates
+                                        .intersect(&rest_union_drafts)
+                                        .to_vec(),
                                 })
                             } else {
                                 None
@@ -1005,6 +1100,8 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
                     if !conclusion.is_empty() {
                         return Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -1016,38 +1113,14 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
         None
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let condition_values: Vec<String> = inference_result.condition[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            let removed_values: Vec<String> = conclusion_remove_drafts[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            return format!(
-                "{} 在 R{:?} 内形成了隐性数对 {} ，因此该 R{:?} 内 {} 不能填写 {} ",
-                condition_cells.join(" "),
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).r + 1,
-                condition_values.join(" "),
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).r + 1,
-                condition_cells.join(" "),
-                removed_values.join(" ")
-            );
-        }
-
-        String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Row(coords.r)],
+            vec![],
+        )
     }
 }
 
@@ -1055,6 +1128,10 @@ impl Inference for RowExplicitHiddenPairExclusionInference {
 /// 定义：X个格子内的候选数字的并集，数量正好是总候选数-X，则称剩余候选数组成的集合为【数对】，其中 2<=X<=4
 struct ColExplicitHiddenPairExclusionInference;
 impl Inference for ColExplicitHiddenPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSubset
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         for vc in field.iter_all_drafts_cells_by_cr() {
             let mut all_combinations = Vec::new();
@@ -1072,7 +1149,7 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
             for (combo, rest) in all_combinations {
                 let rest_union_drafts: Candidate = rest
                     .iter()
-                    .map(|&i| vc[i].candidates.clone())
+                    .map(|&i| vc[i].candidates)
                     .reduce(|a, b| a.union(&b))
                     .unwrap_or_default();
                 let rest_union_drafts_vec = rest_union_drafts.to_vec();
@@ -1080,7 +1157,7 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
                 if rest_union_drafts_vec.len() == vc.len() - combo.len() {
                     let combo_union_drafts = combo
                         .iter()
-                        .map(|&i| vc[i].candidates.clone())
+                        .map(|&i| vc[i].candidates)
                         .reduce(|a, b| a.union(&b))
                         .unwrap_or_default();
                     let hidden_pair_drafts = combo_union_drafts.subtract(&rest_union_drafts);
@@ -1088,7 +1165,7 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
                         .iter()
                         .map(|&i| TheCoordsAndTheValue {
                             the_coords: vc[i].coords,
-                            the_value: hidden_pair_drafts.to_vec().clone(),
+                            the_value: vc[i].candidates.intersect(&hidden_pair_drafts).to_vec(),
                         })
                         .collect();
                     let conclusion: Vec<TheCoordsAndTheValue> = combo
@@ -1102,7 +1179,10 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
                             {
                                 Some(TheCoordsAndTheValue {
                                     the_coords: vc[i].coords,
-                                    the_value: rest_union_drafts.to_vec().clone(),
+                                    the_value: vc[i]
+                                        .candidates
+                                        .intersect(&rest_union_drafts)
+                                        .to_vec(),
                                 })
                             } else {
                                 None
@@ -1112,6 +1192,8 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
                     if !conclusion.is_empty() {
                         return Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -1123,38 +1205,14 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
         None
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let condition_values: Vec<String> = inference_result.condition[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            let removed_values: Vec<String> = conclusion_remove_drafts[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
-
-            return format!(
-                "{} 在 C{:?} 内形成了隐性数对 {} ，因此该 C{:?} 内 {} 不能填写 {} ",
-                condition_cells.join(" "),
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).c + 1,
-                condition_values.join(" "),
-                Into::<RCCoords>::into(conclusion_remove_drafts[0].the_coords).c + 1,
-                condition_cells.join(" "),
-                removed_values.join(" ")
-            );
-        }
-
-        String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Column(coords.c)],
+            vec![],
+        )
     }
 }
 
@@ -1162,6 +1220,10 @@ impl Inference for ColExplicitHiddenPairExclusionInference {
 /// 定义：X个格子内的候选数字的并集，数量正好是总候选数-X，则称剩余候选数组成的集合为【数对】，其中 2<=X<=4
 struct GridExplicitHiddenPairExclusionInference;
 impl Inference for GridExplicitHiddenPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSubset
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         for vg in field.iter_all_drafts_cells_by_gn() {
             let all_combinations = (2..=4)
@@ -1182,7 +1244,7 @@ impl Inference for GridExplicitHiddenPairExclusionInference {
             for (combo, rest) in all_combinations {
                 let rest_union_drafts: Candidate = rest
                     .iter()
-                    .map(|&i| vg[i].candidates.clone())
+                    .map(|&i| vg[i].candidates)
                     .reduce(|a, b| a.union(&b))
                     .unwrap_or_default();
                 let rest_union_drafts_vec = rest_union_drafts.to_vec();
@@ -1190,7 +1252,7 @@ impl Inference for GridExplicitHiddenPairExclusionInference {
                 if rest_union_drafts_vec.len() == vg.len() - combo.len() {
                     let combo_union_drafts = combo
                         .iter()
-                        .map(|&i| vg[i].candidates.clone())
+                        .map(|&i| vg[i].candidates)
                         .reduce(|a, b| a.union(&b))
                         .unwrap_or_default();
                     let hidden_pair_drafts = combo_union_drafts.subtract(&rest_union_drafts);
@@ -1198,7 +1260,7 @@ impl Inference for GridExplicitHiddenPairExclusionInference {
                         .iter()
                         .map(|&i| TheCoordsAndTheValue {
                             the_coords: vg[i].coords,
-                            the_value: hidden_pair_drafts.to_vec(),
+                            the_value: vg[i].candidates.intersect(&hidden_pair_drafts).to_vec(),
                         })
                         .collect();
                     let conclusion: Vec<TheCoordsAndTheValue> = combo
@@ -1212,7 +1274,10 @@ impl Inference for GridExplicitHiddenPairExclusionInference {
                             {
                                 Some(TheCoordsAndTheValue {
                                     the_coords: vg[i].coords,
-                                    the_value: rest_union_drafts.to_vec(),
+                                    the_value: vg[i]
+                                        .candidates
+                                        .intersect(&rest_union_drafts)
+                                        .to_vec(),
                                 })
                             } else {
                                 None
@@ -1222,6 +1287,8 @@ impl Inference for GridExplicitHiddenPairExclusionInference {
                     if !conclusion.is_empty() {
                         return Some(InferenceResult {
                             inference: self,
+                            size: field.size(),
+                            units: vec![],
                             condition,
                             conclusion_set_value: None,
                             conclusion_remove_drafts: Some(conclusion),
@@ -1233,47 +1300,694 @@ impl Inference for GridExplicitHiddenPairExclusionInference {
         None
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let coords = inference_result.condition[0].the_coords;
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            vec![Unit::Grid(coords.g)],
+            vec![],
+        )
+    }
+}
 
-            let condition_values: Vec<String> = inference_result.condition[0]
-                .the_value
-                .iter()
-                .map(|cv| format!("{:?}", cv))
-                .collect();
+// 以下策略遍历变体规则额外的单元（如对角线），标准数独没有额外的单元，不会得到结果
+
+/// 按额外单元排除法，单元内某个草稿值只有一个位置时，填写该值，同时去除其余相关格的草稿值
+struct OnlyOneRightInExtraUnitInference;
+impl Inference for OnlyOneRightInExtraUnitInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSingle
+    }
 
-            let removed_values: Vec<String> = conclusion_remove_drafts[0]
-                .the_value
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        field.rules().extra_units().iter().find_map(|&unit| {
+            let cells = field.collect_all_drafts_cells_in_unit(unit);
+            (0..field.size() as u8).find_map(|v| {
+                let mut cells_with_value = cells.iter().filter(|p| p.candidates.contains(v));
+                let (Some(p), None) = (cells_with_value.next(), cells_with_value.next()) else {
+                    return None;
+                };
+                let cv = TheCoordsAndTheValue {
+                    the_coords: p.coords,
+                    the_value: vec![v],
+                };
+                Some(InferenceResult {
+                    inference: self,
+                    size: field.size(),
+                    units: vec![unit],
+                    condition: vec![cv.clone()],
+                    conclusion_set_value: Some(vec![cv]),
+                    conclusion_remove_drafts: make_simple_conclusion_when_set_value(
+                        field, &p.coords, v,
+                    ),
+                })
+            })
+        })
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(
+            inference_result,
+            Role::Placement,
+            inference_result.units.clone(),
+            vec![],
+        )
+    }
+}
+
+/// 额外单元的区块排除法，某种草稿值在一个单元内只出现在与另一个单元相交的格子中时，
+/// 可以排除另一个单元其余格子的该草稿值，两个单元中至少有一个是额外单元
+/// pointing为true时只处理从宫出发的区块，否则处理从行、列和额外单元出发的区块
+struct ExtraUnitLockedCandidatesInference {
+    pointing: bool,
+}
+impl Inference for ExtraUnitLockedCandidatesInference {
+    fn technique(&self) -> Technique {
+        if self.pointing {
+            Technique::Pointing
+        } else {
+            Technique::Claiming
+        }
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        let index = |p: &Cell| rules.index(p.coords.into());
+        for &extra in rules.extra_units() {
+            for &other in rules.units().iter().filter(|&&u| u != extra) {
+                for (base, cover) in [(extra, other), (other, extra)] {
+                    if matches!(base, Unit::Grid(_)) != self.pointing {
+                        continue;
+                    }
+                    let base_indexes = rules.unit_indexes(base);
+                    let cover_indexes = rules.unit_indexes(cover);
+                    let base_cells = field.collect_all_drafts_cells_in_unit(base);
+                    let cover_cells = field.collect_all_drafts_cells_in_unit(cover);
+                    for v in 0..field.size() as u8 {
+                        let cells_with_value: Vec<&Cell> = base_cells
+                            .iter()
+                            .copied()
+                            .filter(|p| p.candidates.contains(v))
+                            .collect();
+                        if cells_with_value.is_empty()
+                            || !cells_with_value
+                                .iter()
+                                .all(|&p| cover_indexes.contains(&index(p)))
+                        {
+                            continue;
+                        }
+                        let conclusion: Vec<TheCoordsAndTheValue> = cover_cells
+                            .iter()
+                            .filter(|&&p| {
+                                p.candidates.contains(v) && !base_indexes.contains(&index(p))
+                            })
+                            .map(|&p| create_simple_cell_and_value(p.coords, v))
+                            .collect();
+                        if !conclusion.is_empty() {
+                            return Some(InferenceResult {
+                                inference: self,
+                                size: field.size(),
+                                units: vec![base, cover],
+                                condition: cells_with_value
+                                    .iter()
+                                    .map(|&p| create_simple_cell_and_value(p.coords, v))
+                                    .collect(),
+                                conclusion_set_value: None,
+                                conclusion_remove_drafts: Some(conclusion),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            inference_result.units[..1].to_vec(),
+            inference_result.units[1..].to_vec(),
+        )
+    }
+}
+
+// 单元内所有2/3/4个格子的组合及剩余的格子
+fn subset_combinations(len: usize) -> Vec<(Vec<usize>, Vec<usize>)> {
+    let mut all_combinations = Vec::new();
+    for size in 2..=4 {
+        let mut paths = Vec::new();
+        crate::utils::generate_combinations(len, size, 0, &mut paths, &mut all_combinations);
+    }
+    all_combinations
+}
+
+/// 显性数对排除法（额外单元），与按行的显性数对相同
+struct ExtraUnitNakedPairExclusionInference;
+impl Inference for ExtraUnitNakedPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::NakedSubset
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        for &unit in field.rules().extra_units() {
+            let cells = field.collect_all_drafts_cells_in_unit(unit);
+            for (combo, rest) in subset_combinations(cells.len()) {
+                let union_drafts: Candidate = combo
+                    .iter()
+                    .map(|&i| cells[i].candidates)
+                    .reduce(|a, b| a.union(&b))
+                    .unwrap_or_default();
+                if union_drafts.len() as usize != combo.len() {
+                    continue;
+                }
+                let conclusion: Vec<TheCoordsAndTheValue> = rest
+                    .iter()
+                    .map(|&i| TheCoordsAndTheValue {
+                        the_coords: cells[i].coords,
+                        the_value: cells[i].candidates.intersect(&union_drafts).to_vec(),
+                    })
+                    .filter(|cv| !cv.the_value.is_empty())
+                    .collect();
+                if !conclusion.is_empty() {
+                    return Some(InferenceResult {
+                        inference: self,
+                        size: field.size(),
+                        units: vec![unit],
+                        condition: combo
+                            .iter()
+                            .map(|&i| TheCoordsAndTheValue {
+                                the_coords: cells[i].coords,
+                                the_value: cells[i].candidates.to_vec(),
+                            })
+                            .collect(),
+                        conclusion_set_value: None,
+                        conclusion_remove_drafts: Some(conclusion),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            inference_result.units.clone(),
+            vec![],
+        )
+    }
+}
+
+/// 隐性数对排除法（额外单元），与按行的隐性数对相同
+struct ExtraUnitHiddenPairExclusionInference;
+impl Inference for ExtraUnitHiddenPairExclusionInference {
+    fn technique(&self) -> Technique {
+        Technique::HiddenSubset
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        for &unit in field.rules().extra_units() {
+            let cells = field.collect_all_drafts_cells_in_unit(unit);
+            for (combo, rest) in subset_combinations(cells.len()) {
+                let union_of = |indexes: &[usize]| -> Candidate {
+                    indexes
+                        .iter()
+                        .map(|&i| cells[i].candidates)
+                        .reduce(|a, b| a.union(&b))
+                        .unwrap_or_default()
+                };
+                let rest_union_drafts = union_of(&rest);
+                // 检查剩余的并集数量是否等于整个候选数-组合的数量
+                if rest_union_drafts.len() as usize != cells.len() - combo.len() {
+                    continue;
+                }
+                let hidden_pair_drafts = union_of(&combo).subtract(&rest_union_drafts);
+                let conclusion: Vec<TheCoordsAndTheValue> = combo
+                    .iter()
+                    .map(|&i| TheCoordsAndTheValue {
+                        the_coords: cells[i].coords,
+                        the_value: cells[i].candidates.intersect(&rest_union_drafts).to_vec(),
+                    })
+                    .filter(|cv| !cv.the_value.is_empty())
+                    .collect();
+                if !conclusion.is_empty() {
+                    return Some(InferenceResult {
+                        inference: self,
+                        size: field.size(),
+                        units: vec![unit],
+                        condition: combo
+                            .iter()
+                            .map(|&i| TheCoordsAndTheValue {
+                                the_coords: cells[i].coords,
+                                the_value: cells[i]
+                                    .candidates
+                                    .intersect(&hidden_pair_drafts)
+                                    .to_vec(),
+                            })
+                            .collect(),
+                        conclusion_set_value: None,
+                        conclusion_remove_drafts: Some(conclusion),
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            inference_result.units.clone(),
+            vec![],
+        )
+    }
+}
+
+// 以下策略只用于杀手数独，没有笼子时不会得到结果
+
+/// 笼子组合，按笼子和列出所有数字组合，去掉与笼子内已填数字和草稿矛盾的组合后，
+/// 笼子内的格子只能填写剩余组合中的数字
+struct CageCombinationInference;
+impl Inference for CageCombinationInference {
+    fn technique(&self) -> Technique {
+        Technique::CageCombination
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        let domains = field.cell_domains();
+        (0..rules.cages().len()).find_map(|i| {
+            let allowed = rules.cage_candidates(i, &domains);
+            let cells = field.collect_all_drafts_cells_in_unit(Unit::Cage(i));
+            let conclusion: Vec<TheCoordsAndTheValue> = cells
                 .iter()
-                .map(|cv| format!("{:?}", cv))
+                .map(|p| TheCoordsAndTheValue {
+                    the_coords: p.coords,
+                    the_value: p.candidates.subtract(&allowed).to_vec(),
+                })
+                .filter(|cv| !cv.the_value.is_empty())
                 .collect();
+            if conclusion.is_empty() {
+                return None;
+            }
+            Some(InferenceResult {
+                inference: self,
+                size: field.size(),
+                units: vec![Unit::Cage(i)],
+                condition: cells
+                    .iter()
+                    .map(|p| TheCoordsAndTheValue {
+                        the_coords: p.coords,
+                        the_value: p.candidates.intersect(&allowed).to_vec(),
+                    })
+                    .collect(),
+                conclusion_set_value: None,
+                conclusion_remove_drafts: Some(conclusion),
+            })
+        })
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            inference_result.units.clone(),
+            vec![],
+        )
+    }
+}
+
+/// 四十五法则，单元内所有数字之和固定（9x9时为45）：
+/// 完全在单元内的笼子之和加上单元内已填的数字，只差单元内唯一一个未填的格子时，可以算出该格（内格）；
+/// 与单元相交的笼子覆盖整个单元，且在单元外只多出一个未填的格子时，同样可以算出该格（外格）。
+/// 只差两个未填的格子时两格之和固定，排除两格中凑不出这个和的草稿，两格互相可见时两数还不能相同。
+/// 只按单个行、列或宫计算，不合并相邻的多个单元，也不处理三格及以上的内格、外格
+struct Rule45Inference;
+impl Inference for Rule45Inference {
+    fn technique(&self) -> Technique {
+        Technique::Rule45
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        if rules.cages().is_empty() {
+            return None;
+        }
+        let size = field.size();
+        let total = (size * (size + 1) / 2) as i64;
+        let value_of = |idx: usize| field.get_cell_ref_by_rc(rules.rc(idx)).value;
+
+        for &unit in rules.units() {
+            let unit_indexes = rules.unit_indexes(unit);
+            for inside in [true, false] {
+                let cages: Vec<usize> = (0..rules.cages().len())
+                    .filter(|&i| {
+                        let cells = &rules.cages()[i].cells;
+                        if inside {
+                            cells.iter().all(|idx| unit_indexes.contains(idx))
+                        } else {
+                            cells.iter().any(|idx| unit_indexes.contains(idx))
+                        }
+                    })
+                    .collect();
+                if cages.is_empty() {
+                    continue;
+                }
+                let covered: Vec<usize> = cages
+                    .iter()
+                    .flat_map(|&i| rules.cages()[i].cells.iter().copied())
+                    .collect();
+                let cage_sum: i64 = cages.iter().map(|&i| rules.cages()[i].sum as i64).sum();
+                // 内格为单元内不在笼子中的格子，外格为笼子中不在单元内的格子
+                let rest: Vec<usize> = if inside {
+                    unit_indexes
+                        .iter()
+                        .copied()
+                        .filter(|idx| !covered.contains(idx))
+                        .collect()
+                } else {
+                    if !unit_indexes.iter().all(|idx| covered.contains(idx)) {
+                        continue;
+                    }
+                    covered
+                        .iter()
+                        .copied()
+                        .filter(|idx| !unit_indexes.contains(idx))
+                        .collect()
+                };
+                let open: Vec<usize> = rest
+                    .iter()
+                    .copied()
+                    .filter(|&idx| value_of(idx).is_none())
+                    .collect();
+                let known: i64 = rest
+                    .iter()
+                    .filter_map(|&idx| value_of(idx))
+                    .map(|v| v as i64 + 1)
+                    .sum();
+                // 未填的内格（外格）之和
+                let digit = if inside {
+                    total - cage_sum - known
+                } else {
+                    cage_sum - total - known
+                };
+                let mut units = vec![unit];
+                units.extend(cages.iter().map(|&i| Unit::Cage(i)));
+                let idx = match open[..] {
+                    [idx] => idx,
+                    [a, b] => match self.analyze_pair(field, a, b, digit, units) {
+                        Some(result) => return Some(result),
+                        None => continue,
+                    },
+                    _ => continue,
+                };
+                let coords = rules.coords(rules.rc(idx));
+                let cell = field.get_cell_ref_by_coords(coords);
+                if digit < 1 || digit > size as i64 || !cell.candidates.contains(digit as u8 - 1) {
+                    continue;
+                }
+                let value = digit as u8 - 1;
+                let cv = create_simple_cell_and_value(coords, value);
+                return Some(InferenceResult {
+                    inference: self,
+                    size: field.size(),
+                    units,
+                    condition: vec![cv.clone()],
+                    conclusion_set_value: Some(vec![cv]),
+                    conclusion_remove_drafts: make_simple_conclusion_when_set_value(
+                        field, &coords, value,
+                    ),
+                });
+            }
+        }
+        None
+    }
 
-            return format!(
-                "{} 在 G{:?} 内形成了隐性数对 {} ，因此该 G{:?} 内 {} 不能填写 {} ",
-                condition_cells.join(" "),
-                Into::<GNCoords>::into(conclusion_remove_drafts[0].the_coords).g + 1,
-                condition_values.join(" "),
-                Into::<GNCoords>::into(conclusion_remove_drafts[0].the_coords).g + 1,
-                condition_cells.join(" "),
-                removed_values.join(" ")
-            );
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        // 算出一格时条件就是结论，两格时条件是两格的草稿
+        let role = if inference_result.conclusion_set_value.is_some() {
+            Role::Placement
         } else {
-            String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
+            Role::Base
+        };
+        Explanation::new(
+            inference_result,
+            role,
+            inference_result.units[..1].to_vec(),
+            inference_result.units[1..].to_vec(),
+        )
+    }
+}
+
+impl Rule45Inference {
+    // 两格之和为sum，排除每格中在另一格找不到搭配的草稿
+    fn analyze_pair<'a>(
+        &'a self,
+        field: &'a Sudoku,
+        a: usize,
+        b: usize,
+        sum: i64,
+        units: Vec<Unit>,
+    ) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        let cell = |idx: usize| field.get_cell_ref_by_rc(rules.rc(idx));
+        let distinct = rules.peers(a).contains(&b);
+        let removed = |this: usize, other: usize| -> Vec<u8> {
+            cell(this)
+                .candidates
+                .to_vec()
+                .into_iter()
+                .filter(|&x| {
+                    !cell(other)
+                        .candidates
+                        .to_vec()
+                        .into_iter()
+                        .any(|y| x as i64 + y as i64 + 2 == sum && !(distinct && x == y))
+                })
+                .collect()
+        };
+        let conclusion: Vec<TheCoordsAndTheValue> = [(a, removed(a, b)), (b, removed(b, a))]
+            .into_iter()
+            .filter(|(_, values)| !values.is_empty())
+            .map(|(idx, values)| TheCoordsAndTheValue {
+                the_coords: cell(idx).coords,
+                the_value: values,
+            })
+            .collect();
+        if conclusion.is_empty() {
+            return None;
+        }
+        Some(InferenceResult {
+            inference: self,
+            size: field.size(),
+            units,
+            condition: [a, b]
+                .iter()
+                .map(|&idx| TheCoordsAndTheValue {
+                    the_coords: cell(idx).coords,
+                    the_value: cell(idx).candidates.to_vec(),
+                })
+                .collect(),
+            conclusion_set_value: None,
+            conclusion_remove_drafts: Some(conclusion),
+        })
+    }
+}
+
+/// 相邻格关系所属的策略，XV标记为Xv，其余为Kropki
+pub fn relation_technique(relation: Relation) -> Technique {
+    match relation {
+        Relation::Sum(_) | Relation::NotSum(_) => Technique::Xv,
+        _ => Technique::Kropki,
+    }
+}
+
+/// 相邻格关系排除法（非连续数独、克罗普基圆点和XV数独），相邻两格之间有某种关系时，
+/// 一格中与另一格所有可能值都不满足关系的草稿可以排除，每种关系分别处理，方便说明依据
+struct RelationInference {
+    relation: Relation,
+}
+impl Inference for RelationInference {
+    fn technique(&self) -> Technique {
+        relation_technique(self.relation)
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        let domains = field.cell_domains();
+        let cell = |idx: usize| field.get_cell_ref_by_rc(rules.rc(idx));
+        rules
+            .relations()
+            .iter()
+            .filter(|&&(_, _, relation)| relation == self.relation)
+            .flat_map(|&(a, b, _)| [(a, b), (b, a)])
+            .find_map(|(from, to)| {
+                let to_cell = cell(to);
+                if to_cell.status != CellStatus::DRAFT {
+                    return None;
+                }
+                let allowed =
+                    Rules::relation_candidates(self.relation, domains[from], to_cell.candidates);
+                let removed = to_cell.candidates.subtract(&allowed);
+                if removed.is_empty() {
+                    return None;
+                }
+                Some(InferenceResult {
+                    inference: self,
+                    size: field.size(),
+                    units: vec![],
+                    condition: vec![TheCoordsAndTheValue {
+                        the_coords: cell(from).coords,
+                        the_value: domains[from].to_vec(),
+                    }],
+                    conclusion_set_value: None,
+                    conclusion_remove_drafts: Some(vec![TheCoordsAndTheValue {
+                        the_coords: to_cell.coords,
+                        the_value: removed.to_vec(),
+                    }]),
+                })
+            })
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let mut explanation = Explanation::new(inference_result, Role::Base, vec![], vec![]);
+        explanation.relation = Some(self.relation);
+        explanation
+    }
+}
+
+/// 线条排除法（温度计、箭头、德国悄悄话和连号线），按线上其他格子的候选数推算，
+/// 排除线上某格中不可能满足线条约束的草稿，每种线条分别处理，方便说明依据
+struct LineInference {
+    kind: LineKind,
+}
+impl Inference for LineInference {
+    fn technique(&self) -> Technique {
+        Technique::Line
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        let domains = field.cell_domains();
+        let cell = |idx: usize| field.get_cell_ref_by_rc(rules.rc(idx));
+        rules
+            .lines()
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.kind == self.kind)
+            .find_map(|(i, line)| {
+                let allowed = rules.line_candidates(i, &domains);
+                let (pos, removed) = line.cells.iter().enumerate().find_map(|(pos, &idx)| {
+                    let target = cell(idx);
+                    let removed = target.candidates.subtract(&allowed[pos]);
+                    (target.status == CellStatus::DRAFT && !removed.is_empty())
+                        .then_some((pos, removed))
+                })?;
+                Some(InferenceResult {
+                    inference: self,
+                    size: field.size(),
+                    units: vec![],
+                    condition: line
+                        .cells
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != pos)
+                        .map(|(_, &idx)| TheCoordsAndTheValue {
+                            the_coords: cell(idx).coords,
+                            the_value: domains[idx].to_vec(),
+                        })
+                        .collect(),
+                    conclusion_set_value: None,
+                    conclusion_remove_drafts: Some(vec![TheCoordsAndTheValue {
+                        the_coords: cell(line.cells[pos]).coords,
+                        the_value: removed.to_vec(),
+                    }]),
+                })
+            })
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        let mut explanation = Explanation::new(inference_result, Role::Base, vec![], vec![]);
+        explanation.line = Some(self.kind);
+        if self.kind == LineKind::Whisper {
+            explanation.gap = Some(inference_result.size.div_ceil(2) as u8);
         }
+        explanation
     }
 }
 
+/// 三明治排除法，逐一尝试1和9在行（列）中的位置，两端之间的数字之和必须等于提示，
+/// 排除所有位置下都不可能出现的草稿
+struct SandwichInference;
+impl Inference for SandwichInference {
+    fn technique(&self) -> Technique {
+        Technique::Sandwich
+    }
+
+    fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
+        let rules = field.rules();
+        let domains = field.cell_domains();
+        let cell = |idx: usize| field.get_cell_ref_by_rc(rules.rc(idx));
+        rules
+            .sandwiches()
+            .into_iter()
+            .enumerate()
+            .find_map(|(i, (unit, _))| {
+                let cells = rules.unit_indexes(unit);
+                let allowed = rules.sandwich_candidates(i, &domains);
+                let (pos, removed) = cells.iter().enumerate().find_map(|(pos, &idx)| {
+                    let target = cell(idx);
+                    let removed = target.candidates.subtract(&allowed[pos]);
+                    (target.status == CellStatus::DRAFT && !removed.is_empty())
+                        .then_some((pos, removed))
+                })?;
+                Some(InferenceResult {
+                    inference: self,
+                    size: field.size(),
+                    units: vec![unit],
+                    condition: cells
+                        .iter()
+                        .enumerate()
+                        .filter(|&(j, _)| j != pos)
+                        .map(|(_, &idx)| TheCoordsAndTheValue {
+                            the_coords: cell(idx).coords,
+                            the_value: domains[idx].to_vec(),
+                        })
+                        .collect(),
+                    conclusion_set_value: None,
+                    conclusion_remove_drafts: Some(vec![TheCoordsAndTheValue {
+                        the_coords: cell(cells[pos]).coords,
+                        the_value: removed.to_vec(),
+                    }]),
+                })
+            })
+    }
+
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(
+            inference_result,
+            Role::Base,
+            inference_result.units.clone(),
+            vec![],
+        )
+    }
+}
 /// n阶Fish，在一个维度（行/列）中，某个数字只出现在n个单元格中，且正好有n-1个维度的单元格正好位于相同的另一个列中（允许残缺，不允许多）
 struct NStepFishInference;
 impl Inference for NStepFishInference {
+    fn technique(&self) -> Technique {
+        Technique::Fish
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         // 构造返回条件
         fn create_condition(
+            field: &Sudoku,
             v: u8,
             direction: &IterDirection,
             one_indexes: &[usize],   // 阶数个usize的数组
@@ -1285,14 +1999,14 @@ impl Inference for NStepFishInference {
             let n = one_indexes.len();
             assert!(n == other_indexes.len());
 
-            // 生成所有可能的coords1和coords2的组合
-            for i in 0..n {
-                for j in 0..n {
-                    let (one_index, other_index) = (one_indexes[i], other_indexes[j]);
-                    condition.push(create_simple_cell_and_value(
-                        get_coords_with_direction(one_index, other_index, direction),
-                        v,
-                    ));
+            // 生成所有可能的coords1和coords2的组合，只保留含有该草稿的格子
+            for &one_index in one_indexes {
+                for &other_index in other_indexes {
+                    let rc = get_rc_coord_with_direction(one_index, other_index, direction);
+                    let cell = field.get_cell_ref_by_rc(rc);
+                    if cell.status == CellStatus::DRAFT && cell.candidates.contains(v) {
+                        condition.push(create_simple_cell_and_value(cell.coords, v));
+                    }
                 }
             }
 
@@ -1314,14 +2028,14 @@ impl Inference for NStepFishInference {
             assert!(n == other_indexes.len());
 
             // 检查每个one_index中的每个other_index对应的单元格
-            for one_index in 0..9 {
+            for one_index in 0..field.size() {
                 // 确保不是同一个第一维度
                 if !one_indexes.contains(&one_index) {
                     for &other_index in other_indexes {
                         let rc = get_rc_coord_with_direction(one_index, other_index, direction);
                         let cell = field.get_cell_ref_by_rc(rc);
                         if cell.status == CellStatus::DRAFT && cell.candidates.contains(v) {
-                            conclusion.push(create_simple_cell_and_value(rc.into(), v));
+                            conclusion.push(create_simple_cell_and_value(cell.coords, v));
                         }
                     }
                 }
@@ -1331,7 +2045,7 @@ impl Inference for NStepFishInference {
         }
 
         // 判断是否满足数对条件：数组不为空，且待判断数组的每一个值，均在原数组中
-        fn is_n_fish_pair(v1: &Vec<(usize, usize)>, v2: &Vec<(usize, usize)>) -> bool {
+        fn is_n_fish_pair(v1: &[(usize, usize)], v2: &[(usize, usize)]) -> bool {
             if v2.is_empty() {
                 false
             } else {
@@ -1349,13 +2063,13 @@ impl Inference for NStepFishInference {
         ) -> Option<InferenceResult<'a>> {
             let mut all_v_in_field: Vec<Vec<(usize, usize)>> = Vec::new();
             // 这里先求出草稿v的在某个维度上的分布
-            for one_index in 0..9 {
+            for one_index in 0..field.size() {
                 let mut all_v_in_one_index = Vec::new();
-                for other_index in 0..9 {
+                for other_index in 0..field.size() {
                     let p = field.get_cell_ref_by_rc(get_rc_coord_with_direction(
                         one_index,
                         other_index,
-                        &direction,
+                        direction,
                     ));
                     if p.status == CellStatus::DRAFT && p.candidates.contains(v) {
                         all_v_in_one_index.push(match &direction {
@@ -1367,12 +2081,12 @@ impl Inference for NStepFishInference {
                 }
                 all_v_in_field.push(all_v_in_one_index);
             }
-            for one_index in 0..9 {
+            for one_index in 0..field.size() {
                 let cur_len = all_v_in_field[one_index].len();
-                if cur_len >= 2 && cur_len <= 4 {
+                if (2..=4).contains(&cur_len) {
                     // 这里要找cur_len-1个is_n_fish_pair为true的行出来
                     let mut pair_one_index = vec![one_index];
-                    for one_index_2 in 0..9 {
+                    for one_index_2 in 0..field.size() {
                         if one_index_2 != one_index
                             && is_n_fish_pair(
                                 &all_v_in_field[one_index],
@@ -1384,6 +2098,7 @@ impl Inference for NStepFishInference {
                     }
                     if pair_one_index.len() == cur_len {
                         let condition = create_condition(
+                            field,
                             v,
                             direction,
                             &pair_one_index,
@@ -1405,6 +2120,8 @@ impl Inference for NStepFishInference {
                         if !conclusion.is_empty() {
                             return Some(InferenceResult {
                                 inference,
+                                size: field.size(),
+                                units: vec![],
                                 condition,
                                 conclusion_set_value: None,
                                 conclusion_remove_drafts: Some(conclusion),
@@ -1417,43 +2134,41 @@ impl Inference for NStepFishInference {
             None
         }
 
-        (0..9).into_iter().find_map(|v| {
-            self_analyze_with_direction(self, &field, v, &IterDirection::Row).or(
-                self_analyze_with_direction(self, &field, v, &IterDirection::Column),
+        (0..field.size() as u8).find_map(|v| {
+            self_analyze_with_direction(self, field, v, &IterDirection::Row).or(
+                self_analyze_with_direction(self, field, v, &IterDirection::Column),
             )
         })
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        if let Some(conclusion_remove_drafts) = &inference_result.conclusion_remove_drafts {
-            let condition_cells: Vec<String> = inference_result
-                .condition
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let conclusion_cells: Vec<String> = conclusion_remove_drafts
-                .iter()
-                .map(|cv| format!("{:?}", Into::<RCCoords>::into(cv.the_coords)))
-                .collect();
-
-            let fish_step = match condition_cells.len() {
-                3..=4 => "二",
-                5..=9 => "三",
-                10..=16 => "四",
-                _ => "未知",
-            };
-
-            return format!(
-                "{} 形成了 {}阶鱼 ，因此 {} 不能填写 {:?} ",
-                condition_cells.join(" "),
-                fish_step,
-                conclusion_cells.join(" "),
-                inference_result.condition[0].the_value[0]
-            );
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        // 条件格正好是基础行列与覆盖行列的交点，被排除的格子位于覆盖行列上
+        let mut rs: Vec<usize> = inference_result
+            .condition
+            .iter()
+            .map(|cv| cv.the_coords.r)
+            .collect();
+        let mut cs: Vec<usize> = inference_result
+            .condition
+            .iter()
+            .map(|cv| cv.the_coords.c)
+            .collect();
+        rs.sort();
+        rs.dedup();
+        cs.sort();
+        cs.dedup();
+        let rows: Vec<Unit> = rs.into_iter().map(Unit::Row).collect();
+        let cols: Vec<Unit> = cs.into_iter().map(Unit::Column).collect();
+        let eliminated_in_cols = inference_result
+            .conclusion_remove_drafts
+            .iter()
+            .flatten()
+            .all(|cv| cols.contains(&Unit::Column(cv.the_coords.c)));
+        if eliminated_in_cols {
+            Explanation::new(inference_result, Role::Base, rows, cols)
+        } else {
+            Explanation::new(inference_result, Role::Base, cols, rows)
         }
-
-        String::new() // 如果没有结论，返回一个空字符串，正常情况下，不应该到这里来
     }
 }
 
@@ -1461,6 +2176,10 @@ impl Inference for NStepFishInference {
 /// 如果数独存在多解，也返回None
 struct ExploitInference;
 impl Inference for ExploitInference {
+    fn technique(&self) -> Technique {
+        Technique::BruteForce
+    }
+
     fn analyze<'a>(&'a self, field: &'a Sudoku) -> Option<InferenceResult<'a>> {
         let solve_field = field.sovle();
 
@@ -1471,22 +2190,26 @@ impl Inference for ExploitInference {
                 let solve_field = &solve_field[0];
                 let mut conclusion = Vec::new();
 
-                for r in 0..9 {
-                    for c in 0..9 {
-                        let rc = RCCoords { r, c };
-                        let p1 = solve_field.get_cell_ref_by_rc(rc);
-                        let p2 = field.get_cell_ref_by_rc(rc);
-                        if p1.status != p2.status {
-                            conclusion.push(TheCoordsAndTheValue {
-                                the_coords: p2.coords,
-                                the_value: vec![p1.value.unwrap()],
-                            });
-                        }
+                for rc in field.iter_rc() {
+                    let p1 = solve_field.get_cell_ref_by_rc(rc);
+                    let p2 = field.get_cell_ref_by_rc(rc);
+                    if p1.status != p2.status {
+                        conclusion.push(TheCoordsAndTheValue {
+                            the_coords: p2.coords,
+                            the_value: vec![p1.value.unwrap()],
+                        });
                     }
                 }
 
+                // 已经完成的数独没有需要填写的格子
+                if conclusion.is_empty() {
+                    return None;
+                }
+
                 Some(InferenceResult {
                     inference: self,
+                    size: field.size(),
+                    units: vec![],
                     condition: vec![],
                     conclusion_set_value: Some(conclusion),
                     conclusion_remove_drafts: None,
@@ -1498,7 +2221,7 @@ impl Inference for ExploitInference {
         }
     }
 
-    fn write_result(&self, inference_result: &InferenceResult) -> String {
-        "暴力破解法".to_string()
+    fn explain(&self, inference_result: &InferenceResult) -> Explanation {
+        Explanation::new(inference_result, Role::Base, vec![], vec![])
     }
 }
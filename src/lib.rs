@@ -1,10 +1,39 @@
+pub mod batch;
+pub mod book;
+pub mod explanation;
+pub mod formats;
+pub mod game;
+pub mod hint;
 pub mod inferences;
+pub mod locale;
+pub mod multi;
+pub mod notation;
+pub mod rules;
+pub mod svg;
+pub mod tui;
 pub mod types;
 pub mod utils;
+pub mod validator;
+pub mod wasm;
 
 #[cfg(test)]
 mod tests {
-    use crate::{inferences::InferenceSet, types::Sudoku, utils::generate_combinations};
+    use crate::{
+        batch::{self, BatchFormat, BatchRecord},
+        book::{Book, BookOptions},
+        explanation::Explanation,
+        game::{Action, Game},
+        hint::HintLevel,
+        inferences::{InferenceSet, Technique},
+        locale::{Catalog, CoordsFormat, Message},
+        multi::{Layout, LayoutKind, MultiSudoku},
+        rules::{sum_combinations, LineKind, Relation, Rules},
+        svg::SvgOptions,
+        tui::{Key, Tui},
+        types::{Candidate, Cell, CellStatus, Difficulty, RCCoords, Sudoku, Unit},
+        utils::generate_combinations,
+        validator::{Mistake, MistakeKind},
+    };
 
     fn sovle(field: &Sudoku) {
         let mut field = field.clone();
@@ -171,6 +200,8 @@ mod tests {
         println!("{:?}", all_combinations);
     }
 
+    // 保持测试原有的写法，不按clippy改动
+    #[allow(clippy::ptr_arg)]
     fn is_n_fish_pair(v1: &Vec<(usize, usize)>, v2: &Vec<(usize, usize)>) -> bool {
         if v2.is_empty() {
             false
@@ -181,15 +212,1151 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_comparison)]
     fn is_n_fish_pair_test() {
         let v1 = vec![(0, 1), (0, 5)];
         let v2 = vec![(7, 1), (7, 5)];
         assert!(is_n_fish_pair(&v1, &v2) == true);
     }
 
+    #[test]
+    fn serde_json_test() {
+        let mut field = Sudoku::initial_by_string(
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060"
+                .to_string(),
+        )
+        .unwrap();
+        let ifs = InferenceSet::new();
+        let old_field = field.clone();
+        let result = ifs.analyze(&old_field).unwrap();
+        let json = serde_json::to_string(&result).unwrap();
+        println!("{}", json);
+        assert!(json.contains("\"technique\""));
+        InferenceSet::apply(&mut field, result);
+
+        let json = serde_json::to_string(&field).unwrap();
+        println!("{}", json);
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_compact_string(), field.to_compact_string());
+
+        // 省略候选数时按给定数重新计算
+        let restored: Sudoku = serde_json::from_str(
+            r#"{"givens":"070009800008002006906100000600000150030801020092000008000003701800600300001900060","values":"000000000000000000000000000000000000000000000000000000000000000000000000000000000"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            restored
+                .get_cell_ref_by_rc(RCCoords { r: 0, c: 0 })
+                .candidates,
+            field.get_cell_ref_by_rc(RCCoords { r: 0, c: 0 }).candidates
+        );
+    }
+
+    #[test]
+    fn serde_compact_test() {
+        let field = Sudoku::initial_by_string(
+            "615800790290600015040000260000080000730512046000090000080000030900008071071060582"
+                .to_string(),
+        )
+        .unwrap();
+        let compact = field.to_compact_string();
+        let restored = Sudoku::from_compact_string(&compact).unwrap();
+        assert_eq!(restored.to_compact_string(), compact);
+
+        let cell = field.get_cell_ref_by_rc(RCCoords { r: 0, c: 3 });
+        let json = serde_json::to_string(cell).unwrap();
+        println!("{}", json);
+        let restored: Cell = serde_json::from_str(&json).unwrap();
+        assert!(restored.coords == cell.coords && restored.value == cell.value);
+
+        assert!(Sudoku::from_compact_string("0:0").is_err());
+        assert!(serde_json::from_str::<Candidate>("[25]").is_err());
+    }
+
+    #[test]
+    fn place_unplace_test() {
+        let mut field = Sudoku::initial_by_string(
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060"
+                .to_string(),
+        )
+        .unwrap();
+        let original = field.clone();
+        let rc = RCCoords { r: 0, c: 0 };
+        let peer = RCCoords { r: 0, c: 2 };
+        let other_peer = RCCoords { r: 1, c: 1 };
+        assert!(field.get_cell_ref_by_rc(peer).candidates.contains(4));
+        assert!(field.place(RCCoords { r: 0, c: 1 }, 0).is_err());
+
+        field.eliminate_candidate(other_peer, 4).unwrap();
+        field.place(rc, 4).unwrap();
+        assert!(!field.get_cell_ref_by_rc(peer).candidates.contains(4));
+        assert!(field.find_conflict().is_none());
+
+        // 重新填写其他数值时，先恢复原数值的草稿
+        field.place(rc, 3).unwrap();
+        assert!(field.get_cell_ref_by_rc(peer).candidates.contains(4));
+        assert!(!field.get_cell_ref_by_rc(peer).candidates.contains(3));
+
+        // 清除时不恢复手动排除的草稿
+        field.unplace(rc, false).unwrap();
+        assert!(field.get_cell_ref_by_rc(peer).candidates.contains(3));
+        assert!(!field.get_cell_ref_by_rc(other_peer).candidates.contains(4));
+        assert_eq!(
+            field.get_cell_ref_by_rc(rc).candidates,
+            original.get_cell_ref_by_rc(rc).candidates
+        );
+        let restored = Sudoku::from_compact_string(&field.to_compact_string()).unwrap();
+        assert!(restored
+            .get_cell_ref_by_rc(other_peer)
+            .eliminated
+            .contains(4));
+
+        field.place(rc, 4).unwrap();
+        field.unplace(rc, true).unwrap();
+        assert!(field.get_cell_ref_by_rc(other_peer).candidates.contains(4));
+        assert_eq!(field.to_compact_string(), original.to_compact_string());
+    }
+
+    #[test]
+    fn game_undo_redo_test() {
+        let field = Sudoku::initial_by_string(
+            "615800790290600015040000260000080000730512046000090000080000030900008071071060582"
+                .to_string(),
+        )
+        .unwrap();
+        let mut game = Game::new(field.clone()).unwrap();
+        let rc = RCCoords { r: 0, c: 4 };
+        assert!(game.set_value(RCCoords { r: 0, c: 0 }, 1).is_err());
+
+        game.set_value(rc, 3).unwrap();
+        game.remove_candidate(RCCoords { r: 0, c: 5 }, 3).unwrap();
+        game.add_bookmark("before hint");
+        assert!(game.apply_hint().is_some());
+        assert_eq!(game.history().len(), 3);
+
+        assert!(matches!(game.undo(), Some(Action::ApplyHint { .. })));
+        assert!(matches!(game.undo(), Some(Action::RemoveCandidate { .. })));
+        assert!(!game
+            .field()
+            .get_cell_ref_by_rc(RCCoords { r: 0, c: 5 })
+            .eliminated
+            .contains(3));
+        assert!(matches!(game.redo(), Some(Action::RemoveCandidate { .. })));
+        game.clear_value(rc).unwrap();
+        assert!(!game.can_redo());
+        assert_eq!(game.field().get_cell_ref_by_rc(rc).value, None);
+
+        game.restore_bookmark("before hint").unwrap();
+        assert_eq!(game.history().len(), 2);
+        assert_eq!(game.field().get_cell_ref_by_rc(rc).value, Some(3));
+        assert!(game.restore_bookmark("missing").is_err());
+
+        let json = serde_json::to_string(&game).unwrap();
+        let restored: Game = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.bookmarks(), vec!["before hint"]);
+
+        // 6x6盘面反序列化后撤销，格子仍使用盘面自己的宫号
+        let mut game = Game::generate_with(Rules::new(2, 3).unwrap(), Difficulty::EASY);
+        assert_eq!(game.field().size(), 6);
+        let rc = game
+            .field()
+            .iter_rc()
+            .find(|&rc| {
+                rc.r == 5 && game.field().get_cell_ref_by_rc(rc).status == CellStatus::DRAFT
+            })
+            .unwrap();
+        let coords = game.field().coords_of(rc);
+        game.set_value(rc, 0).unwrap();
+        let json = serde_json::to_string(&game).unwrap();
+        let mut game: Game = serde_json::from_str(&json).unwrap();
+        assert!(game.undo().is_some());
+        assert_eq!(game.field().get_cell_ref_by_rc(rc).coords, coords);
+        assert!(game.redo().is_some());
+        assert_eq!(game.field().get_cell_ref_by_rc(rc).coords, coords);
+
+        let mut game = Game::new(field).unwrap();
+        while game.apply_hint().is_some() {}
+        assert!(game.is_solved());
+    }
+
+    #[test]
+    fn mistake_detection_test() {
+        let field = Sudoku::initial_by_string(
+            "615800790290600015040000260000080000730512046000090000080000030900008071071060582"
+                .to_string(),
+        )
+        .unwrap();
+        let mut game = Game::new(field.clone()).unwrap();
+        game.set_detect_early(true);
+        let solution = game.solution().clone();
+        let answer = |rc| solution.get_cell_ref_by_rc(rc).value.unwrap();
+
+        let rc = RCCoords { r: 0, c: 4 };
+        let wrong_rc = RCCoords { r: 0, c: 5 };
+        let wrong = (answer(wrong_rc) + 1) % 9;
+        assert!(game.check().is_empty());
+        game.set_value(rc, answer(rc)).unwrap();
+        assert!(game.check().is_empty());
+        assert!(game.flagged_moves().is_empty());
+
+        let draft = RCCoords { r: 1, c: 2 };
+        let draft_answer = answer(draft);
+        game.remove_candidate(draft, draft_answer).unwrap();
+        game.set_value(wrong_rc, wrong).unwrap();
+        assert_eq!(game.flagged_moves(), vec![1]);
+        let mistakes = game.check();
+        assert!(mistakes.contains(&Mistake {
+            coords: draft,
+            value: draft_answer,
+            kind: MistakeKind::EliminatedAnswer,
+        }));
+        assert!(mistakes
+            .iter()
+            .any(|m| m.kind == MistakeKind::WrongValue && m.value == wrong));
+        // 填错的值自动移除的同行列宫草稿不算排除了答案
+        assert_eq!(
+            mistakes
+                .iter()
+                .filter(|m| m.kind == MistakeKind::EliminatedAnswer)
+                .count(),
+            1
+        );
+        assert!(!game.field().is_solvable());
+
+        game.undo();
+        game.undo();
+        assert!(game.check().is_empty());
+        assert!(game.field().is_solvable());
+    }
+
+    #[test]
+    fn progressive_hint_test() {
+        let field = Sudoku::initial_by_string(
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060"
+                .to_string(),
+        )
+        .unwrap();
+        let mut level = Some(HintLevel::Unit);
+        while let Some(l) = level {
+            let hint = field.hint(l).unwrap();
+            println!("{}", serde_json::to_string(&hint).unwrap());
+            assert!(!hint.units.is_empty());
+            assert_eq!(hint.technique.is_some(), l >= HintLevel::Technique);
+            assert_eq!(hint.condition.is_some(), l >= HintLevel::Condition);
+            assert_eq!(hint.explanation.is_some(), l == HintLevel::Conclusion);
+            level = l.next();
+        }
+    }
+
+    #[test]
+    fn explanation_test() {
+        for puzzle in [
+            "000000010400000000020000000000050407008000300001090000300400200050100000000806000",
+            "800000000003600000070090200050007000000045700000100030001000068008500010090000400",
+            "000000000000010000012304560000000000035000780081020350000000000057000630063807210",
+            "900400613320190700000000009000017008000000000700360000800000000009045086253001004",
+        ] {
+            let mut field = Sudoku::initial_by_string(puzzle.to_string()).unwrap();
+            let ifs = InferenceSet::new();
+            while !field.check_if_finish() {
+                let old_field = field.clone();
+                let Some(result) = ifs.analyze(&old_field) else {
+                    break;
+                };
+                let explanation = result.explain();
+                println!("{}", explanation);
+                assert_eq!(explanation.technique, result.technique());
+                assert!(!explanation.describe().is_empty());
+                // 除暴力破解外，所有高亮的草稿都必须真实存在于盘面上
+                if explanation.technique != Technique::BruteForce {
+                    assert!(!explanation.base_units.is_empty());
+                    assert!(explanation.highlights.iter().all(|h| {
+                        old_field
+                            .get_cell_ref_by_rc(h.coords)
+                            .candidates
+                            .contains(h.value)
+                    }));
+                }
+                assert!(explanation
+                    .eliminations
+                    .iter()
+                    .all(|e| !explanation.placements.contains(e)));
+                InferenceSet::apply(&mut field, result);
+            }
+            assert!(field.find_conflict().is_none());
+        }
+    }
+
+    #[test]
+    fn localization_test() {
+        let field = Sudoku::initial_by_string(
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060"
+                .to_string(),
+        )
+        .unwrap();
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        let rc: RCCoords = result.condition()[0].the_coords.into();
+
+        let chinese = result.explanation_with(&Catalog::builtin("zh-CN").unwrap());
+        let english = result.explanation_with(&Catalog::builtin("en").unwrap());
+        let chess =
+            result.explanation_with(&Catalog::english().with_coords_format(CoordsFormat::Chess));
+        println!("{}\n{}\n{}", chinese, english, chess);
+        assert_eq!(chinese, result.explanation());
+        assert!(chinese.contains(&format!("{:?}", rc)));
+        assert!(english.contains(&format!("r{}c{}", rc.r + 1, rc.c + 1)));
+        assert!(chess.contains(&CoordsFormat::Chess.format_coords(rc)));
+        assert!(Catalog::builtin("fr").is_none());
+
+        // 自定义语言只提供部分内容时，缺少的部分使用英文
+        let catalog: Catalog = serde_json::from_str(
+            r#"{"coords_format":"RowColumn","techniques":{"NakedSingle":"Singleton"},"messages":{"Elimination":"{cells} <> {values}"}}"#,
+        )
+        .unwrap();
+        assert_eq!(catalog.technique_name(Technique::NakedSingle), "Singleton");
+        assert_eq!(catalog.technique_name(Technique::Fish), "Fish");
+        assert_eq!(
+            catalog.message(Message::BruteForce, &[]),
+            Catalog::english().message(Message::BruteForce, &[])
+        );
+        println!("{}", result.explanation_with(&catalog));
+    }
+
+    #[test]
+    fn notation_test() {
+        let x_wing = "X-Wing: 5 r26 c37 => r4c3<>5";
+        let explanation = Explanation::from_notation(x_wing).unwrap();
+        assert_eq!(explanation.technique, Technique::Fish);
+        assert_eq!(explanation.base_units, vec![Unit::Row(1), Unit::Row(5)]);
+        assert_eq!(
+            explanation.cover_units,
+            vec![Unit::Column(2), Unit::Column(6)]
+        );
+        assert_eq!(explanation.eliminations[0].coords, RCCoords { r: 3, c: 2 });
+        assert_eq!(explanation.eliminations[0].value, 4);
+        assert_eq!(explanation.to_notation(), x_wing);
+        assert!(Explanation::from_notation("X-Wing: 5 r2 c37 => r4c3<>5").is_err());
+        assert!(Explanation::from_notation("Naked Single: r3c5=0").is_err());
+        assert!(Explanation::from_notation("Unknown: r3c5=1").is_err());
+
+        for puzzle in [
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060",
+            "000000000000010000012304560000000000035000780081020350000000000057000630063807210",
+        ] {
+            let mut field = Sudoku::initial_by_string(puzzle.to_string()).unwrap();
+            let ifs = InferenceSet::new();
+            while !field.check_if_finish() {
+                let old_field = field.clone();
+                let Some(result) = ifs.analyze(&old_field) else {
+                    break;
+                };
+                let notation = result.notation();
+                println!("{}", notation);
+                let parsed = Explanation::from_notation(&notation).unwrap();
+                assert_eq!(parsed.technique, result.technique());
+                assert_eq!(parsed.to_notation(), notation);
+                let explanation = result.explain();
+                if !matches!(
+                    explanation.technique,
+                    Technique::NakedSingle | Technique::HiddenSingle
+                ) {
+                    assert_eq!(parsed.eliminations.len(), explanation.eliminations.len());
+                    assert!(parsed
+                        .eliminations
+                        .iter()
+                        .all(|e| explanation.eliminations.contains(e)));
+                }
+                assert_eq!(parsed.placements, explanation.placements);
+                InferenceSet::apply(&mut field, result);
+            }
+        }
+
+        // 16x16的坐标和数字超过9，用字母表示
+        let rules = Rules::with_size(16).unwrap();
+        let single = Explanation::from_notation_with("Naked Single: rAcG=G", &rules).unwrap();
+        assert_eq!(single.placements[0].value, 15);
+        assert!(single
+            .describe_with(&Catalog::english())
+            .contains("r10c16 must be G"));
+        assert!(Explanation::from_notation_with("Naked Single: rAcG=H", &rules).is_err());
+        assert!(Explanation::from_notation("Naked Single: rAc1=1").is_err());
+        let mut field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        let ifs = InferenceSet::new();
+        for _ in 0..60 {
+            let old_field = field.clone();
+            let Some(result) = ifs.analyze(&old_field) else {
+                break;
+            };
+            let notation = result.notation();
+            let parsed = Explanation::from_notation_with(&notation, &rules).unwrap();
+            assert_eq!(parsed.to_notation(), notation);
+            assert_eq!(parsed.placements, result.explain().placements);
+            InferenceSet::apply(&mut field, result);
+        }
+    }
+
+    #[test]
+    fn line_format_test() {
+        let line =
+            "..9..4...458900032079000000006007420000690000010082050900040080042008790000730204";
+        let field = Sudoku::from_line(line).unwrap();
+        assert_eq!(field.to_value_string(), line.replace('.', "0"));
+        assert!(Sudoku::from_line(&line.replace('.', "x")).is_err());
+        assert!(Sudoku::from_line(&line[1..]).is_err());
+        assert_eq!("Medium".parse::<Difficulty>(), Ok(Difficulty::MIDIUM));
+        assert!("impossible".parse::<Difficulty>().is_err());
+        assert_eq!(field.to_grid_string().lines().count(), 37);
+    }
+
+    #[test]
+    fn batch_test() {
+        let input = "\
+# comment
+070009800008002006906100000600000150030801020092000008000003701800600300001900060
+
+000000000000000000000000000000000000000000000000000000000000000000000000000000000
+770009800008002006906100000600000150030801020092000008000003701800600300001900060
+12345
+";
+        let mut output = vec![];
+        let summary = batch::run(input.as_bytes(), &mut output, BatchFormat::Csv, 2).unwrap();
+        assert_eq!(summary.total, 4);
+        assert_eq!(summary.valid, 1);
+
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[0], BatchRecord::CSV_HEADER);
+        assert!(lines[1].starts_with("2,070009800"));
+        assert!(lines[2].starts_with("4,000000000"));
+        assert!(lines[2].contains(",false,2,"));
+        assert!(lines[3].ends_with("Puzzle Has Conflicts."));
+        assert!(lines[4].starts_with("6,12345,false,0,"));
+
+        let record = BatchRecord::process(1, &lines[1][2..83]);
+        assert!(record.valid && record.solved);
+        assert!(record.to_json().contains("\"solutions\":1"));
+
+        // 需要暴力破解的题目有唯一解，但不算靠推理解出
+        let escargot = BatchRecord::process(
+            1,
+            "100007090030020008009600500005300900010080002600004000300000010040000007007000300",
+        );
+        assert!(escargot.valid && !escargot.solved);
+        assert_eq!(escargot.hardest, Some(Technique::BruteForce));
+    }
+
+    #[test]
+    fn svg_test() {
+        let field = Sudoku::initial_by_string(
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060"
+                .to_string(),
+        )
+        .unwrap();
+        let svg = field.to_svg();
+        assert!(svg.starts_with("<svg") && svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("font-weight=\"bold\"").count(), 28);
+        assert!(!svg.contains("<circle"));
+
+        let options = SvgOptions {
+            show_candidates: false,
+            ..Default::default()
+        };
+        assert!(!field
+            .to_svg_with(&options, None)
+            .contains("font-size=\"15\""));
+
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        let explanation = result.explain();
+        let svg = result.to_svg(&field, &SvgOptions::default());
+        assert_eq!(svg.matches("<circle").count(), explanation.highlights.len());
+    }
+
+    #[test]
+    fn book_test() {
+        let book = Book::generate(3, &[Difficulty::EASY, Difficulty::HARD]);
+        let difficulties: Vec<Difficulty> = book.entries.iter().map(|e| e.difficulty).collect();
+        assert_eq!(
+            difficulties,
+            vec![Difficulty::EASY, Difficulty::EASY, Difficulty::HARD]
+        );
+        assert!(book.entries.iter().all(|e| e.solution.check_if_finish()));
+
+        let options = BookOptions {
+            title: "Weekly #1".to_string(),
+            per_page: 2,
+            ..Default::default()
+        };
+        let latex = book.to_latex(&options).unwrap();
+        println!("{}", latex);
+        assert_eq!(latex.matches("\\newpage").count(), 3);
+        assert_eq!(latex.matches("\\begin{tikzpicture}").count(), 6);
+        assert!(latex.contains("Weekly \\#1 -- Answers"));
+        assert!(book
+            .to_latex(&BookOptions {
+                per_page: 0,
+                ..Default::default()
+            })
+            .is_err());
+
+        let empty = Sudoku::from_line(&"0".repeat(81)).unwrap();
+        assert!(Book::from_puzzles(vec![empty]).is_err());
+
+        // 网格线落在格子的边上，6x6的宫是2行3列
+        let small = Sudoku::generate(Rules::new(2, 3).unwrap(), Difficulty::EASY);
+        let latex = Book::from_puzzles(vec![small])
+            .unwrap()
+            .to_latex(&Default::default())
+            .unwrap();
+        let thin = latex
+            .lines()
+            .find(|line| line.starts_with(r"\draw[gray!60,thin]"))
+            .unwrap();
+        assert_eq!(thin.matches(" -- ").count(), 14);
+        assert!(thin.contains("(5,0) -- (5,6)"));
+        let thick = latex
+            .lines()
+            .find(|line| line.starts_with(r"\draw[very thick]"))
+            .unwrap();
+        assert_eq!(
+            thick,
+            r"\draw[very thick] (0,0) -- (0,6) (3,0) -- (3,6) (6,0) -- (6,6) (0,0) -- (6,0) (0,2) -- (6,2) (0,4) -- (6,4) (0,6) -- (6,6);"
+        );
+    }
+
+    #[test]
+    fn formats_test() {
+        let puzzle =
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060";
+        let mut field = Sudoku::from_line(puzzle).unwrap();
+        println!("{}", field.to_ss().unwrap());
+        assert_eq!(
+            Sudoku::from_ss(&field.to_ss().unwrap())
+                .unwrap()
+                .to_value_string(),
+            puzzle
+        );
+        let sdk = format!("#A someone\n[Puzzle]\n{}\n", field.to_sdk().unwrap());
+        assert_eq!(Sudoku::from_sdk(&sdk).unwrap().to_value_string(), puzzle);
+        let url = field.to_sudokuwiki_url().unwrap();
+        assert_eq!(Sudoku::from_url(&url).unwrap().to_value_string(), puzzle);
+        let fresh_url = field.to_fpuzzles_url().unwrap();
+        assert!(!fresh_url.contains("%"));
+
+        field.place(RCCoords { r: 0, c: 0 }, 0).unwrap();
+        field
+            .eliminate_candidate(RCCoords { r: 0, c: 2 }, 2)
+            .unwrap();
+
+        let sdx = field.to_sdx().unwrap();
+        println!("{}", sdx);
+        let restored = Sudoku::from_sdx(&sdx).unwrap();
+        assert_eq!(restored.to_sdx().unwrap(), sdx);
+        assert_eq!(
+            restored.get_cell_ref_by_rc(RCCoords { r: 0, c: 0 }).status,
+            CellStatus::SOLVE
+        );
+
+        let line = field.to_hodoku_line().unwrap();
+        println!("{}", line);
+        assert!(line.starts_with(":0"));
+        assert!(line.contains(":+17.."));
+        let restored = Sudoku::from_hodoku_line(&line).unwrap();
+        assert_eq!(restored.to_sdx().unwrap(), sdx);
+
+        let url = field.to_fpuzzles_url().unwrap();
+        println!("{}", url);
+        assert!(url.len() > fresh_url.len());
+        assert_eq!(Sudoku::from_url(&url).unwrap().to_sdx().unwrap(), sdx);
+        let restored = Sudoku::from_url(&fresh_url).unwrap();
+        assert_eq!(restored.to_value_string(), puzzle);
+
+        assert!(Sudoku::from_ss("123").is_err());
+        assert!(Sudoku::from_hodoku_line("0000:1:...").is_err());
+        assert!(Sudoku::from_url("https://example.com/?load=@@@").is_err());
+        assert!(Sudoku::from_url("https://example.com/").is_err());
+
+        // 第三方格式只支持标准9x9数独
+        let six = Sudoku::generate(Rules::new(2, 3).unwrap(), Difficulty::EASY);
+        assert!(six.to_ss().is_err());
+        assert!(six.to_sdx().is_err());
+        assert!(six.to_hodoku_line().is_err());
+        let diagonal = Sudoku::from_line_with(puzzle, Rules::classic().with_diagonals()).unwrap();
+        assert!(diagonal.to_sudokuwiki_url().is_err());
+    }
+
+    #[test]
+    fn tui_test() {
+        let field = Sudoku::initial_by_string(
+            "615800790290600015040000260000080000730512046000090000080000030900008071071060582"
+                .to_string(),
+        )
+        .unwrap();
+        let mut tui = Tui::new(Game::new(field).unwrap(), Difficulty::EASY);
+        let keys = Key::parse(b"\x1b[C\x1b[C\x1b[C\x1b[Cx4\r\n");
+        assert_eq!(keys.len(), 6);
+        assert_eq!(keys[5], Key::Digit(3));
+        for key in keys {
+            tui.handle_key(key);
+        }
+        let rc = RCCoords { r: 0, c: 4 };
+        assert_eq!(tui.cursor(), rc);
+        assert_eq!(tui.game().field().get_cell_ref_by_rc(rc).value, Some(3));
+
+        tui.handle_key(Key::Char('u'));
+        assert!(!tui.game().can_undo());
+        tui.handle_key(Key::Char('p'));
+        tui.handle_key(Key::Digit(2));
+        assert!(!tui
+            .game()
+            .field()
+            .get_cell_ref_by_rc(rc)
+            .candidates
+            .contains(2));
+
+        tui.handle_key(Key::Char('?'));
+        assert!(tui.render().contains("\x1b[32m"));
+        tui.handle_key(Key::Char('?'));
+        assert_eq!(tui.game().history().len(), 2);
+        assert!(!tui.render().contains("\x1b[32m"));
+
+        tui.handle_key(Key::Char('q'));
+        assert!(tui.is_quit());
+    }
+
+    #[test]
+    fn grid_sizes_test() {
+        for (box_rows, box_cols) in [(2, 2), (2, 3), (3, 2), (3, 4), (4, 4)] {
+            let rules = Rules::new(box_rows, box_cols).unwrap();
+            let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+            field.print();
+            assert_eq!(field.size(), box_rows * box_cols);
+            assert!(field.find_conflict().is_none());
+            let solution = field.sovle();
+            assert_eq!(solution.len(), 1);
+            assert!(solution[0].find_conflict().is_none());
+            assert!(InferenceSet::new().rate(&field).solved);
+
+            // 默认形状以外的宫需要在JSON中记录规则
+            let json = serde_json::to_string(&field).unwrap();
+            assert_eq!(json.contains("box_rows"), !rules.is_default_for_size());
+            let restored: Sudoku = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored.rules(), &rules);
+            assert_eq!(restored.to_compact_string(), field.to_compact_string());
+        }
+
+        let line = "1.3.......4..2......1...3.4.2..1..";
+        assert!(Sudoku::from_line(line).is_err());
+        let field = Sudoku::from_line("1..4..1.2.4..3.1").unwrap();
+        assert_eq!(field.rules().box_rows(), 2);
+        assert_eq!(field.sovle()[0].to_value_string(), "1234341221434321");
+        assert_eq!(field.sovle().len(), 1);
+        assert!(Sudoku::from_line("1..5..1.2.4..3.2").is_err());
+        let hexadoku = Sudoku::generate(Rules::with_size(16).unwrap(), Difficulty::EASY);
+        let compact = hexadoku.to_compact_string();
+        assert!(compact.contains('G') || compact.contains('A'));
+        assert_eq!(Sudoku::from_compact_string(&compact).unwrap().size(), 16);
+    }
+
+    #[test]
+    fn jigsaw_test() {
+        let regions = "AAAAABBBB AAAABBBBB CCCCCDDDD CCCCDDDDD EEEEEFFFF \
+                       EEEEFFFFF GGGGGHHHH GGGGHHHHH IIIIIIIII";
+        let rules = Rules::jigsaw(regions).unwrap();
+        assert!(rules.is_jigsaw());
+        assert_eq!(&rules.regions()[..18], "111112222111122222");
+        assert!(Rules::jigsaw(&regions.replace('I', "H")).is_err());
+        assert!(Rules::jigsaw(&regions.replacen("AAAAABBBB", "BAAAABBBA", 1)).is_err());
+
+        // R1C5和R2C1在标准数独中互不相关，在锯齿数独中属于同一区域
+        let line = format!("....1{}1{}", ".".repeat(4), ".".repeat(71));
+        assert!(Sudoku::from_line(&line).unwrap().find_conflict().is_none());
+        let field = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        assert!(field.find_conflict().is_some());
+
+        let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        field.print();
+        assert!(field.find_conflict().is_none());
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        assert!(InferenceSet::new().rate(&field).solved);
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("regions"));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+        assert!(field.to_grid_string().contains("╬═══╬"));
+
+        // 紧凑字符串附带区域图，不指定规则也能还原
+        let compact = field.to_compact_string();
+        let restored = Sudoku::from_compact_string(&compact).unwrap();
+        assert_eq!(restored.rules(), &rules);
+        assert_eq!(restored.sovle().len(), 1);
+        let (plain, _) = compact.split_once('|').unwrap();
+        let restored = Sudoku::from_compact_string_with(plain, rules.clone()).unwrap();
+        assert_eq!(restored.rules(), &rules);
+        assert!(!Sudoku::from_line(&field.to_value_string())
+            .unwrap()
+            .to_compact_string()
+            .contains('|'));
+    }
+
+    #[test]
+    fn diagonal_test() {
+        let rules = Rules::classic().with_diagonals();
+        assert_eq!(rules.extra_units(), [Unit::Diagonal(0), Unit::Diagonal(1)]);
+        let center = rules.coords(RCCoords { r: 4, c: 4 });
+        assert_eq!(rules.units_of(center).len(), 5);
+
+        // R1C1和R9C9只在对角线上相关
+        let line = format!("1{}1", ".".repeat(79));
+        assert!(Sudoku::from_line(&line).unwrap().find_conflict().is_none());
+        let field = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        assert!(field.find_conflict().is_some());
+        let line = format!("1{}", ".".repeat(80));
+        let field = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        let center = field.get_cell_ref_by_rc(RCCoords { r: 4, c: 4 });
+        assert!(!center.candidates.contains(0));
+
+        // 对角线上只剩R1C1可以填1
+        let mut field = Sudoku::blank(rules.clone());
+        for i in 1..9 {
+            field
+                .eliminate_candidate(RCCoords { r: i, c: i }, 0)
+                .unwrap();
+        }
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::HiddenSingle);
+        assert_eq!(result.units(), vec![Unit::Diagonal(0)]);
+        assert!(result.notation().contains("r1c1"));
+
+        let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        assert!(InferenceSet::new().rate(&field).solved);
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("\"diagonals\":true"));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+    }
+
+    #[test]
+    fn windows_test() {
+        let rules = Rules::classic().with_windows();
+        assert_eq!(rules.window_origins(), vec![(1, 1), (1, 5), (5, 1), (5, 5)]);
+        assert_eq!(rules.unit_coords(Unit::Window(3)), Unit::Window(3).coords());
+        assert_eq!(
+            rules.units_of(rules.coords(RCCoords { r: 2, c: 2 })).len(),
+            4
+        );
+        assert_eq!(
+            Rules::new(2, 3).unwrap().with_windows().extra_units().len(),
+            1
+        );
+
+        // R2C2和R4C4只在窗口内相关
+        let mut line = vec!['.'; 81];
+        line[10] = '5';
+        line[30] = '5';
+        let line: String = line.into_iter().collect();
+        assert!(Sudoku::from_line(&line).unwrap().find_conflict().is_none());
+        let field = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        assert!(field.find_conflict().is_some());
+        assert_eq!(
+            Catalog::english().format_unit(Unit::Window(1)),
+            "w2".to_string()
+        );
+
+        let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("\"windows\":true"));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+    }
+
+    #[test]
+    fn anti_chess_test() {
+        let rules = Rules::classic().with_anti_knight();
+        let center = rules.index(RCCoords { r: 4, c: 4 });
+        assert_eq!(rules.peers(center).len(), 28);
+        assert!(rules.peers(0).contains(&11));
+        let king = Rules::classic().with_anti_king();
+        assert!(king.peers(20).contains(&30));
+        assert!(!king.peers(20).contains(&31));
+
+        // R1C1和R2C3只在马步上相关
+        let line = format!("..5.........5{}", ".".repeat(68));
+        assert!(Sudoku::from_line(&line).unwrap().find_conflict().is_none());
+        let line = format!("5.........5{}", ".".repeat(70));
+        let field = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        assert!(field.find_conflict().is_some());
+        let field = Sudoku::blank(rules.clone());
+        let r1c1 = rules.coords(RCCoords { r: 0, c: 0 });
+        let r2c3 = rules.coords(RCCoords { r: 1, c: 2 });
+        assert!(field
+            .collect_all_drafts_coords_by_coords_and_value(r1c1, 0)
+            .contains(&r2c3));
+
+        let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("\"anti_knight\":true"));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+    }
+
+    #[test]
+    fn killer_test() {
+        assert_eq!(sum_combinations(2, 3, 9).len(), 1);
+        assert_eq!(sum_combinations(3, 24, 9)[0].to_vec(), vec![6, 7, 8]);
+        assert!(sum_combinations(2, 18, 9).is_empty());
+        let blank = ".".repeat(79);
+        assert!(Rules::classic()
+            .with_cage_map(&format!("A.{}=10", blank))
+            .is_err());
+        assert!(Rules::classic()
+            .with_cage_map(&format!("AB{}=3", blank))
+            .is_err());
+
+        // 两格和为3的笼子只能填1和2
+        let rules = Rules::classic()
+            .with_cage_map(&format!("AA{}=3", blank))
+            .unwrap();
+        let r1c1 = rules.coords(RCCoords { r: 0, c: 0 });
+        assert_eq!(rules.units_of(r1c1).last(), Some(&Unit::Cage(0)));
+        let field = Sudoku::blank(rules.clone());
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::CageCombination);
+        assert_eq!(result.units(), vec![Unit::Cage(0)]);
+        assert!(result
+            .notation()
+            .starts_with("Cage Combination: 1,2 in r1c12"));
+        let mut domains = field.cell_domains();
+        assert!(rules.prune(&mut domains));
+        assert_eq!(domains[1].to_vec(), vec![0, 1]);
+
+        // 第1行除R1C9外被两个笼子覆盖，45-20-16=9
+        let map = format!("AAAABBBB.{}", ".".repeat(72));
+        let rules = Rules::classic()
+            .with_cage_map(&format!("{}=20,16", map))
+            .unwrap();
+        let field = Sudoku::blank(rules);
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::Rule45);
+        assert_eq!(
+            result.units(),
+            vec![Unit::Row(0), Unit::Cage(0), Unit::Cage(1)]
+        );
+        assert_eq!(result.notation(), "Rule of 45: r1c9=9");
+
+        // 第1行只差R1C8和R1C9，两格之和为45-20-12=13，不能填写1、2、3
+        let map = format!("AAAABBB..{}", ".".repeat(72));
+        let rules = Rules::classic()
+            .with_cage_map(&format!("{}=20,12", map))
+            .unwrap();
+        let mut field = Sudoku::blank(rules.clone());
+        let old_field = field.clone();
+        let result = ifs.analyze(&old_field).unwrap();
+        assert_eq!(result.technique(), Technique::Rule45);
+        let notation = result.notation();
+        assert_eq!(
+            notation,
+            "Rule of 45: 1,2,3,4,5,6,7,8,9 in r1c89 => r1c89<>1, r1c89<>2, r1c89<>3"
+        );
+        let parsed = Explanation::from_notation_with(&notation, &rules).unwrap();
+        assert_eq!(parsed.to_notation(), notation);
+        assert!(result
+            .explanation_with(&Catalog::english())
+            .contains("differ only by r1c8 r1c9"));
+        InferenceSet::apply(&mut field, result);
+        assert_eq!(
+            field
+                .get_cell_ref_by_rc(RCCoords { r: 0, c: 8 })
+                .candidates
+                .to_vec(),
+            vec![3, 4, 5, 6, 7, 8]
+        );
+
+        let field = Sudoku::generate_killer(Rules::classic(), Difficulty::EASY);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        for cage in field.rules().cages() {
+            let sum: u32 = cage
+                .cells
+                .iter()
+                .map(|&idx| {
+                    solution[0]
+                        .get_cell_ref_by_rc(RCCoords::from_idx(idx))
+                        .value
+                        .unwrap() as u32
+                        + 1
+                })
+                .sum();
+            assert_eq!(sum, cage.sum);
+        }
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("\"cages\""));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), field.rules());
+        assert!(field.to_svg().contains("stroke-dasharray"));
+    }
+
+    #[test]
+    fn kropki_test() {
+        assert!(Relation::Consecutive.allows(2, 3));
+        assert!(Relation::Double.allows(1, 3));
+        assert!(!Relation::NotDouble.allows(3, 1));
+        assert!(!Relation::NotConsecutive.allows(5, 4));
+        assert!(Rules::classic()
+            .with_dot_list("r1c1-r1c3=w", false)
+            .is_err());
+        assert!(Rules::classic()
+            .with_dot_list("r1c1-r1c2=x", false)
+            .is_err());
+        assert!(Rules::classic()
+            .with_dot_list("r1c1-r1c2=w,r1c2-r1c1=b", false)
+            .is_err());
+
+        // 黑点两侧只能是1、2、3、4、6、8
+        let rules = Rules::classic()
+            .with_dot_list("r1c1-r1c2=b", false)
+            .unwrap();
+        let field = Sudoku::blank(rules.clone());
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::Kropki);
+        assert_eq!(
+            result.notation(),
+            "Kropki Black Dot: 1,2,3,4,5,6,7,8,9 in r1c1 => r1c2<>5, r1c2<>7, r1c2<>9"
+        );
+        assert_eq!(result.explain().relation, Some(Relation::Double));
+        let mut domains = field.cell_domains();
+        assert!(rules.prune(&mut domains));
+        assert_eq!(domains[0].to_vec(), vec![0, 1, 2, 3, 5, 7]);
+        assert!(field.to_svg().contains("<circle"));
+
+        let rules = Rules::classic().with_non_consecutive();
+        assert_eq!(rules.relations().len(), 144);
+        let line = format!("12{}", ".".repeat(79));
+        assert!(Sudoku::from_line(&line).unwrap().find_conflict().is_none());
+        let field = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        assert!(field.find_conflict().is_some());
+        let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("\"non_consecutive\":true"));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+    }
+
+    #[test]
+    fn line_test() {
+        assert!(Rules::classic().with_line_list("r1c1-r1c3=t").is_err());
+        assert!(Rules::classic().with_line_list("r1c1-r1c2=x").is_err());
+        assert!(Rules::classic().with_line_list("r1c1=t").is_err());
+        assert!(Rules::classic().with_line_list("r1c1-r1c2-r1c1=r").is_err());
+
+        let rules = Rules::classic()
+            .with_line_list("r1c1-r1c2-r1c3=t,r9c9-r8c8-r7c7=a,r5c1-r5c2=w,r3c3-r4c4=r")
+            .unwrap();
+        let field = Sudoku::blank(rules.clone());
+        let domains = field.cell_domains();
+        assert_eq!(
+            rules.line_candidates(0, &domains)[0].to_vec(),
+            (0..7).collect::<Vec<u8>>()
+        );
+        assert_eq!(
+            rules.line_candidates(1, &domains)[0].to_vec(),
+            (1..9).collect::<Vec<u8>>()
+        );
+        assert!(!rules.line_candidates(2, &domains)[0].contains(4));
+        assert!(rules.peers(20).contains(&30));
+
+        // 温度计的圆头最大为7
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::Line);
+        assert!(result.notation().starts_with("Thermometer: "));
+        assert!(result.notation().ends_with("r1c1<>8, r1c1<>9"));
+        assert_eq!(result.explain().line, Some(LineKind::Thermometer));
+        let line = format!("21{}", ".".repeat(79));
+        assert!(Sudoku::from_line(&line).unwrap().find_conflict().is_none());
+        let conflict = Sudoku::from_line_with(&line, rules.clone()).unwrap();
+        assert!(conflict.find_conflict().is_some());
+
+        let field = Sudoku::generate(rules.clone(), Difficulty::EASY);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        let json = serde_json::to_string(&field).unwrap();
+        assert!(json.contains("\"lines\""));
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+        assert!(field.to_svg().contains("<polyline"));
+
+        // 6x6的德国悄悄话相邻数字至少相差3，3旁边只能是6
+        let rules = Rules::new(2, 3)
+            .unwrap()
+            .with_line_list("r1c1-r1c2=w")
+            .unwrap();
+        let field = Sudoku::from_line_with(&format!("3{}", ".".repeat(35)), rules).unwrap();
+        let explanation = ifs.analyze(&field).unwrap().explain();
+        assert_eq!(explanation.line, Some(LineKind::Whisper));
+        assert_eq!(explanation.gap, Some(3));
+        assert!(explanation
+            .describe_with(&Catalog::english())
+            .contains("differ by at least 3"));
+    }
+
+    #[test]
+    fn sandwich_xv_test() {
+        assert!(Rules::classic().with_sandwich_list("1,2,3").is_err());
+        assert!(Rules::classic().with_sandwich_list("36/").is_err());
+        assert!(Rules::classic().with_xv_list("r1c1-r1c3=x", false).is_err());
+        assert!(Rules::classic().with_xv_list("r1c1-r1c2=y", false).is_err());
+
+        // 第一行提示35时1和9只能在两端
+        let rules = Rules::classic()
+            .with_sandwich_list("35,.,.,.,.,.,.,.,./")
+            .unwrap();
+        let field = Sudoku::blank(rules.clone());
+        let allowed = rules.sandwich_candidates(0, &field.cell_domains());
+        assert_eq!(allowed[0].to_vec(), vec![0, 8]);
+        assert_eq!(allowed[4].to_vec(), (1..8).collect::<Vec<u8>>());
+        let ifs = InferenceSet::new();
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::Sandwich);
+        assert!(result.notation().starts_with("Sandwich: "));
+        println!("{}", result.explain().describe());
+
+        // 所有提示与经典题目的答案一致，仍然只有一个解
+        let rules = Rules::classic()
+            .with_sandwich_list("8,20,10,7,8,0,25,7,0/13,15,7,24,11,14,2,29,8")
+            .unwrap();
+        let line =
+            "070009800008002006906100000600000150030801020092000008000003701800600300001900060";
+        let field = Sudoku::from_line_with(line, rules.clone()).unwrap();
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].find_conflict().is_none());
+        let json = serde_json::to_string(&field).unwrap();
+        let restored: Sudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.rules(), &rules);
+        assert!(field.to_svg().contains(">25</text>"));
+        let conflict = format!("1234567890{}", ".".repeat(71));
+        let conflict = Sudoku::from_line_with(&conflict, rules).unwrap();
+        assert!(conflict.find_conflict().is_some());
+
+        // X两数之和为10，V两数之和为5
+        let rules = Rules::classic()
+            .with_xv_list("r1c1-r1c2=x,r2c1-r3c1=v", false)
+            .unwrap();
+        let field = Sudoku::blank(rules.clone());
+        let result = ifs.analyze(&field).unwrap();
+        assert_eq!(result.technique(), Technique::Xv);
+        assert_eq!(
+            result.notation(),
+            "XV X: 1,2,3,4,5,6,7,8,9 in r1c1 => r1c2<>5"
+        );
+        assert_eq!(result.explain().relation, Some(Relation::Sum(10)));
+        let parsed = Explanation::from_notation(&result.notation()).unwrap();
+        assert_eq!(parsed.technique, Technique::Xv);
+        let line = format!("19{}", ".".repeat(79));
+        assert!(Sudoku::from_line_with(&line, rules.clone())
+            .unwrap()
+            .find_conflict()
+            .is_none());
+        let line = format!("12{}", ".".repeat(79));
+        assert!(Sudoku::from_line_with(&line, rules.clone())
+            .unwrap()
+            .find_conflict()
+            .is_some());
+        let field = Sudoku::generate(
+            Rules::classic()
+                .with_xv_list("r1c1-r1c2=x,r2c1-r3c1=v", true)
+                .unwrap(),
+            Difficulty::EASY,
+        );
+        assert_eq!(field.sovle().len(), 1);
+        assert!(field.to_svg().contains(">V</text>"));
+    }
+
+    #[test]
+    fn multi_test() {
+        assert!("star".parse::<LayoutKind>().is_err());
+        let layout = Layout::new("Samurai".parse().unwrap());
+        assert_eq!((layout.rows(), layout.cols()), (21, 21));
+        assert_eq!(layout.grid_count(), 5);
+        assert_eq!(layout.cell_count(), 369);
+        assert_eq!(layout.shared_cells().len(), 36);
+        let owners: Vec<usize> = layout
+            .owners(RCCoords { r: 6, c: 6 })
+            .iter()
+            .map(|&(g, _)| g)
+            .collect();
+        assert_eq!(owners, vec![0, 2]);
+        assert!(layout.owners(RCCoords { r: 0, c: 10 }).is_empty());
+        let layout = Layout::new(LayoutKind::Butterfly);
+        assert_eq!(layout.cell_count(), 144);
+        assert_eq!(layout.owners(RCCoords { r: 4, c: 4 }).len(), 4);
+
+        // 共享格子的给定数必须一致
+        let blank = ".".repeat(81);
+        let corner = format!("{}1{}", ".".repeat(60), ".".repeat(20));
+        assert!(MultiSudoku::from_lines(LayoutKind::Twin, &[&blank]).is_err());
+        assert!(MultiSudoku::from_lines(LayoutKind::Twin, &[&corner, &blank]).is_err());
+
+        // 在共享格子填写数值，两个盘面的草稿同时更新
+        let mut field = MultiSudoku::blank(LayoutKind::Twin);
+        field.place(RCCoords { r: 6, c: 6 }, 4).unwrap();
+        assert_eq!(field.value(RCCoords { r: 6, c: 6 }), Some(4));
+        let second = &field.grids()[1];
+        assert_eq!(
+            second.get_cell_ref_by_rc(RCCoords { r: 0, c: 0 }).value,
+            Some(4)
+        );
+        assert!(!second
+            .get_cell_ref_by_rc(RCCoords { r: 0, c: 8 })
+            .candidates
+            .contains(4));
+        assert!(field.place(RCCoords { r: 0, c: 10 }, 4).is_err());
+
+        let field = MultiSudoku::generate(LayoutKind::Twin, Difficulty::EASY);
+        println!("{}", field.to_canvas_string());
+        assert_eq!(field.to_canvas_string().lines().count(), 15);
+        let solution = field.sovle();
+        assert_eq!(solution.len(), 1);
+        assert!(solution[0].check_if_finish());
+        assert!(solution[0].find_conflict().is_none());
+        let json = serde_json::to_string(&field).unwrap();
+        let restored: MultiSudoku = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.to_lines(), field.to_lines());
+        let lines = field.to_lines();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let parsed = MultiSudoku::from_lines(LayoutKind::Twin, &lines).unwrap();
+        assert_eq!(parsed.sovle()[0].to_lines(), solution[0].to_lines());
+    }
+
     #[test]
     fn test_initial_by_random() {
-        let field = Sudoku::new(crate::types::Difficulty::EASY);
-        // sovle(&field);
+        for difficulty in [Difficulty::EASY, Difficulty::MIDIUM, Difficulty::EXPERT] {
+            let field = Sudoku::new(difficulty);
+            field.print();
+            assert!(field.find_conflict().is_none());
+            assert_eq!(field.sovle().len(), 1);
+            println!("{:?}", InferenceSet::new().rate(&field));
+        }
     }
 }
@@ -0,0 +1,445 @@
+// 本地化：策略名称和文字说明的模板，内置中文和英文，其他语言可以自行构造或从JSON反序列化得到
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    inferences::Technique,
+    types::{RCCoords, Unit},
+};
+
+/// 坐标的书写格式
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum CoordsFormat {
+    // r1c1，行列宫为r1/c1/b1，对角线为d1/d2，窗口为w1，笼子为k1
+    RowColumn,
+    // A1，行用字母，列用数字
+    Chess,
+    // R1C1，与RCCoords的Debug输出一致，行列宫为R1/C1/G1，对角线为D1/D2，窗口为W1，笼子为K1
+    Debug,
+}
+
+impl CoordsFormat {
+    pub fn format_coords(&self, rc: RCCoords) -> String {
+        match self {
+            CoordsFormat::RowColumn => format!("r{}c{}", rc.r + 1, rc.c + 1),
+            CoordsFormat::Chess => format!("{}{}", (b'A' + rc.r as u8) as char, rc.c + 1),
+            CoordsFormat::Debug => format!("{:?}", rc),
+        }
+    }
+}
+
+/// 说明模板的键，模板中的{cell}、{cells}、{value}、{values}、{units}、{base_units}、{cover_units}、
+/// {eliminations}、{fish}、{n}会被替换为实际内容
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Message {
+    NakedSingle,
+    HiddenSingle,
+    // 填写数值后顺带移除的草稿
+    AlsoRemove,
+    LockedCandidates,
+    NakedSubset,
+    HiddenSubset,
+    Fish,
+    CageCombination,
+    Rule45,
+    Rule45Pair,
+    // 相邻格的四种关系
+    Consecutive,
+    Double,
+    NotConsecutive,
+    NotDouble,
+    // XV标记：两数之和为10或5，以及反XV的不能为10或5
+    XvSum,
+    NotXvSum,
+    // 四种线条约束
+    Thermometer,
+    Arrow,
+    Whisper,
+    Renban,
+    Sandwich,
+    BruteForce,
+    // 一组被排除的草稿
+    Elimination,
+    // 多组被排除的草稿之间的分隔符
+    Separator,
+    // 二/三/四阶鱼的名称
+    Fish2,
+    Fish3,
+    Fish4,
+    // Chess格式下的行列宫名称
+    Row,
+    Column,
+    Box,
+    Diagonal,
+    Window,
+    Cage,
+}
+
+/// 一种语言的文字表
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Catalog {
+    pub coords_format: CoordsFormat,
+    pub techniques: HashMap<Technique, String>,
+    pub messages: HashMap<Message, String>,
+}
+
+impl Catalog {
+    pub fn chinese() -> Catalog {
+        Catalog {
+            coords_format: CoordsFormat::Debug,
+            techniques: HashMap::from([
+                (Technique::NakedSingle, "唯余法".to_string()),
+                (Technique::HiddenSingle, "排除法".to_string()),
+                (Technique::Pointing, "宫区块".to_string()),
+                (Technique::Claiming, "行列区块".to_string()),
+                (Technique::NakedSubset, "显性数对".to_string()),
+                (Technique::HiddenSubset, "隐性数对".to_string()),
+                (Technique::Fish, "鱼".to_string()),
+                (Technique::CageCombination, "笼子组合".to_string()),
+                (Technique::Rule45, "四十五法则".to_string()),
+                (Technique::Kropki, "黑白点".to_string()),
+                (Technique::Line, "线条约束".to_string()),
+                (Technique::Xv, "XV".to_string()),
+                (Technique::Sandwich, "三明治".to_string()),
+                (Technique::BruteForce, "暴力破解".to_string()),
+            ]),
+            messages: HashMap::from([
+                (
+                    Message::NakedSingle,
+                    "{cell} 的可能 {value} 在格内唯一，因此 {cell} 只能填写 {value}".to_string(),
+                ),
+                (
+                    Message::HiddenSingle,
+                    "{cell} 的可能 {value} 在 {units} 内唯一，因此 {cell} 只能填写 {value}"
+                        .to_string(),
+                ),
+                (
+                    Message::AlsoRemove,
+                    "，并移除 {cells} 的可能 {value}".to_string(),
+                ),
+                (
+                    Message::LockedCandidates,
+                    "{cells} 的所有可能 {values} 在 {base_units} 内都只在 {cover_units} 中，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NakedSubset,
+                    "{cells} 的草稿 {values} 在 {base_units} 内形成了数对，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::HiddenSubset,
+                    "{cells} 在 {base_units} 内形成了隐性数对 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Fish,
+                    "{base_units} 中的所有可能 {values} 都在 {cover_units} 内，形成了{fish}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::CageCombination,
+                    "{cells} 所在的 {base_units} 只能由 {values} 组成，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Rule45,
+                    "根据四十五法则，{base_units} 与 {cover_units} 只相差 {cell}，因此 {cell} 只能填写 {value}"
+                        .to_string(),
+                ),
+                (
+                    Message::Rule45Pair,
+                    "根据四十五法则，{base_units} 与 {cover_units} 只相差 {cells}，两格之和固定，凑不出这个和的数字不能填写，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Consecutive,
+                    "{cell} 与 {cells} 之间有白点，两数相差1，而 {cells} 只能填写 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Double,
+                    "{cell} 与 {cells} 之间有黑点，一个数是另一个数的两倍，而 {cells} 只能填写 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NotConsecutive,
+                    "{cell} 与 {cells} 相邻，两数不能相差1，而 {cells} 只能填写 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NotDouble,
+                    "{cell} 与 {cells} 之间没有黑点，两数不能是两倍关系，而 {cells} 只能填写 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::XvSum,
+                    "{cell} 与 {cells} 之间有{mark}，两数之和为{sum}，而 {cells} 只能填写 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NotXvSum,
+                    "{cell} 与 {cells} 之间没有{mark}，两数之和不能为{sum}，而 {cells} 只能填写 {values}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Thermometer,
+                    "{cell} 与 {cells} 在同一支温度计上，数字从圆头开始递增，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Arrow,
+                    "{cell} 与 {cells} 在同一个箭头上，圆圈中的数字等于箭身数字之和，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Whisper,
+                    "{cell} 与 {cells} 在同一条德国悄悄话线上，线上相邻的数字至少相差{gap}，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Renban,
+                    "{cell} 与 {cells} 在同一条连号线上，线上的数字互不相同且连续，因此 {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Sandwich,
+                    "{base_units} 中1与最大数字之间的数字之和必须等于提示，因此 {eliminations}".to_string(),
+                ),
+                (Message::BruteForce, "暴力破解法".to_string()),
+                (Message::Elimination, "{cells} 不能填写 {values}".to_string()),
+                (Message::Separator, "，".to_string()),
+                (Message::Fish2, "二阶鱼".to_string()),
+                (Message::Fish3, "三阶鱼".to_string()),
+                (Message::Fish4, "四阶鱼".to_string()),
+                (Message::Row, "{n}行".to_string()),
+                (Message::Column, "{n}列".to_string()),
+                (Message::Box, "{n}宫".to_string()),
+                (Message::Diagonal, "对角线{n}".to_string()),
+                (Message::Window, "窗口{n}".to_string()),
+                (Message::Cage, "笼子{n}".to_string()),
+            ]),
+        }
+    }
+
+    pub fn english() -> Catalog {
+        Catalog {
+            coords_format: CoordsFormat::RowColumn,
+            techniques: HashMap::from([
+                (Technique::NakedSingle, "Naked Single".to_string()),
+                (Technique::HiddenSingle, "Hidden Single".to_string()),
+                (Technique::Pointing, "Pointing".to_string()),
+                (Technique::Claiming, "Claiming".to_string()),
+                (Technique::NakedSubset, "Naked Subset".to_string()),
+                (Technique::HiddenSubset, "Hidden Subset".to_string()),
+                (Technique::Fish, "Fish".to_string()),
+                (Technique::CageCombination, "Cage Combination".to_string()),
+                (Technique::Rule45, "Rule of 45".to_string()),
+                (Technique::Kropki, "Kropki".to_string()),
+                (Technique::Line, "Line Constraint".to_string()),
+                (Technique::Xv, "XV".to_string()),
+                (Technique::Sandwich, "Sandwich".to_string()),
+                (Technique::BruteForce, "Brute Force".to_string()),
+            ]),
+            messages: HashMap::from([
+                (
+                    Message::NakedSingle,
+                    "{value} is the only candidate left in {cell}, so {cell} must be {value}"
+                        .to_string(),
+                ),
+                (
+                    Message::HiddenSingle,
+                    "{value} can only go in {cell} within {units}, so {cell} must be {value}"
+                        .to_string(),
+                ),
+                (
+                    Message::AlsoRemove,
+                    ", and {value} is removed from {cells}".to_string(),
+                ),
+                (
+                    Message::LockedCandidates,
+                    "In {base_units}, {values} only appears in {cells}, all within {cover_units}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NakedSubset,
+                    "{cells} form a naked subset {values} in {base_units}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::HiddenSubset,
+                    "{cells} form a hidden subset {values} in {base_units}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Fish,
+                    "In {base_units}, {values} only appears within {cover_units}, forming a {fish}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::CageCombination,
+                    "{base_units} containing {cells} can only be made of {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Rule45,
+                    "By the rule of 45, {base_units} and {cover_units} differ only by {cell}, so {cell} must be {value}"
+                        .to_string(),
+                ),
+                (
+                    Message::Rule45Pair,
+                    "By the rule of 45, {base_units} and {cover_units} differ only by {cells}, so their sum is fixed and digits that cannot reach it are ruled out; {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Consecutive,
+                    "A white dot joins {cell} and {cells}, so they differ by 1; {cells} can only be {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Double,
+                    "A black dot joins {cell} and {cells}, so one is double the other; {cells} can only be {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NotConsecutive,
+                    "{cell} and {cells} are adjacent and cannot differ by 1; {cells} can only be {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NotDouble,
+                    "No black dot joins {cell} and {cells}, so neither is double the other; {cells} can only be {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::XvSum,
+                    "{cell} and {cells} are joined by {mark}, so they sum to {sum}; {cells} can only be {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::NotXvSum,
+                    "No {mark} joins {cell} and {cells}, so they cannot sum to {sum}; {cells} can only be {values}, so {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Thermometer,
+                    "{cell} and {cells} share a thermometer, so the digits increase from the bulb; {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Arrow,
+                    "{cell} and {cells} share an arrow, so the circle equals the sum along the arrow; {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Whisper,
+                    "{cell} and {cells} share a German whispers line, so neighbouring digits differ by at least {gap}; {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Renban,
+                    "{cell} and {cells} share a renban line, so its digits are distinct and consecutive; {eliminations}"
+                        .to_string(),
+                ),
+                (
+                    Message::Sandwich,
+                    "The digits between 1 and the largest digit in {base_units} must sum to the clue, so {eliminations}"
+                        .to_string(),
+                ),
+                (Message::BruteForce, "Brute force".to_string()),
+                (Message::Elimination, "{cells} cannot be {values}".to_string()),
+                (Message::Separator, "; ".to_string()),
+                (Message::Fish2, "X-Wing".to_string()),
+                (Message::Fish3, "Swordfish".to_string()),
+                (Message::Fish4, "Jellyfish".to_string()),
+                (Message::Row, "row {n}".to_string()),
+                (Message::Column, "column {n}".to_string()),
+                (Message::Box, "box {n}".to_string()),
+                (Message::Diagonal, "diagonal {n}".to_string()),
+                (Message::Window, "window {n}".to_string()),
+                (Message::Cage, "cage {n}".to_string()),
+            ]),
+        }
+    }
+
+    /// 按语言代码获取内置的文字表，如"zh"、"en-US"
+    pub fn builtin(language: &str) -> Option<Catalog> {
+        match language.split(['-', '_']).next()? {
+            "zh" => Some(Catalog::chinese()),
+            "en" => Some(Catalog::english()),
+            _ => None,
+        }
+    }
+
+    /// 替换坐标格式，如英文界面中使用A1格式
+    pub fn with_coords_format(mut self, coords_format: CoordsFormat) -> Catalog {
+        self.coords_format = coords_format;
+        self
+    }
+
+    /// 策略名称，缺少翻译时使用英文名
+    pub fn technique_name(&self, technique: Technique) -> String {
+        self.techniques
+            .get(&technique)
+            .cloned()
+            .unwrap_or_else(|| format!("{:?}", technique))
+    }
+
+    /// 用参数填充模板，缺少模板时使用英文模板
+    pub fn message(&self, message: Message, args: &[(&str, &str)]) -> String {
+        let template = match self.messages.get(&message) {
+            Some(template) => template.clone(),
+            None => {
+                // 英文模板是完整的，只构造一次
+                static ENGLISH: OnceLock<Catalog> = OnceLock::new();
+                ENGLISH.get_or_init(Catalog::english).messages[&message].clone()
+            }
+        };
+        args.iter().fold(template, |s, (key, value)| {
+            s.replace(&format!("{{{}}}", key), value)
+        })
+    }
+
+    pub fn format_coords(&self, rc: RCCoords) -> String {
+        self.coords_format.format_coords(rc)
+    }
+
+    pub fn format_unit(&self, unit: Unit) -> String {
+        match (self.coords_format, unit) {
+            (CoordsFormat::RowColumn, Unit::Row(r)) => format!("r{}", r + 1),
+            (CoordsFormat::RowColumn, Unit::Column(c)) => format!("c{}", c + 1),
+            (CoordsFormat::RowColumn, Unit::Grid(g)) => format!("b{}", g + 1),
+            (CoordsFormat::RowColumn, Unit::Diagonal(d)) => format!("d{}", d + 1),
+            (CoordsFormat::RowColumn, Unit::Window(w)) => format!("w{}", w + 1),
+            (CoordsFormat::RowColumn, Unit::Cage(k)) => format!("k{}", k + 1),
+            (CoordsFormat::Chess, Unit::Row(r)) => {
+                let n = ((b'A' + r as u8) as char).to_string();
+                self.message(Message::Row, &[("n", &n)])
+            }
+            (CoordsFormat::Chess, Unit::Column(c)) => {
+                self.message(Message::Column, &[("n", &(c + 1).to_string())])
+            }
+            (CoordsFormat::Chess, Unit::Grid(g)) => {
+                self.message(Message::Box, &[("n", &(g + 1).to_string())])
+            }
+            (CoordsFormat::Chess, Unit::Diagonal(d)) => {
+                self.message(Message::Diagonal, &[("n", &(d + 1).to_string())])
+            }
+            (CoordsFormat::Chess, Unit::Window(w)) => {
+                self.message(Message::Window, &[("n", &(w + 1).to_string())])
+            }
+            (CoordsFormat::Chess, Unit::Cage(k)) => {
+                self.message(Message::Cage, &[("n", &(k + 1).to_string())])
+            }
+            (CoordsFormat::Debug, unit) => format!("{:?}", unit),
+        }
+    }
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Catalog::chinese()
+    }
+}
@@ -0,0 +1,495 @@
+// 多重数独：几个9x9盘面共享部分宫拼在一起，如武士数独（五个盘面）、双子数独和蝴蝶数独
+// 每个盘面是一个独立的Sudoku，所有盘面放在同一张画布上，共享的格子在所属的每个盘面中同时填写，
+// 草稿取各盘面的交集；求解和生成都在画布上进行，逐格检查所有所属盘面的规则
+
+use std::str::FromStr;
+
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    rules::Rules,
+    types::{value_to_char, Candidate, CellStatus, Difficulty, RCCoords, Sudoku},
+};
+
+// 每个盘面的边长
+const GRID: usize = 9;
+
+/// 多重数独的拼接方式
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum LayoutKind {
+    // 武士数独：中间一个盘面，四个角的宫分别与四个角上的盘面共享
+    Samurai,
+    // 双子数独：两个盘面沿对角线错开，共享一个宫
+    Twin,
+    // 蝴蝶数独：四个盘面在12x12的画布上两两错开三格，中间的宫由四个盘面共享
+    Butterfly,
+}
+
+impl FromStr for LayoutKind {
+    type Err = String;
+
+    /// 不区分大小写，如"samurai"、"Twin"
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "samurai" => Ok(LayoutKind::Samurai),
+            "twin" => Ok(LayoutKind::Twin),
+            "butterfly" => Ok(LayoutKind::Butterfly),
+            _ => Err(format!("Unknown Layout: {}.", s)),
+        }
+    }
+}
+
+/// 盘面在画布上的位置，以及画布每个格子属于哪些盘面
+#[derive(Clone, Debug, PartialEq)]
+pub struct Layout {
+    kind: LayoutKind,
+    rows: usize,
+    cols: usize,
+    // 每个盘面左上角在画布中的坐标
+    origins: Vec<RCCoords>,
+    // 每个盘面的格子在画布中按行优先的序号
+    cells: Vec<Vec<usize>>,
+    // 画布每个格子所属的盘面和在盘面内的序号，不属于任何盘面的格子为空
+    owners: Vec<Vec<(usize, usize)>>,
+}
+
+impl Layout {
+    pub fn new(kind: LayoutKind) -> Layout {
+        let origins: &[(usize, usize)] = match kind {
+            LayoutKind::Samurai => &[(0, 0), (0, 12), (6, 6), (12, 0), (12, 12)],
+            LayoutKind::Twin => &[(0, 0), (6, 6)],
+            LayoutKind::Butterfly => &[(0, 0), (0, 3), (3, 0), (3, 3)],
+        };
+        let origins: Vec<RCCoords> = origins.iter().map(|&(r, c)| RCCoords { r, c }).collect();
+        let rows = origins.iter().map(|o| o.r + GRID).max().unwrap_or(0);
+        let cols = origins.iter().map(|o| o.c + GRID).max().unwrap_or(0);
+        let mut owners = vec![vec![]; rows * cols];
+        let cells = origins
+            .iter()
+            .enumerate()
+            .map(|(g, origin)| {
+                (0..GRID * GRID)
+                    .map(|idx| {
+                        let canvas = (origin.r + idx / GRID) * cols + origin.c + idx % GRID;
+                        owners[canvas].push((g, idx));
+                        canvas
+                    })
+                    .collect()
+            })
+            .collect();
+        Layout {
+            kind,
+            rows,
+            cols,
+            origins,
+            cells,
+            owners,
+        }
+    }
+
+    pub fn kind(&self) -> LayoutKind {
+        self.kind
+    }
+
+    /// 画布的行数和列数
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn grid_count(&self) -> usize {
+        self.origins.len()
+    }
+
+    /// 每个盘面左上角在画布中的坐标
+    pub fn origins(&self) -> &[RCCoords] {
+        &self.origins
+    }
+
+    /// 画布格子所属的盘面和在盘面内的坐标，不在画布内或不属于任何盘面时为空
+    pub fn owners(&self, rc: RCCoords) -> Vec<(usize, RCCoords)> {
+        if rc.r >= self.rows || rc.c >= self.cols {
+            return vec![];
+        }
+        self.owners[rc.r * self.cols + rc.c]
+            .iter()
+            .map(|&(g, idx)| (g, RCCoords::from_idx(idx)))
+            .collect()
+    }
+
+    /// 盘面内的坐标换算为画布坐标
+    pub fn canvas_rc(&self, grid: usize, rc: RCCoords) -> RCCoords {
+        let origin = self.origins[grid];
+        RCCoords {
+            r: origin.r + rc.r,
+            c: origin.c + rc.c,
+        }
+    }
+
+    /// 属于至少一个盘面的格子数
+    pub fn cell_count(&self) -> usize {
+        self.owners.iter().filter(|o| !o.is_empty()).count()
+    }
+
+    /// 被多个盘面共享的格子，按行优先的顺序
+    pub fn shared_cells(&self) -> Vec<RCCoords> {
+        (0..self.owners.len())
+            .filter(|&i| self.owners[i].len() > 1)
+            .map(|i| self.rc(i))
+            .collect()
+    }
+
+    fn rc(&self, canvas: usize) -> RCCoords {
+        RCCoords {
+            r: canvas / self.cols,
+            c: canvas % self.cols,
+        }
+    }
+}
+
+/// 多重数独，grids与layout中的盘面一一对应，共享格子在各盘面中的数值始终相同
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "MultiSudokuState", into = "MultiSudokuState")]
+pub struct MultiSudoku {
+    layout: Layout,
+    grids: Vec<Sudoku>,
+}
+
+// 序列化格式：拼接方式和每个盘面的Sudoku，反序列化时检查共享格子是否一致
+#[derive(Serialize, Deserialize)]
+struct MultiSudokuState {
+    layout: LayoutKind,
+    grids: Vec<Sudoku>,
+}
+
+impl TryFrom<MultiSudokuState> for MultiSudoku {
+    type Error = String;
+
+    fn try_from(state: MultiSudokuState) -> Result<Self, Self::Error> {
+        MultiSudoku::from_grids(state.layout, state.grids)
+    }
+}
+
+impl From<MultiSudoku> for MultiSudokuState {
+    fn from(field: MultiSudoku) -> Self {
+        MultiSudokuState {
+            layout: field.layout.kind,
+            grids: field.grids,
+        }
+    }
+}
+
+impl MultiSudoku {
+    /// 空白的多重数独，每个盘面都是标准数独
+    pub fn blank(kind: LayoutKind) -> MultiSudoku {
+        let layout = Layout::new(kind);
+        let grids = (0..layout.grid_count())
+            .map(|_| Sudoku::blank(Rules::classic()))
+            .collect();
+        MultiSudoku { layout, grids }
+    }
+
+    /// 由每个盘面的Sudoku组成，盘面数必须与拼接方式一致，共享格子的给定数和填写值必须相同
+    pub fn from_grids(kind: LayoutKind, grids: Vec<Sudoku>) -> Result<MultiSudoku, String> {
+        let layout = Layout::new(kind);
+        if grids.len() != layout.grid_count() {
+            return Err(format!("Invalid Grid Count: {}.", grids.len()));
+        }
+        if let Some(grid) = grids.iter().find(|grid| grid.size() != GRID) {
+            return Err(format!("Invalid Grid Size: {}.", grid.size()));
+        }
+        for rc in layout.shared_cells() {
+            let owners = layout.owners(rc);
+            let (first, rest) = owners.split_first().unwrap();
+            let cell = grids[first.0].get_cell_ref_by_rc(first.1);
+            if rest.iter().any(|&(g, local)| {
+                let other = grids[g].get_cell_ref_by_rc(local);
+                other.value != cell.value || other.status != cell.status
+            }) {
+                return Err(format!("Shared Cell Mismatch: R{}C{}.", rc.r + 1, rc.c + 1));
+            }
+        }
+        let mut field = MultiSudoku { layout, grids };
+        field.sync_candidates();
+        Ok(field)
+    }
+
+    /// 从每个盘面一行的字符初始化，格式同Sudoku::from_line
+    pub fn from_lines(kind: LayoutKind, lines: &[&str]) -> Result<MultiSudoku, String> {
+        let grids = lines
+            .iter()
+            .map(|line| Sudoku::from_line(line))
+            .collect::<Result<Vec<Sudoku>, String>>()?;
+        MultiSudoku::from_grids(kind, grids)
+    }
+
+    /// 每个盘面一行字符，格式同Sudoku::to_value_string
+    pub fn to_lines(&self) -> Vec<String> {
+        self.grids
+            .iter()
+            .map(|grid| grid.to_value_string())
+            .collect()
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn grids(&self) -> &[Sudoku] {
+        &self.grids
+    }
+
+    /// 画布格子的数值，未填写或不属于任何盘面时为None
+    pub fn value(&self, rc: RCCoords) -> Option<u8> {
+        let (g, local) = *self.layout.owners(rc).first()?;
+        self.grids[g].get_cell_ref_by_rc(local).value
+    }
+
+    /// 在画布格子中填写数值，所属的每个盘面同时填写并移除相关格的草稿
+    pub fn place(&mut self, rc: RCCoords, value: u8) -> Result<(), String> {
+        let owners = self.layout.owners(rc);
+        if owners.is_empty() {
+            return Err(format!("Invalid Coords: {:?}", rc));
+        }
+        for (g, local) in owners {
+            self.grids[g].place(local, value)?;
+        }
+        self.sync_candidates();
+        Ok(())
+    }
+
+    /// 共享格子的草稿取所属各盘面草稿的交集，一个盘面中排除的草稿会传递到其他盘面
+    pub fn sync_candidates(&mut self) {
+        for rc in self.layout.shared_cells() {
+            let owners = self.layout.owners(rc);
+            let cell = |grids: &[Sudoku], (g, local): (usize, RCCoords)| {
+                *grids[g].get_cell_ref_by_rc(local)
+            };
+            if cell(&self.grids, owners[0]).status != CellStatus::DRAFT {
+                continue;
+            }
+            let candidates = owners
+                .iter()
+                .map(|&owner| cell(&self.grids, owner).candidates)
+                .reduce(|a, b| a.intersect(&b))
+                .unwrap_or_default();
+            for (g, local) in owners {
+                self.grids[g].get_cell_mut_by_rc(local).candidates = candidates;
+            }
+        }
+    }
+
+    /// 所有盘面中互相矛盾的格子，坐标为画布坐标，共享格子的矛盾只报告一次
+    pub fn find_conflict(&self) -> Option<Vec<(RCCoords, RCCoords)>> {
+        let mut ret: Vec<(RCCoords, RCCoords)> = vec![];
+        for (g, grid) in self.grids.iter().enumerate() {
+            for (a, b) in grid.find_conflict().unwrap_or_default() {
+                let a = self.layout.canvas_rc(g, a.coords.into());
+                let b = self.layout.canvas_rc(g, b.coords.into());
+                if !ret.contains(&(a, b)) {
+                    ret.push((a, b));
+                }
+            }
+        }
+        if !ret.is_empty() {
+            Some(ret)
+        } else {
+            None
+        }
+    }
+
+    pub fn check_if_finish(&self) -> bool {
+        self.grids.iter().all(|grid| grid.check_if_finish())
+    }
+
+    /// 回溯求解，最多返回两个解，只有一个解时题目有唯一解
+    pub fn sovle(&self) -> Vec<MultiSudoku> {
+        let mut field = self.clone();
+        let mut solutions = vec![];
+        let mut budget = usize::MAX;
+        field.search(&mut solutions, 2, &mut |_| {}, &mut budget);
+        solutions
+    }
+
+    /// 生成随机的多重数独：先随机回溯填满画布，再逐格挖空，每次挖空都保证整体仍然只有唯一解
+    /// 挖空数量按画布的格子数换算，武士数独的格子较多，挖空时需要反复求解，耗时较长
+    pub fn generate(kind: LayoutKind, difficulty: Difficulty) -> MultiSudoku {
+        let mut rng = rand::thread_rng();
+        let blank = MultiSudoku::blank(kind);
+        let budget = blank.layout.cell_count() * 4;
+        let mut solutions = vec![];
+        let mut shuffle = |values: &mut Vec<u8>| values.shuffle(&mut rng);
+        while solutions.is_empty() {
+            blank
+                .clone()
+                .search(&mut solutions, 1, &mut shuffle, &mut budget.clone());
+        }
+        let mut field = solutions.remove(0);
+        for grid in field.grids.iter_mut() {
+            for rc in grid.iter_rc().collect::<Vec<RCCoords>>() {
+                let cell = grid.get_cell_mut_by_rc(rc);
+                cell.status = CellStatus::FIXED;
+                cell.candidates = Candidate::default();
+            }
+        }
+
+        let (min_digs, max_digs) = difficulty.empty_cells(field.layout.cell_count());
+        field.dig_holes(rng.gen_range(min_digs..=max_digs), &mut rng);
+        field
+    }
+
+    // 随机挖空，每挖一个格子都要保证整体仍然只有唯一解，否则还原
+    fn dig_holes(&mut self, max_digs: usize, rng: &mut impl Rng) {
+        let mut cells: Vec<usize> = (0..self.layout.owners.len())
+            .filter(|&i| !self.layout.owners[i].is_empty())
+            .collect();
+        cells.shuffle(rng);
+
+        let mut digs = 0;
+        for canvas in cells {
+            if digs >= max_digs {
+                break;
+            }
+            let original = self.canvas_value(canvas);
+            self.set_canvas(canvas, None, CellStatus::DRAFT);
+            self.fill_drafts();
+            if self.sovle().len() == 1 {
+                digs += 1;
+            } else {
+                self.set_canvas(canvas, original, CellStatus::FIXED);
+            }
+        }
+        self.fill_drafts();
+    }
+
+    // 重新计算所有盘面的草稿，共享格子取交集
+    fn fill_drafts(&mut self) {
+        for grid in self.grids.iter_mut() {
+            grid.fill_drafts();
+        }
+        self.sync_candidates();
+    }
+
+    fn canvas_value(&self, canvas: usize) -> Option<u8> {
+        let (g, idx) = self.layout.owners[canvas][0];
+        self.grids[g]
+            .get_cell_ref_by_rc(RCCoords::from_idx(idx))
+            .value
+    }
+
+    // 同时修改画布格子在所属各盘面中的数值和状态
+    fn set_canvas(&mut self, canvas: usize, value: Option<u8>, status: CellStatus) {
+        for &(g, idx) in &self.layout.owners[canvas] {
+            let cell = self.grids[g].get_cell_mut_by_rc(RCCoords::from_idx(idx));
+            cell.value = value;
+            cell.status = status;
+        }
+    }
+
+    // 在画布上回溯，每格可以填写的数值为所属各盘面可填数值的交集
+    // 优先填写某个盘面的单元内只剩一个位置的数值，否则选择可填数值最少的格子，order决定尝试数值的顺序
+    // 找到limit个解或者搜索次数超过budget时返回true停止搜索
+    fn search(
+        &mut self,
+        solutions: &mut Vec<MultiSudoku>,
+        limit: usize,
+        order: &mut dyn FnMut(&mut Vec<u8>),
+        budget: &mut usize,
+    ) -> bool {
+        if *budget == 0 {
+            return true;
+        }
+        *budget -= 1;
+
+        let mut domains = vec![Candidate::full(GRID); self.layout.owners.len()];
+        for (g, grid) in self.grids.iter().enumerate() {
+            let Some(allowed) = grid.allowed_values(|cell| cell.candidates) else {
+                return false;
+            };
+            for (idx, &canvas) in self.layout.cells[g].iter().enumerate() {
+                domains[canvas] = domains[canvas].intersect(&allowed[idx]);
+            }
+        }
+        let unplaced = |canvas: usize| {
+            !self.layout.owners[canvas].is_empty() && self.canvas_value(canvas).is_none()
+        };
+        let mut best: Option<(usize, Candidate)> = None;
+        for canvas in (0..domains.len()).filter(|&i| unplaced(i)) {
+            let candidates = domains[canvas];
+            if candidates.is_empty() {
+                return false;
+            }
+            if best.is_none_or(|(_, b)| candidates.len() < b.len()) {
+                best = Some((canvas, candidates));
+            }
+        }
+        let Some((mut canvas, mut candidates)) = best else {
+            solutions.push(self.clone());
+            return solutions.len() >= limit;
+        };
+
+        // 单元内某个数值没有位置时说明无解，只剩一个位置时直接填写
+        if candidates.len() > 1 {
+            let full = Candidate::full(GRID);
+            'units: for (g, grid) in self.grids.iter().enumerate() {
+                for unit in grid.rules().unit_cells() {
+                    let mut once = Candidate::default();
+                    let mut twice = Candidate::default();
+                    for &p in unit {
+                        let a = domains[self.layout.cells[g][p]];
+                        twice = twice.union(&once.intersect(&a));
+                        once = once.union(&a);
+                    }
+                    if once != full {
+                        return false;
+                    }
+                    let single = once.subtract(&twice);
+                    if let Some(&p) = unit.iter().find(|&&p| {
+                        let i = self.layout.cells[g][p];
+                        unplaced(i) && !domains[i].intersect(&single).is_empty()
+                    }) {
+                        canvas = self.layout.cells[g][p];
+                        candidates = domains[canvas].intersect(&single);
+                        break 'units;
+                    }
+                }
+            }
+        }
+
+        let mut values = candidates.to_vec();
+        order(&mut values);
+        for v in values {
+            self.set_canvas(canvas, Some(v), CellStatus::SOLVE);
+            if self.search(solutions, limit, order, budget) {
+                return true;
+            }
+        }
+        self.set_canvas(canvas, None, CellStatus::DRAFT);
+        false
+    }
+
+    /// 画布的文字形式，每行一个字符串，空格用.表示，不属于任何盘面的格子为空白
+    pub fn to_canvas_string(&self) -> String {
+        (0..self.layout.rows)
+            .map(|r| {
+                (0..self.layout.cols)
+                    .map(|c| {
+                        let rc = RCCoords { r, c };
+                        if self.layout.owners(rc).is_empty() {
+                            ' '
+                        } else {
+                            self.value(rc).map_or('.', value_to_char)
+                        }
+                    })
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
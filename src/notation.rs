@@ -0,0 +1,501 @@
+// 推理步骤的通用记法，与HoDoKu等求解器的日志格式一致，方便对比解题路径，例如：
+// Naked Single: r3c5=6
+// Locked Candidates Type 1 (Pointing): 4 in b5 => r5c19<>4
+// Naked Pair: 1,5 in r3c46 => r3c2<>1, r3c28<>5
+// X-Wing: 5 r26 c37 => r4c3<>5
+// Cage Combination: 1,2 in r1c12 => r1c1<>3（杀手数独）
+// Rule of 45: r3c5=6（杀手数独）
+// Rule of 45: 4,5,6,7,8,9 in r1c89 => r1c8<>1,2,3（只差两格时）
+// Kropki White Dot: 4 in r1c1 => r1c2<>1（克罗普基圆点和非连续数独）
+// Thermometer: 1,2,3 in r1c12 => r1c3<>1（温度计、箭头等线条约束）
+// XV X: 1,2 in r1c1 => r1c2<>1,2（XV数独）
+// Sandwich: 1,2,3,4,5,6,7,8,9 in r1c12345678 => r1c9<>5（三明治数独）
+// 坐标和数字都从1开始，超过9的坐标和数字与盘面的写法相同，用字母A-P表示，如r2cD=E
+
+use crate::{
+    explanation::{CellValue, Explanation, Highlight, Role},
+    inferences::{relation_technique, Technique},
+    rules::{LineKind, Relation, Rules},
+    types::{char_to_value, value_to_char, RCCoords, Unit},
+};
+
+const SUBSET_NAMES: [&str; 3] = ["Pair", "Triple", "Quadruple"];
+const FISH_NAMES: [&str; 3] = ["X-Wing", "Swordfish", "Jellyfish"];
+const POINTING: &str = "Locked Candidates Type 1 (Pointing)";
+const CLAIMING: &str = "Locked Candidates Type 2 (Claiming)";
+const CAGE_COMBINATION: &str = "Cage Combination";
+const RULE_45: &str = "Rule of 45";
+const SANDWICH: &str = "Sandwich";
+const RELATION_NAMES: [(Relation, &str); 8] = [
+    (Relation::Consecutive, "Kropki White Dot"),
+    (Relation::Double, "Kropki Black Dot"),
+    (Relation::NotConsecutive, "Non-Consecutive"),
+    (Relation::NotDouble, "Kropki Negative Black Dot"),
+    (Relation::Sum(10), "XV X"),
+    (Relation::Sum(5), "XV V"),
+    (Relation::NotSum(10), "XV Negative X"),
+    (Relation::NotSum(5), "XV Negative V"),
+];
+const LINE_NAMES: [(LineKind, &str); 4] = [
+    (LineKind::Thermometer, "Thermometer"),
+    (LineKind::Arrow, "Arrow"),
+    (LineKind::Whisper, "German Whispers"),
+    (LineKind::Renban, "Renban"),
+];
+
+impl Explanation {
+    /// 输出为通用记法
+    pub fn to_notation(&self) -> String {
+        let base = self.with_role(Role::Base);
+        let mut values: Vec<u8> = base.iter().map(|cv| cv.value).collect();
+        values.sort();
+        values.dedup();
+        let value = self
+            .eliminations
+            .first()
+            .map(|cv| value_to_char(cv.value))
+            .unwrap_or('0');
+
+        // 数字和条件格，如"1,5 in r3c46"
+        let values_in_cells = || {
+            let cells: Vec<RCCoords> = base.iter().map(|cv| cv.coords).collect();
+            format!(
+                "{}: {} in {}",
+                self.notation_name(),
+                values
+                    .iter()
+                    .map(|&v| value_to_char(v).to_string())
+                    .collect::<Vec<String>>()
+                    .join(","),
+                format_cells(&cells)
+            )
+        };
+
+        let head = match self.technique {
+            // 只差两格时没有填写值，记法同笼子组合
+            Technique::Rule45 if self.placements.is_empty() => values_in_cells(),
+            Technique::NakedSingle
+            | Technique::HiddenSingle
+            | Technique::Rule45
+            | Technique::BruteForce => {
+                return format!(
+                    "{}: {}",
+                    self.notation_name(),
+                    format_placements(&self.placements)
+                );
+            }
+            Technique::Pointing | Technique::Claiming => format!(
+                "{}: {} in {}",
+                self.notation_name(),
+                value,
+                format_units(&self.base_units)
+            ),
+            Technique::NakedSubset
+            | Technique::HiddenSubset
+            | Technique::CageCombination
+            | Technique::Kropki
+            | Technique::Xv
+            | Technique::Line
+            | Technique::Sandwich => values_in_cells(),
+            Technique::Fish => format!(
+                "{}: {} {} {}",
+                self.notation_name(),
+                value,
+                format_units(&self.base_units),
+                format_units(&self.cover_units)
+            ),
+        };
+        format!("{} => {}", head, format_eliminations(&self.eliminations))
+    }
+
+    /// 从通用记法解析，得到的说明只包含记法中出现的内容：
+    /// 数对的条件格按所有数对数字高亮，鱼和区块没有条件格高亮，唯一数没有顺带移除的草稿
+    pub fn from_notation(input: &str) -> Result<Explanation, String> {
+        Explanation::from_notation_with(input, &Rules::classic())
+    }
+
+    /// 按指定的规则解析通用记法，坐标和数字不能超过盘面边长，推断的单元取自规则
+    pub fn from_notation_with(input: &str, rules: &Rules) -> Result<Explanation, String> {
+        let (name, body) = input
+            .trim()
+            .split_once(": ")
+            .ok_or_else(|| "Missing Technique Name.".to_string())?;
+        let (technique, size) = parse_name(name)?;
+        let relation = RELATION_NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(relation, _)| *relation);
+        let line = LINE_NAMES
+            .iter()
+            .find(|(_, n)| *n == name)
+            .map(|(kind, _)| *kind);
+        let (condition, conclusion) = match body.split_once(" => ") {
+            Some((condition, conclusion)) => (condition, conclusion),
+            None => ("", body),
+        };
+
+        let mut explanation = Explanation {
+            technique,
+            highlights: vec![],
+            base_units: vec![],
+            cover_units: vec![],
+            relation,
+            line,
+            gap: (line == Some(LineKind::Whisper)).then_some(rules.size().div_ceil(2) as u8),
+            placements: vec![],
+            eliminations: vec![],
+        };
+        let mut base: Vec<CellValue> = vec![];
+
+        match technique {
+            Technique::NakedSingle | Technique::HiddenSingle | Technique::BruteForce => {
+                explanation.placements = parse_placements(conclusion, rules.size())?;
+            }
+            // 只差两格时有条件格，记法同笼子组合
+            Technique::Rule45 if condition.is_empty() => {
+                explanation.placements = parse_placements(conclusion, rules.size())?;
+            }
+            Technique::Pointing | Technique::Claiming => {
+                let (_, unit) = condition
+                    .split_once(" in ")
+                    .ok_or_else(|| format!("Invalid Condition: {}", condition))?;
+                explanation.base_units = parse_units(unit, rules.size())?;
+                explanation.eliminations = parse_eliminations(conclusion, rules.size())?;
+                explanation.cover_units = common_units(
+                    rules,
+                    &explanation
+                        .eliminations
+                        .iter()
+                        .map(|cv| cv.coords)
+                        .collect::<Vec<RCCoords>>(),
+                )
+                .into_iter()
+                .filter(|u| !explanation.base_units.contains(u))
+                .take(1)
+                .collect();
+            }
+            Technique::NakedSubset
+            | Technique::HiddenSubset
+            | Technique::CageCombination
+            | Technique::Rule45
+            | Technique::Kropki
+            | Technique::Xv
+            | Technique::Line
+            | Technique::Sandwich => {
+                let (values, cells) = condition
+                    .split_once(" in ")
+                    .ok_or_else(|| format!("Invalid Condition: {}", condition))?;
+                let values = values
+                    .split(',')
+                    .map(|value| parse_value(value, rules.size()))
+                    .collect::<Result<Vec<u8>, String>>()?;
+                let cells = parse_cells(cells, rules.size())?;
+                if size > 0 && (values.len() != size || cells.len() != size) {
+                    return Err(format!("Invalid Subset Size: {}", condition));
+                }
+                base = cells
+                    .iter()
+                    .flat_map(|&coords| {
+                        values.iter().map(move |&value| CellValue { coords, value })
+                    })
+                    .collect();
+                // 记法中没有笼子的序号，笼子组合、四十五法则、相邻格关系和线条约束不记录所在的单元，
+                // 三明治的条件格是提示所在行（列）的其他格
+                if size > 0 || technique == Technique::Sandwich {
+                    explanation.base_units =
+                        common_units(rules, &cells).into_iter().take(1).collect();
+                }
+                explanation.eliminations = parse_eliminations(conclusion, rules.size())?;
+            }
+            Technique::Fish => {
+                let parts: Vec<&str> = condition.split(' ').collect();
+                let [_, base_units, cover_units] = parts[..] else {
+                    return Err(format!("Invalid Condition: {}", condition));
+                };
+                explanation.base_units = parse_units(base_units, rules.size())?;
+                explanation.cover_units = parse_units(cover_units, rules.size())?;
+                if explanation.base_units.len() != size || explanation.cover_units.len() != size {
+                    return Err(format!("Invalid Fish Size: {}", condition));
+                }
+                explanation.eliminations = parse_eliminations(conclusion, rules.size())?;
+            }
+        }
+
+        let roles = [
+            (&base, Role::Base),
+            (&explanation.placements, Role::Placement),
+            (&explanation.eliminations, Role::Elimination),
+        ];
+        for (cvs, role) in roles {
+            for cv in cvs {
+                explanation.highlights.push(Highlight {
+                    coords: cv.coords,
+                    value: cv.value,
+                    role,
+                });
+            }
+        }
+        Ok(explanation)
+    }
+
+    fn notation_name(&self) -> String {
+        match self.technique {
+            Technique::NakedSingle => "Naked Single".to_string(),
+            Technique::HiddenSingle => "Hidden Single".to_string(),
+            Technique::Pointing => POINTING.to_string(),
+            Technique::Claiming => CLAIMING.to_string(),
+            Technique::NakedSubset | Technique::HiddenSubset => {
+                let mut cells: Vec<RCCoords> = vec![];
+                for cv in self.with_role(Role::Base) {
+                    if !cells.contains(&cv.coords) {
+                        cells.push(cv.coords);
+                    }
+                }
+                let prefix = if self.technique == Technique::NakedSubset {
+                    "Naked"
+                } else {
+                    "Hidden"
+                };
+                format!("{} {}", prefix, SUBSET_NAMES[cells.len().clamp(2, 4) - 2])
+            }
+            Technique::Fish => FISH_NAMES[self.base_units.len().clamp(2, 4) - 2].to_string(),
+            Technique::CageCombination => CAGE_COMBINATION.to_string(),
+            Technique::Rule45 => RULE_45.to_string(),
+            Technique::Kropki | Technique::Xv => RELATION_NAMES
+                .iter()
+                .find(|(relation, _)| Some(*relation) == self.relation)
+                .map_or(
+                    if self.technique == Technique::Xv {
+                        "XV"
+                    } else {
+                        "Kropki"
+                    },
+                    |(_, name)| name,
+                )
+                .to_string(),
+            Technique::Sandwich => SANDWICH.to_string(),
+            Technique::Line => LINE_NAMES
+                .iter()
+                .find(|(kind, _)| Some(*kind) == self.line)
+                .map_or("Line", |(_, name)| name)
+                .to_string(),
+            Technique::BruteForce => "Brute Force".to_string(),
+        }
+    }
+}
+
+// 解析策略名，同时返回数对和鱼的阶数
+fn parse_name(name: &str) -> Result<(Technique, usize), String> {
+    if let Some((relation, _)) = RELATION_NAMES.iter().find(|(_, n)| *n == name) {
+        return Ok((relation_technique(*relation), 0));
+    }
+    match name {
+        "Naked Single" | "Full House" => return Ok((Technique::NakedSingle, 1)),
+        "Hidden Single" => return Ok((Technique::HiddenSingle, 1)),
+        "Brute Force" => return Ok((Technique::BruteForce, 0)),
+        CAGE_COMBINATION => return Ok((Technique::CageCombination, 0)),
+        RULE_45 => return Ok((Technique::Rule45, 0)),
+        SANDWICH => return Ok((Technique::Sandwich, 0)),
+        _ if LINE_NAMES.iter().any(|(_, n)| *n == name) => return Ok((Technique::Line, 0)),
+        POINTING => return Ok((Technique::Pointing, 1)),
+        CLAIMING => return Ok((Technique::Claiming, 1)),
+        _ => {}
+    }
+    if let Some(i) = FISH_NAMES.iter().position(|&n| n == name) {
+        return Ok((Technique::Fish, i + 2));
+    }
+    for (prefix, technique) in [
+        ("Naked ", Technique::NakedSubset),
+        ("Hidden ", Technique::HiddenSubset),
+    ] {
+        if let Some(i) = name
+            .strip_prefix(prefix)
+            .and_then(|rest| SUBSET_NAMES.iter().position(|&n| n == rest))
+        {
+            return Ok((technique, i + 2));
+        }
+    }
+    Err(format!("Unknown Technique: {}", name))
+}
+
+// 解析一个数字，超过9的数字用字母表示，不能超过盘面边长
+fn parse_value(input: &str, size: usize) -> Result<u8, String> {
+    let mut chars = input.trim().chars();
+    match (chars.next().and_then(char_to_value), chars.next()) {
+        (Some(v), None) if (v as usize) < size => Ok(v),
+        _ => Err(format!("Invalid Value: {}", input)),
+    }
+}
+
+// 解析一串坐标，每个字符一个，写法同数字，如"37"、"1D"
+fn parse_indexes(input: &str, size: usize) -> Result<Vec<usize>, String> {
+    if input.is_empty() {
+        return Err("Missing Index.".to_string());
+    }
+    input
+        .chars()
+        .map(|ch| match char_to_value(ch) {
+            Some(i) if (i as usize) < size => Ok(i as usize),
+            _ => Err(format!("Invalid Index: {}", ch)),
+        })
+        .collect()
+}
+
+// 解析"r12c37"形式的格子，表示行和列的所有组合
+fn parse_cells(input: &str, size: usize) -> Result<Vec<RCCoords>, String> {
+    let mut cells = vec![];
+    for part in input.split(',') {
+        let (rows, cols) = part
+            .trim()
+            .strip_prefix('r')
+            .and_then(|rest| rest.split_once('c'))
+            .ok_or_else(|| format!("Invalid Cell: {}", part))?;
+        for r in parse_indexes(rows, size)? {
+            for c in parse_indexes(cols, size)? {
+                cells.push(RCCoords { r, c });
+            }
+        }
+    }
+    Ok(cells)
+}
+
+// 解析"r26"、"c37"、"b5"、"d1"、"w2"、"k3"形式的单元
+fn parse_units(input: &str, size: usize) -> Result<Vec<Unit>, String> {
+    let mut chars = input.chars();
+    let kind = chars.next();
+    let indexes = parse_indexes(chars.as_str(), size)?;
+    match kind {
+        Some('r') => Ok(indexes.into_iter().map(Unit::Row).collect()),
+        Some('c') => Ok(indexes.into_iter().map(Unit::Column).collect()),
+        Some('b') => Ok(indexes.into_iter().map(Unit::Grid).collect()),
+        Some('d') => Ok(indexes.into_iter().map(Unit::Diagonal).collect()),
+        Some('w') => Ok(indexes.into_iter().map(Unit::Window).collect()),
+        Some('k') => Ok(indexes.into_iter().map(Unit::Cage).collect()),
+        _ => Err(format!("Invalid Unit: {}", input)),
+    }
+}
+
+// 解析"r3c2<>1, r3c28<>5"
+fn parse_eliminations(input: &str, size: usize) -> Result<Vec<CellValue>, String> {
+    let mut eliminations = vec![];
+    for item in input.split(", ") {
+        let (cells, value) = item
+            .split_once("<>")
+            .ok_or_else(|| format!("Invalid Elimination: {}", item))?;
+        let value = parse_value(value, size)?;
+        for coords in parse_cells(cells, size)? {
+            eliminations.push(CellValue { coords, value });
+        }
+    }
+    Ok(eliminations)
+}
+
+// 解析"r3c5=6, r3c6=1"
+fn parse_placements(input: &str, size: usize) -> Result<Vec<CellValue>, String> {
+    let mut placements = vec![];
+    for item in input.split(", ") {
+        let (cells, value) = item
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid Placement: {}", item))?;
+        let value = parse_value(value, size)?;
+        for coords in parse_cells(cells, size)? {
+            placements.push(CellValue { coords, value });
+        }
+    }
+    Ok(placements)
+}
+
+// 所有格子共同所在的单元，顺序同Rules::units_of，宫的形状和额外的单元由规则决定
+fn common_units(rules: &Rules, cells: &[RCCoords]) -> Vec<Unit> {
+    let Some(&first) = cells.first() else {
+        return vec![];
+    };
+    rules
+        .units_of(rules.coords(first))
+        .into_iter()
+        .filter(|&unit| {
+            let indexes = rules.unit_indexes(unit);
+            cells.iter().all(|&rc| indexes.contains(&rules.index(rc)))
+        })
+        .collect()
+}
+
+fn format_indexes(indexes: &[usize]) -> String {
+    indexes.iter().map(|&i| value_to_char(i as u8)).collect()
+}
+
+// 同类单元合并输出，如"r26"
+fn format_units(units: &[Unit]) -> String {
+    let (prefix, indexes): (&str, Vec<usize>) = match units.first() {
+        Some(Unit::Row(_)) => ("r", units.iter().map(|u| u.index()).collect()),
+        Some(Unit::Column(_)) => ("c", units.iter().map(|u| u.index()).collect()),
+        Some(Unit::Grid(_)) => ("b", units.iter().map(|u| u.index()).collect()),
+        Some(Unit::Diagonal(_)) => ("d", units.iter().map(|u| u.index()).collect()),
+        Some(Unit::Window(_)) => ("w", units.iter().map(|u| u.index()).collect()),
+        Some(Unit::Cage(_)) => ("k", units.iter().map(|u| u.index()).collect()),
+        None => return String::new(),
+    };
+    format!("{}{}", prefix, format_indexes(&indexes))
+}
+
+// 格子合并输出：先按行分组，列相同的行再合并，如"r12c37,r4c5"
+fn format_cells(cells: &[RCCoords]) -> String {
+    let mut rows: Vec<(usize, Vec<usize>)> = vec![];
+    for rc in cells {
+        match rows.iter_mut().find(|(r, _)| *r == rc.r) {
+            Some((_, cols)) if !cols.contains(&rc.c) => cols.push(rc.c),
+            Some(_) => {}
+            None => rows.push((rc.r, vec![rc.c])),
+        }
+    }
+    let mut groups: Vec<(Vec<usize>, Vec<usize>)> = vec![];
+    for (r, mut cols) in rows {
+        cols.sort();
+        match groups.iter_mut().find(|(_, cs)| *cs == cols) {
+            Some((rs, _)) => rs.push(r),
+            None => groups.push((vec![r], cols)),
+        }
+    }
+    groups
+        .iter_mut()
+        .map(|(rs, cs)| {
+            rs.sort();
+            format!("r{}c{}", format_indexes(rs), format_indexes(cs))
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// 按数字分组输出，如"r3c2<>1, r3c28<>5"
+fn format_eliminations(eliminations: &[CellValue]) -> String {
+    let mut values: Vec<u8> = eliminations.iter().map(|cv| cv.value).collect();
+    values.sort();
+    values.dedup();
+    values
+        .iter()
+        .map(|&v| {
+            let cells: Vec<RCCoords> = eliminations
+                .iter()
+                .filter(|cv| cv.value == v)
+                .map(|cv| cv.coords)
+                .collect();
+            format!("{}<>{}", format_cells(&cells), value_to_char(v))
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn format_placements(placements: &[CellValue]) -> String {
+    placements
+        .iter()
+        .map(|cv| {
+            format!(
+                "r{}c{}={}",
+                value_to_char(cv.coords.r as u8),
+                value_to_char(cv.coords.c as u8),
+                value_to_char(cv.value)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
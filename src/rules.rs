@@ -0,0 +1,1561 @@
+// 盘面规则：边长和宫的形状，决定每个格子属于哪些单元、与哪些格子互相约束
+// 标准数独为9x9、3x3的宫，另外支持4x4、6x6、12x12、16x16、25x25等由矩形宫组成的盘面，
+// 以及由不规则区域代替宫的锯齿数独（Jigsaw）
+// 变体规则在行列宫之外增加额外的单元（如对角线、窗口），求解、草稿和推理都通过单元列表和相关格生效
+// 杀手数独的笼子内数字不重复，作为相关格生效，笼子和的限制由prune在求解和生成时缩小候选数
+// 无马、无王数独没有新的单元，只把相隔马步、王步的格子加入相关格
+// 非连续数独和克罗普基圆点是相邻两格之间的数字关系，同样由prune缩小候选数
+// 温度计、箭头、德国悄悄话和连号线等线条约束也由prune缩小候选数，连号线上的格子互为相关格
+// XV数独的标记同样是相邻两格之间的关系，三明治数独的提示由prune按笼子的组合表缩小候选数
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{value_to_char, Candidate, Coords, GNCoords, RCCoords, Unit};
+
+/// 支持的最大边长，候选数的位掩码为u32
+pub const MAX_SIZE: usize = 25;
+
+/// 规则的可序列化部分，其余的表格都由它计算得到
+/// 矩形宫记录宫的行列数，不规则区域记录区域图（格式见Rules::jigsaw）
+#[derive(Clone, Serialize, Deserialize)]
+struct RulesSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    box_rows: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    box_cols: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    regions: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    diagonals: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    windows: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    anti_knight: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    anti_king: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    cages: Vec<Cage>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    non_consecutive: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dots: Vec<Dot>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    negative_dots: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    lines: Vec<Line>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sandwich_rows: Vec<Option<u32>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    sandwich_cols: Vec<Option<u32>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    xv_marks: Vec<XvMark>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    negative_xv: bool,
+}
+
+/// 杀手数独的笼子：格子下标按行优先排列，笼子内的数字不重复且之和为sum（数字从1开始计）
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cage {
+    pub cells: Vec<usize>,
+    pub sum: u32,
+}
+
+/// 组合表：从边长为size的盘面的数字中取count个不同的数字、和为sum的所有组合
+pub fn sum_combinations(count: usize, sum: u32, size: usize) -> Vec<Candidate> {
+    fn search(
+        start: usize,
+        count: usize,
+        sum: u32,
+        size: usize,
+        current: Candidate,
+        out: &mut Vec<Candidate>,
+    ) {
+        if count == 0 {
+            if sum == 0 {
+                out.push(current);
+            }
+            return;
+        }
+        for v in start..size {
+            let digit = v as u32 + 1;
+            if digit > sum {
+                break;
+            }
+            let mut next = current;
+            next.add(v as u8);
+            search(v + 1, count - 1, sum - digit, size, next, out);
+        }
+    }
+
+    let mut combinations = vec![];
+    search(0, count, sum, size, Candidate::default(), &mut combinations);
+    combinations
+}
+
+/// 相邻两格之间的数字关系，数字从0开始，比较时按1开始计
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Relation {
+    // 白点：两数相差1
+    Consecutive,
+    // 黑点：一个数是另一个数的两倍
+    Double,
+    // 非连续数独及白点的反向约束：两数不能相差1
+    NotConsecutive,
+    // 黑点的反向约束：两数不能是两倍关系
+    NotDouble,
+    // XV数独的标记：两数之和为10（X）或5（V）
+    Sum(u32),
+    // XV标记的反向约束：两数之和不能为该值
+    NotSum(u32),
+}
+
+impl Relation {
+    /// 两个数字是否满足该关系
+    pub fn allows(&self, a: u8, b: u8) -> bool {
+        let (a, b) = (a as u32 + 1, b as u32 + 1);
+        let consecutive = a.abs_diff(b) == 1;
+        let double = a * 2 == b || b * 2 == a;
+        match self {
+            Relation::Consecutive => consecutive,
+            Relation::Double => double,
+            Relation::NotConsecutive => !consecutive,
+            Relation::NotDouble => !double,
+            Relation::Sum(sum) => a + b == *sum,
+            Relation::NotSum(sum) => a + b != *sum,
+        }
+    }
+
+    // 与u满足该关系且不等于u的所有数字，用位运算代替逐个比较，传播约束时需要频繁调用
+    fn partners(&self, u: u8) -> Candidate {
+        let mut near = Candidate::default();
+        let mut double = Candidate::default();
+        for v in [u.checked_sub(1), Some(u + 1)].into_iter().flatten() {
+            near.add(v);
+        }
+        if ((2 * u + 1) as usize) < MAX_SIZE {
+            double.add(2 * u + 1);
+        }
+        if u % 2 == 1 {
+            double.add(u / 2);
+        }
+        let mut complement = Candidate::default();
+        if let Relation::Sum(sum) | Relation::NotSum(sum) = *self {
+            // 从1开始计时两数之和为sum，即从0开始计时另一个数为sum-2-u
+            let v = (sum as usize).checked_sub(u as usize + 2);
+            if let Some(v) = v.filter(|&v| v < MAX_SIZE && v != u as usize) {
+                complement.add(v as u8);
+            }
+        }
+        let mut others = Candidate::full(MAX_SIZE);
+        others.remove(u);
+        match self {
+            Relation::Consecutive => near,
+            Relation::Double => double,
+            Relation::NotConsecutive => others.subtract(&near),
+            Relation::NotDouble => others.subtract(&double),
+            Relation::Sum(_) => complement,
+            Relation::NotSum(_) => others.subtract(&complement),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DotColor {
+    White,
+    Black,
+}
+
+/// 克罗普基圆点，两个格子必须上下或左右相邻，白点两数相差1，黑点两数为两倍关系
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Dot {
+    pub cells: [usize; 2],
+    pub color: DotColor,
+}
+
+/// XV数独的标记，两个格子必须上下或左右相邻，sum为10（X）或5（V）
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct XvMark {
+    pub cells: [usize; 2],
+    pub sum: u32,
+}
+
+/// 线条约束的种类
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LineKind {
+    // 温度计：从圆头（第一个格子）开始严格递增
+    Thermometer,
+    // 箭头：圆圈（第一个格子）中的数字等于箭身上其余格子的数字之和
+    Arrow,
+    // 德国悄悄话：线上相邻的两格至少相差5，其他尺寸为边长的一半（向上取整）
+    Whisper,
+    // 连号线：线上的数字互不相同，且从小到大排列后是连续的
+    Renban,
+}
+
+/// 线条：格子按线的方向排列，线上前后两个格子必须八个方向相邻
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Line {
+    pub kind: LineKind,
+    pub cells: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "RulesSpec", into = "RulesSpec")]
+pub struct Rules {
+    // 每个宫的行数和列数，边长为两者之积
+    box_rows: usize,
+    box_cols: usize,
+    size: usize,
+    // 宫是否为不规则区域，此时box_rows和box_cols只是同尺寸的默认形状，不代表宫
+    jigsaw: bool,
+    // 两条对角线是否也是单元（对角线数独，Sudoku-X）
+    diagonals: bool,
+    // 是否有与宫形状相同、与盘面边缘和宫之间各隔一格的窗口单元（窗口数独，Windoku/Hyper）
+    windows: bool,
+    // 相隔国际象棋马步（无马数独）或王步（无王数独）的格子不能填写相同的数字
+    anti_knight: bool,
+    anti_king: bool,
+    // 杀手数独的笼子及每个笼子所有可能的数字组合
+    cages: Vec<Cage>,
+    cage_combinations: Vec<Vec<Candidate>>,
+    // 非连续数独：上下左右相邻的格子不能相差1
+    non_consecutive: bool,
+    // 克罗普基圆点，negative_dots为true时没有圆点的相邻格子既不能相差1也不能是两倍关系
+    dots: Vec<Dot>,
+    negative_dots: bool,
+    // 由以上规则得到的所有相邻两格之间的关系，同一对格子可以有多个关系
+    relations: Vec<(usize, usize, Relation)>,
+    // 温度计、箭头等线条约束
+    lines: Vec<Line>,
+    // 三明治数独每行、每列的提示：1和9（其他尺寸为最小和最大的数字）之间的数字之和，
+    // None表示该行（列）没有提示，没有任何提示时为空
+    sandwich_rows: Vec<Option<u32>>,
+    sandwich_cols: Vec<Option<u32>>,
+    // 由以上提示得到的所有三明治：所在的行（列）、和，以及两端之间有k个格子时所有可能的数字组合
+    sandwiches: Vec<(Unit, u32, Vec<Vec<Candidate>>)>,
+    // XV数独的标记，negative_xv为true时没有标记的相邻格子之和既不是10也不是5
+    xv_marks: Vec<XvMark>,
+    negative_xv: bool,
+    // 每个格子的坐标（含宫号和宫内序号），按行优先排列
+    coords: Vec<Coords>,
+    // 每个宫内的格子下标，按宫内序号排列
+    grids: Vec<Vec<usize>>,
+    // 所有单元及其格子下标
+    units: Vec<Unit>,
+    unit_cells: Vec<Vec<usize>>,
+    // 每个格子的相关格（同行列宫，不含自身）
+    peers: Vec<Vec<usize>>,
+}
+
+impl Rules {
+    /// 标准9x9数独
+    pub fn classic() -> Rules {
+        Rules::new(3, 3).unwrap()
+    }
+
+    /// 由box_rows行、box_cols列的矩形宫组成的盘面，如2x3的宫组成6x6的盘面
+    pub fn new(box_rows: usize, box_cols: usize) -> Result<Rules, String> {
+        let size = box_rows * box_cols;
+        if box_rows == 0 || box_cols == 0 || size < 4 {
+            return Err(format!("Invalid Box Shape: {}x{}.", box_rows, box_cols));
+        }
+        if size > MAX_SIZE {
+            return Err(format!("Grid Size Too Large: {}.", size));
+        }
+
+        let regions = (0..size * size)
+            .map(|idx| {
+                let (r, c) = (idx / size, idx % size);
+                r / box_rows * box_rows + c / box_cols
+            })
+            .collect();
+        Ok(Rules::build(box_rows, box_cols, regions, false))
+    }
+
+    /// 锯齿数独：用区域图代替矩形宫，区域图每格一个字符，相同字符的格子属于同一区域，
+    /// 如9x9时为81个字符，空白字符会被忽略，因此可以按行分行书写
+    /// 每个区域必须恰好有边长个格子且互相连通，区域按首次出现的顺序编号
+    pub fn jigsaw(regions: &str) -> Result<Rules, String> {
+        let labels: Vec<char> = regions.chars().filter(|ch| !ch.is_whitespace()).collect();
+        let default = Rules::with_cell_count(labels.len())?;
+        let size = default.size;
+
+        let mut seen: Vec<char> = vec![];
+        let ids: Vec<usize> = labels
+            .iter()
+            .map(|&ch| match seen.iter().position(|&l| l == ch) {
+                Some(id) => id,
+                None => {
+                    seen.push(ch);
+                    seen.len() - 1
+                }
+            })
+            .collect();
+        if seen.len() != size {
+            return Err(format!("Invalid Region Count: {}.", seen.len()));
+        }
+
+        for (id, &label) in seen.iter().enumerate() {
+            let cells: Vec<usize> = (0..ids.len()).filter(|&idx| ids[idx] == id).collect();
+            if cells.len() != size {
+                return Err(format!("Invalid Region Size: {}.", label));
+            }
+            // 从第一个格子出发沿上下左右扩展，能到达区域内所有格子才算连通
+            let mut reached = vec![cells[0]];
+            let mut i = 0;
+            while i < reached.len() {
+                let (r, c) = (reached[i] / size, reached[i] % size);
+                let neighbors = [
+                    (r > 0).then(|| reached[i] - size),
+                    (r + 1 < size).then(|| reached[i] + size),
+                    (c > 0).then(|| reached[i] - 1),
+                    (c + 1 < size).then(|| reached[i] + 1),
+                ];
+                for p in neighbors.into_iter().flatten() {
+                    if ids[p] == id && !reached.contains(&p) {
+                        reached.push(p);
+                    }
+                }
+                i += 1;
+            }
+            if reached.len() != size {
+                return Err(format!("Region Not Connected: {}.", label));
+            }
+        }
+
+        Ok(Rules::build(default.box_rows, default.box_cols, ids, true))
+    }
+
+    // 由每个格子所在的宫（区域）计算坐标、单元和相关格，宫内序号按行优先排列
+    fn build(box_rows: usize, box_cols: usize, regions: Vec<usize>, jigsaw: bool) -> Rules {
+        let size = box_rows * box_cols;
+        let mut grids: Vec<Vec<usize>> = vec![vec![]; size];
+        let coords: Vec<Coords> = regions
+            .iter()
+            .enumerate()
+            .map(|(idx, &g)| {
+                grids[g].push(idx);
+                Coords {
+                    r: idx / size,
+                    c: idx % size,
+                    g,
+                    n: grids[g].len() - 1,
+                }
+            })
+            .collect();
+
+        let mut rules = Rules {
+            box_rows,
+            box_cols,
+            size,
+            jigsaw,
+            diagonals: false,
+            windows: false,
+            anti_knight: false,
+            anti_king: false,
+            cages: vec![],
+            cage_combinations: vec![],
+            non_consecutive: false,
+            dots: vec![],
+            negative_dots: false,
+            relations: vec![],
+            lines: vec![],
+            sandwich_rows: vec![],
+            sandwich_cols: vec![],
+            sandwiches: vec![],
+            xv_marks: vec![],
+            negative_xv: false,
+            coords,
+            grids,
+            units: vec![],
+            unit_cells: vec![],
+            peers: vec![],
+        };
+        rules.compute_units();
+        rules
+    }
+
+    // 重新计算单元列表和相关格，变体规则修改后调用
+    fn compute_units(&mut self) {
+        let size = self.size;
+        let mut units: Vec<Unit> = (0..size)
+            .map(Unit::Row)
+            .chain((0..size).map(Unit::Column))
+            .chain((0..size).map(Unit::Grid))
+            .collect();
+        if self.diagonals {
+            units.extend([Unit::Diagonal(0), Unit::Diagonal(1)]);
+        }
+        if self.windows {
+            units.extend((0..self.window_origins().len()).map(Unit::Window));
+        }
+        self.units = units;
+        self.unit_cells = self.units.iter().map(|&u| self.unit_indexes(u)).collect();
+        self.relations = self.compute_relations();
+        self.peers = (0..size * size)
+            .map(|idx| self.compute_peers(idx))
+            .collect();
+    }
+
+    /// 增加两条对角线作为单元（对角线数独，Sudoku-X）
+    pub fn with_diagonals(mut self) -> Rules {
+        self.diagonals = true;
+        self.compute_units();
+        self
+    }
+
+    /// 增加窗口作为单元（窗口数独，Windoku/Hyper），9x9时为左上角在R2C2、R2C6、R6C2、R6C6的四个3x3区域
+    pub fn with_windows(mut self) -> Rules {
+        self.windows = true;
+        self.compute_units();
+        self
+    }
+
+    /// 相隔马步的格子也是相关格（无马数独，Anti-Knight）
+    pub fn with_anti_knight(mut self) -> Rules {
+        self.anti_knight = true;
+        self.compute_units();
+        self
+    }
+
+    /// 相隔王步（八个方向相邻）的格子也是相关格（无王数独，Anti-King）
+    pub fn with_anti_king(mut self) -> Rules {
+        self.anti_king = true;
+        self.compute_units();
+        self
+    }
+
+    /// 上下左右相邻的格子不能相差1（非连续数独，Non-Consecutive）
+    pub fn with_non_consecutive(mut self) -> Rules {
+        self.non_consecutive = true;
+        self.compute_units();
+        self
+    }
+
+    /// 增加克罗普基圆点（Kropki），negative为true时没有圆点的相邻格子既不相差1也不是两倍关系
+    pub fn with_dots(mut self, dots: Vec<Dot>, negative: bool) -> Result<Rules, String> {
+        let pairs: Vec<[usize; 2]> = dots.iter().map(|dot| dot.cells).collect();
+        self.check_pairs(&pairs, "Dot")?;
+        self.dots = dots;
+        self.negative_dots = negative;
+        self.compute_units();
+        Ok(self)
+    }
+
+    /// 从文字解析圆点，逗号分隔，每项为`r1c1-r1c2=w`的形式，w为白点，b为黑点，行列从1开始
+    pub fn with_dot_list(self, input: &str, negative: bool) -> Result<Rules, String> {
+        let dots = self
+            .parse_pairs(input, "Dot", |kind| match kind {
+                "w" => Some(DotColor::White),
+                "b" => Some(DotColor::Black),
+                _ => None,
+            })?
+            .into_iter()
+            .map(|(cells, color)| Dot { cells, color })
+            .collect();
+        self.with_dots(dots, negative)
+    }
+
+    /// 增加XV数独的标记，negative为true时没有标记的相邻格子之和既不是10也不是5
+    pub fn with_xv(mut self, marks: Vec<XvMark>, negative: bool) -> Result<Rules, String> {
+        if let Some(mark) = marks.iter().find(|mark| mark.sum != 10 && mark.sum != 5) {
+            return Err(format!("Invalid XV Sum: {}.", mark.sum));
+        }
+        let pairs: Vec<[usize; 2]> = marks.iter().map(|mark| mark.cells).collect();
+        self.check_pairs(&pairs, "XV Mark")?;
+        self.xv_marks = marks;
+        self.negative_xv = negative;
+        self.compute_units();
+        Ok(self)
+    }
+
+    /// 从文字解析XV标记，格式同with_dot_list，x为和为10，v为和为5
+    pub fn with_xv_list(self, input: &str, negative: bool) -> Result<Rules, String> {
+        let marks = self
+            .parse_pairs(input, "XV Mark", |kind| match kind {
+                "x" => Some(10),
+                "v" => Some(5),
+                _ => None,
+            })?
+            .into_iter()
+            .map(|(cells, sum)| XvMark { cells, sum })
+            .collect();
+        self.with_xv(marks, negative)
+    }
+
+    // 圆点、XV标记等两格之间的标记：格子必须有效且上下或左右相邻，同一对格子只能有一个标记
+    fn check_pairs(&self, pairs: &[[usize; 2]], name: &str) -> Result<(), String> {
+        for (i, &[a, b]) in pairs.iter().enumerate() {
+            if a >= self.cell_count() || b >= self.cell_count() {
+                return Err(format!("Invalid {} Cell: {}.", name, a.max(b)));
+            }
+            let (ra, rb) = (self.rc(a), self.rc(b));
+            if ra.r.abs_diff(rb.r) + ra.c.abs_diff(rb.c) != 1 {
+                return Err(format!("{} Cells Not Adjacent: {:?} {:?}.", name, ra, rb));
+            }
+            if pairs[..i].iter().any(|&p| p == [a, b] || p == [b, a]) {
+                return Err(format!("Duplicate {}: {:?} {:?}.", name, ra, rb));
+            }
+        }
+        Ok(())
+    }
+
+    // 解析逗号分隔的`r1c1-r1c2=<种类>`，kind把种类的文字转换为标记的种类
+    fn parse_pairs<T>(
+        &self,
+        input: &str,
+        name: &str,
+        kind: impl Fn(&str) -> Option<T>,
+    ) -> Result<Vec<([usize; 2], T)>, String> {
+        input
+            .split(',')
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| {
+                let invalid = || format!("Invalid {}: {}.", name, item.trim());
+                let (cells, label) = item.split_once('=').ok_or_else(invalid)?;
+                let (a, b) = cells.split_once('-').ok_or_else(invalid)?;
+                let cells = [
+                    self.parse_cell(a).ok_or_else(invalid)?,
+                    self.parse_cell(b).ok_or_else(invalid)?,
+                ];
+                Ok((cells, kind(label.trim()).ok_or_else(invalid)?))
+            })
+            .collect()
+    }
+
+    /// 增加三明治数独的提示，rows和cols分别为每行、每列的提示，长度为边长，也可以为空表示没有提示
+    pub fn with_sandwiches(
+        mut self,
+        rows: Vec<Option<u32>>,
+        cols: Vec<Option<u32>>,
+    ) -> Result<Rules, String> {
+        let size = self.size;
+        // 1和9之间最多是其余所有数字
+        let max = (2..size as u32).sum::<u32>();
+        let mut sandwiches = vec![];
+        for (clues, unit) in [
+            (&rows, Unit::Row as fn(usize) -> Unit),
+            (&cols, Unit::Column),
+        ] {
+            if !clues.is_empty() && clues.len() != size {
+                return Err(format!("Invalid Sandwich Count: {}.", clues.len()));
+            }
+            for (i, &clue) in clues.iter().enumerate() {
+                let Some(sum) = clue else {
+                    continue;
+                };
+                if sum > max {
+                    return Err(format!("Invalid Sandwich Sum: {}.", sum));
+                }
+                let mut ends = Candidate::default();
+                ends.add(0);
+                ends.add(size as u8 - 1);
+                let combinations = (0..size - 1)
+                    .map(|count| {
+                        sum_combinations(count, sum, size)
+                            .into_iter()
+                            .filter(|c| c.intersect(&ends).is_empty())
+                            .collect()
+                    })
+                    .collect();
+                sandwiches.push((unit(i), sum, combinations));
+            }
+        }
+        self.sandwich_rows = rows;
+        self.sandwich_cols = cols;
+        self.sandwiches = sandwiches;
+        self.compute_units();
+        Ok(self)
+    }
+
+    /// 从文字解析三明治提示，格式为`<行提示>/<列提示>`，每部分逗号分隔，.或空白表示没有提示，
+    /// 如`2,.,35,0,.,.,.,.,10/.,.,.,.,.,.,.,.,5`
+    pub fn with_sandwich_list(self, input: &str) -> Result<Rules, String> {
+        let (rows, cols) = input
+            .split_once('/')
+            .ok_or_else(|| "Missing Sandwich Columns.".to_string())?;
+        let parse = |part: &str| -> Result<Vec<Option<u32>>, String> {
+            if part.trim().is_empty() {
+                return Ok(vec![]);
+            }
+            part.split(',')
+                .map(|clue| match clue.trim() {
+                    "" | "." => Ok(None),
+                    clue => clue
+                        .parse::<u32>()
+                        .map(Some)
+                        .map_err(|_| format!("Invalid Sandwich Sum: {}.", clue)),
+                })
+                .collect()
+        };
+        self.with_sandwiches(parse(rows)?, parse(cols)?)
+    }
+
+    /// 增加线条约束，线上至少两个格子且不能重复，温度计和连号线不能超过边长个格子
+    pub fn with_lines(mut self, lines: Vec<Line>) -> Result<Rules, String> {
+        for line in &lines {
+            if line.cells.len() < 2 {
+                return Err("Line Too Short.".to_string());
+            }
+            if matches!(line.kind, LineKind::Thermometer | LineKind::Renban)
+                && line.cells.len() > self.size
+            {
+                return Err(format!("Line Too Long: {}.", line.cells.len()));
+            }
+            for (i, &idx) in line.cells.iter().enumerate() {
+                if idx >= self.cell_count() {
+                    return Err(format!("Invalid Line Cell: {}.", idx));
+                }
+                if line.cells[..i].contains(&idx) {
+                    return Err(format!("Duplicate Line Cell: {:?}.", self.rc(idx)));
+                }
+            }
+            for pair in line.cells.windows(2) {
+                let (a, b) = (self.rc(pair[0]), self.rc(pair[1]));
+                if a.r.abs_diff(b.r).max(a.c.abs_diff(b.c)) != 1 {
+                    return Err(format!("Line Cells Not Adjacent: {:?} {:?}.", a, b));
+                }
+            }
+        }
+        self.lines = lines;
+        self.compute_units();
+        Ok(self)
+    }
+
+    /// 从文字解析线条，逗号分隔，每项为`r1c1-r1c2-r2c3=t`的形式，格子按线的方向排列，
+    /// t为温度计（从圆头开始），a为箭头（从圆圈开始），w为德国悄悄话，r为连号线，行列从1开始
+    pub fn with_line_list(self, input: &str) -> Result<Rules, String> {
+        let lines = input
+            .split(',')
+            .filter(|item| !item.trim().is_empty())
+            .map(|item| {
+                let invalid = || format!("Invalid Line: {}.", item.trim());
+                let (cells, kind) = item.split_once('=').ok_or_else(invalid)?;
+                let kind = match kind.trim() {
+                    "t" => LineKind::Thermometer,
+                    "a" => LineKind::Arrow,
+                    "w" => LineKind::Whisper,
+                    "r" => LineKind::Renban,
+                    _ => return Err(invalid()),
+                };
+                let cells = cells
+                    .split('-')
+                    .map(|cell| self.parse_cell(cell).ok_or_else(invalid))
+                    .collect::<Result<Vec<usize>, String>>()?;
+                Ok(Line { kind, cells })
+            })
+            .collect::<Result<Vec<Line>, String>>()?;
+        self.with_lines(lines)
+    }
+
+    // 解析`r1c1`形式的格子，行列从1开始，返回行优先的下标
+    fn parse_cell(&self, cell: &str) -> Option<usize> {
+        let (r, c) = cell.trim().strip_prefix('r')?.split_once('c')?;
+        let (r, c) = (r.parse::<usize>().ok()?, c.parse::<usize>().ok()?);
+        let valid = |i: usize| (1..=self.size).contains(&i);
+        (valid(r) && valid(c)).then(|| (r - 1) * self.size + c - 1)
+    }
+
+    /// 增加杀手数独的笼子，笼子之间不能重叠，笼子和必须能由不同的数字组成
+    pub fn with_cages(mut self, cages: Vec<Cage>) -> Result<Rules, String> {
+        let mut owner: Vec<bool> = vec![false; self.cell_count()];
+        let mut combinations = vec![];
+        for cage in &cages {
+            if cage.cells.is_empty() {
+                return Err("Empty Cage.".to_string());
+            }
+            for &idx in &cage.cells {
+                if idx >= owner.len() {
+                    return Err(format!("Invalid Cage Cell: {}.", idx));
+                }
+                if owner[idx] {
+                    return Err(format!("Overlapping Cages At: {:?}.", self.rc(idx)));
+                }
+                owner[idx] = true;
+            }
+            let cage_combinations = sum_combinations(cage.cells.len(), cage.sum, self.size);
+            if cage_combinations.is_empty() {
+                return Err(format!("Invalid Cage Sum: {}.", cage.sum));
+            }
+            combinations.push(cage_combinations);
+        }
+        self.cages = cages;
+        self.cage_combinations = combinations;
+        self.compute_units();
+        Ok(self)
+    }
+
+    /// 从笼子图解析笼子，格式为`<笼子图>=<和,和,...>`，笼子图与区域图相同，每格一个字符，
+    /// .表示不在笼子内，笼子按字符首次出现的顺序对应逗号分隔的和
+    pub fn with_cage_map(self, input: &str) -> Result<Rules, String> {
+        let (map, sums) = input
+            .split_once('=')
+            .ok_or_else(|| "Missing Cage Sums.".to_string())?;
+        let labels: Vec<char> = map.chars().filter(|ch| !ch.is_whitespace()).collect();
+        if labels.len() != self.cell_count() {
+            return Err("Invalid String Length.".to_string());
+        }
+        let sums = sums
+            .split(',')
+            .map(|sum| {
+                sum.trim()
+                    .parse::<u32>()
+                    .map_err(|_| format!("Invalid Cage Sum: {}.", sum))
+            })
+            .collect::<Result<Vec<u32>, String>>()?;
+
+        let mut seen: Vec<char> = vec![];
+        let mut cages: Vec<Cage> = vec![];
+        for (idx, &ch) in labels.iter().enumerate().filter(|(_, &ch)| ch != '.') {
+            match seen.iter().position(|&l| l == ch) {
+                Some(i) => cages[i].cells.push(idx),
+                None => {
+                    let sum = *sums
+                        .get(seen.len())
+                        .ok_or_else(|| format!("Missing Cage Sum: {}.", ch))?;
+                    seen.push(ch);
+                    cages.push(Cage {
+                        cells: vec![idx],
+                        sum,
+                    });
+                }
+            }
+        }
+        if sums.len() != cages.len() {
+            return Err(format!("Invalid Cage Count: {}.", cages.len()));
+        }
+        self.with_cages(cages)
+    }
+
+    /// 按边长选择默认的宫形状：行数取不超过平方根的最大因数，如6为2x3、12为3x4
+    pub fn with_size(size: usize) -> Result<Rules, String> {
+        let box_rows = (1..=size)
+            .take_while(|d| d * d <= size)
+            .filter(|&d| size.is_multiple_of(d))
+            .last()
+            .unwrap_or(1);
+        if box_rows == 1 {
+            return Err(format!("Invalid Grid Size: {}.", size));
+        }
+        Rules::new(box_rows, size / box_rows)
+    }
+
+    /// 按格子总数推断边长，用于解析一行字符的题目
+    pub fn with_cell_count(count: usize) -> Result<Rules, String> {
+        match (2..=MAX_SIZE).find(|size| size * size == count) {
+            Some(size) => Rules::with_size(size),
+            None => Err("Invalid String Length.".to_string()),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn box_rows(&self) -> usize {
+        self.box_rows
+    }
+
+    pub fn box_cols(&self) -> usize {
+        self.box_cols
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.size * self.size
+    }
+
+    /// 宫是否为不规则区域
+    pub fn is_jigsaw(&self) -> bool {
+        self.jigsaw
+    }
+
+    pub fn has_diagonals(&self) -> bool {
+        self.diagonals
+    }
+
+    pub fn has_windows(&self) -> bool {
+        self.windows
+    }
+
+    pub fn is_anti_knight(&self) -> bool {
+        self.anti_knight
+    }
+
+    pub fn is_anti_king(&self) -> bool {
+        self.anti_king
+    }
+
+    pub fn is_non_consecutive(&self) -> bool {
+        self.non_consecutive
+    }
+
+    pub fn dots(&self) -> &[Dot] {
+        &self.dots
+    }
+
+    pub fn has_negative_dots(&self) -> bool {
+        self.negative_dots
+    }
+
+    /// 所有相邻两格之间的关系
+    pub fn relations(&self) -> &[(usize, usize, Relation)] {
+        &self.relations
+    }
+
+    pub fn lines(&self) -> &[Line] {
+        &self.lines
+    }
+
+    pub fn sandwich_rows(&self) -> &[Option<u32>] {
+        &self.sandwich_rows
+    }
+
+    pub fn sandwich_cols(&self) -> &[Option<u32>] {
+        &self.sandwich_cols
+    }
+
+    /// 所有三明治提示所在的行（列）和提示的和，先行后列
+    pub fn sandwiches(&self) -> Vec<(Unit, u32)> {
+        self.sandwiches
+            .iter()
+            .map(|&(unit, sum, _)| (unit, sum))
+            .collect()
+    }
+
+    pub fn xv_marks(&self) -> &[XvMark] {
+        &self.xv_marks
+    }
+
+    pub fn has_negative_xv(&self) -> bool {
+        self.negative_xv
+    }
+
+    /// 按无马、无王规则与格子相关的格子，按行优先排列，不属于任何单元
+    pub fn chess_neighbors(&self, idx: usize) -> Vec<usize> {
+        const KNIGHT: [(isize, isize); 8] = [
+            (-2, -1),
+            (-2, 1),
+            (-1, -2),
+            (-1, 2),
+            (1, -2),
+            (1, 2),
+            (2, -1),
+            (2, 1),
+        ];
+        const KING: [(isize, isize); 8] = [
+            (-1, -1),
+            (-1, 0),
+            (-1, 1),
+            (0, -1),
+            (0, 1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+        ];
+        let size = self.size as isize;
+        let (r, c) = ((idx / self.size) as isize, (idx % self.size) as isize);
+        let mut moves: Vec<(isize, isize)> = vec![];
+        if self.anti_knight {
+            moves.extend(KNIGHT);
+        }
+        if self.anti_king {
+            moves.extend(KING);
+        }
+        let mut neighbors: Vec<usize> = moves
+            .into_iter()
+            .map(|(dr, dc)| (r + dr, c + dc))
+            .filter(|(r, c)| (0..size).contains(r) && (0..size).contains(c))
+            .map(|(r, c)| (r * size + c) as usize)
+            .collect();
+        neighbors.sort();
+        neighbors
+    }
+
+    /// 每个窗口左上角的格子（行，列），窗口从第2行第2列开始，窗口之间隔一行（列），不超出盘面的最后一行（列）
+    pub fn window_origins(&self) -> Vec<(usize, usize)> {
+        let starts = |step: usize| -> Vec<usize> {
+            (1..self.size)
+                .step_by(step + 1)
+                .filter(|&i| i + step < self.size)
+                .collect()
+        };
+        let cols = starts(self.box_cols);
+        starts(self.box_rows)
+            .into_iter()
+            .flat_map(|r| cols.iter().map(move |&c| (r, c)))
+            .collect()
+    }
+
+    /// 是否只有行、列和矩形宫，此时可以通过交换行列由基础终盘生成题目
+    pub fn is_regular(&self) -> bool {
+        !self.jigsaw
+            && self.extra_units().is_empty()
+            && self.cages.is_empty()
+            && !self.anti_knight
+            && !self.anti_king
+            && self.relations.is_empty()
+            && self.lines.is_empty()
+            && self.sandwiches.is_empty()
+    }
+
+    pub fn cages(&self) -> &[Cage] {
+        &self.cages
+    }
+
+    /// 格子所在的笼子序号
+    pub fn cage_of(&self, idx: usize) -> Option<usize> {
+        self.cages.iter().position(|cage| cage.cells.contains(&idx))
+    }
+
+    /// 在当前的候选数下笼子内的格子还能填写的数字：所有仍然可能的组合的并集，
+    /// 组合中的每个数字都要有格子可以填写，每个格子也都要能填写组合中的某个数字
+    /// domains为每个格子的候选数，已填写的格子为只含该数值的集合
+    pub fn cage_candidates(&self, cage: usize, domains: &[Candidate]) -> Candidate {
+        let cells = &self.cages[cage].cells;
+        self.cage_combinations[cage]
+            .iter()
+            .filter(|&combination| {
+                let mut covered = Candidate::default();
+                for &idx in cells {
+                    let fit = domains[idx].intersect(combination);
+                    if fit.is_empty() {
+                        return false;
+                    }
+                    covered = covered.union(&fit);
+                }
+                covered == *combination
+            })
+            .fold(Candidate::default(), |all, c| all.union(c))
+    }
+
+    /// 笼子的虚线框，坐标以格子边长为单位（x为列，y为行），框线向格内缩进inset，
+    /// 每格只画与笼子外相邻的边，相邻格子也属于同一笼子时边延伸到格子边缘，与相邻格子的边相接
+    pub fn cage_outline(&self, cage: usize, inset: f64) -> Vec<((f64, f64), (f64, f64))> {
+        let size = self.size as isize;
+        let inside = |r: isize, c: isize| {
+            (0..size).contains(&r)
+                && (0..size).contains(&c)
+                && self.cages[cage].cells.contains(&((r * size + c) as usize))
+        };
+        let start = |same: bool, p: f64| if same { p } else { p + inset };
+        let end = |same: bool, p: f64| if same { p + 1.0 } else { p + 1.0 - inset };
+
+        let mut edges = vec![];
+        for &idx in &self.cages[cage].cells {
+            let (r, c) = ((idx / self.size) as isize, (idx % self.size) as isize);
+            let (x, y) = (c as f64, r as f64);
+            let (x1, x2) = (start(inside(r, c - 1), x), end(inside(r, c + 1), x));
+            let (y1, y2) = (start(inside(r - 1, c), y), end(inside(r + 1, c), y));
+            if !inside(r - 1, c) {
+                edges.push(((x1, y + inset), (x2, y + inset)));
+            }
+            if !inside(r + 1, c) {
+                edges.push(((x1, y + 1.0 - inset), (x2, y + 1.0 - inset)));
+            }
+            if !inside(r, c - 1) {
+                edges.push(((x + inset, y1), (x + inset, y2)));
+            }
+            if !inside(r, c + 1) {
+                edges.push(((x + 1.0 - inset, y1), (x + 1.0 - inset, y2)));
+            }
+        }
+        edges
+    }
+
+    /// 在from格子的可能值下，to格子满足关系relation的数字，两格相邻所以数字不能相同
+    pub fn relation_candidates(relation: Relation, from: Candidate, to: Candidate) -> Candidate {
+        let mut allowed = Candidate::default();
+        for u in (0..MAX_SIZE as u8).filter(|&u| from.contains(u)) {
+            allowed = allowed.union(&relation.partners(u));
+            if to.subtract(&allowed).is_empty() {
+                break;
+            }
+        }
+        to.intersect(&allowed)
+    }
+
+    /// 在当前的候选数下线上每个格子还能填写的数字，顺序与线上的格子相同，格式同cage_candidates
+    /// 温度计和箭头按最小、最大值推算范围，德国悄悄话按前后相邻的格子筛选，
+    /// 连号线只保留仍然可能的连续数字段，数字互不相同由相关格保证
+    pub fn line_candidates(&self, line: usize, domains: &[Candidate]) -> Vec<Candidate> {
+        let line = &self.lines[line];
+        let mut out: Vec<Candidate> = line.cells.iter().map(|&idx| domains[idx]).collect();
+        if out.iter().any(Candidate::is_empty) {
+            return vec![Candidate::default(); out.len()];
+        }
+        let values = |c: Candidate| (0..self.size as u8).filter(move |&v| c.contains(v));
+        let lowest = |c: Candidate| values(c).next().map_or(0, |v| v as u32 + 1);
+        let highest = |c: Candidate| values(c).next_back().map_or(0, |v| v as u32 + 1);
+        let keep = |c: Candidate, f: &dyn Fn(u32) -> bool| {
+            let mut kept = Candidate::default();
+            for v in values(c).filter(|&v| f(v as u32 + 1)) {
+                kept.add(v);
+            }
+            kept
+        };
+
+        match line.kind {
+            LineKind::Thermometer => {
+                for i in 1..out.len() {
+                    let low = lowest(out[i - 1]);
+                    out[i] = keep(out[i], &|d| d > low);
+                }
+                for i in (0..out.len() - 1).rev() {
+                    let high = highest(out[i + 1]);
+                    out[i] = keep(out[i], &|d| d < high);
+                }
+            }
+            LineKind::Arrow => {
+                let shaft = &out[1..];
+                let min: u32 = shaft.iter().map(|&c| lowest(c)).sum();
+                let max: u32 = shaft.iter().map(|&c| highest(c)).sum();
+                out[0] = keep(out[0], &|d| (min..=max).contains(&d));
+                let (low, high) = (lowest(out[0]), highest(out[0]));
+                for domain in out[1..].iter_mut() {
+                    let (others_min, others_max) = (min - lowest(*domain), max - highest(*domain));
+                    *domain = keep(*domain, &|d| {
+                        d + others_max >= low && d + others_min <= high
+                    });
+                }
+            }
+            LineKind::Whisper => {
+                let gap = self.size.div_ceil(2) as u32;
+                let original = out.clone();
+                for (i, domain) in out.iter_mut().enumerate() {
+                    let neighbors = [i.checked_sub(1), Some(i + 1)];
+                    for j in neighbors
+                        .into_iter()
+                        .flatten()
+                        .filter(|&j| j < original.len())
+                    {
+                        let other = original[j];
+                        *domain = keep(*domain, &|d| {
+                            values(other).any(|u| (u as u32 + 1).abs_diff(d) >= gap)
+                        });
+                    }
+                }
+            }
+            LineKind::Renban => {
+                let n = out.len();
+                let mut allowed = Candidate::default();
+                for start in 0..=self.size - n {
+                    let mut run = Candidate::default();
+                    for v in start..start + n {
+                        run.add(v as u8);
+                    }
+                    let fits: Vec<Candidate> = out.iter().map(|c| c.intersect(&run)).collect();
+                    let covered = fits.iter().fold(Candidate::default(), |a, c| a.union(c));
+                    if fits.iter().all(|c| !c.is_empty()) && covered == run {
+                        allowed = allowed.union(&run);
+                    }
+                }
+                for domain in out.iter_mut() {
+                    *domain = domain.intersect(&allowed);
+                }
+            }
+        }
+        out
+    }
+
+    /// 在当前的候选数下第sandwich个三明治所在行（列）每个格子还能填写的数字，顺序与unit_indexes相同，
+    /// 格式同cage_candidates：逐一尝试1和9的位置，两端之间的格子按组合表筛选，外面的格子不能是1和9
+    pub fn sandwich_candidates(&self, sandwich: usize, domains: &[Candidate]) -> Vec<Candidate> {
+        let (unit, _, ref combinations) = self.sandwiches[sandwich];
+        let cells = self.unit_indexes(unit);
+        let (low, high) = (0, self.size as u8 - 1);
+        let mut ends = Candidate::default();
+        ends.add(low);
+        ends.add(high);
+
+        let mut out = vec![Candidate::default(); cells.len()];
+        for (p, q) in (0..cells.len()).flat_map(|p| (0..cells.len()).map(move |q| (p, q))) {
+            if p == q || !domains[cells[p]].contains(low) || !domains[cells[q]].contains(high) {
+                continue;
+            }
+            let (from, to) = (p.min(q), p.max(q));
+            let between = &cells[from + 1..to];
+            let feasible: Vec<&Candidate> = combinations[between.len()]
+                .iter()
+                .filter(|&combination| {
+                    let mut covered = Candidate::default();
+                    for &idx in between {
+                        let fit = domains[idx].intersect(combination);
+                        if fit.is_empty() {
+                            return false;
+                        }
+                        covered = covered.union(&fit);
+                    }
+                    covered == *combination
+                })
+                .collect();
+            let outside = |i: usize| i != p && i != q && !(from..to).contains(&i);
+            if feasible.is_empty()
+                || (0..cells.len())
+                    .filter(|&i| outside(i))
+                    .any(|i| domains[cells[i]].subtract(&ends).is_empty())
+            {
+                continue;
+            }
+            let fillings = feasible
+                .into_iter()
+                .fold(Candidate::default(), |all, c| all.union(c));
+            out[p].add(low);
+            out[q].add(high);
+            for (i, &idx) in cells.iter().enumerate() {
+                if (from + 1..to).contains(&i) {
+                    out[i] = out[i].union(&domains[idx].intersect(&fillings));
+                } else if outside(i) {
+                    out[i] = out[i].union(&domains[idx].subtract(&ends));
+                }
+            }
+        }
+        out
+    }
+
+    /// 三明治提示与已填数字的矛盾，value同line_conflicts，返回矛盾所在行（列）的1和9两个格子
+    pub fn sandwich_conflicts(&self, value: impl Fn(usize) -> Option<u8>) -> Vec<(usize, usize)> {
+        let mut conflicts = vec![];
+        for &(unit, sum, _) in &self.sandwiches {
+            let cells = self.unit_indexes(unit);
+            let position = |v: u8| cells.iter().position(|&idx| value(idx) == Some(v));
+            let (Some(p), Some(q)) = (position(0), position(self.size as u8 - 1)) else {
+                continue;
+            };
+            let between = &cells[p.min(q) + 1..p.max(q)];
+            let placed: Vec<u32> = between
+                .iter()
+                .filter_map(|&idx| value(idx).map(|v| v as u32 + 1))
+                .collect();
+            let total: u32 = placed.iter().sum();
+            if total > sum || (placed.len() == between.len() && total != sum) {
+                conflicts.push((cells[p], cells[q]));
+            }
+        }
+        conflicts
+    }
+
+    /// 线上已填的数字之间的矛盾，value为每个格子已填的数字，返回互相矛盾的两个格子
+    pub fn line_conflicts(&self, value: impl Fn(usize) -> Option<u8>) -> Vec<(usize, usize)> {
+        let mut conflicts = vec![];
+        for line in &self.lines {
+            let placed: Vec<(usize, usize, u32)> = line
+                .cells
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &idx)| value(idx).map(|v| (i, idx, v as u32 + 1)))
+                .collect();
+            match line.kind {
+                LineKind::Arrow => {
+                    let Some(&(0, circle, total)) = placed.first() else {
+                        continue;
+                    };
+                    let Some(&(_, last, _)) = placed.last().filter(|_| placed.len() > 1) else {
+                        continue;
+                    };
+                    let sum: u32 = placed[1..].iter().map(|&(_, _, d)| d).sum();
+                    let unplaced = (line.cells.len() - placed.len()) as u32;
+                    if sum + unplaced > total || (unplaced == 0 && sum != total) {
+                        conflicts.push((circle, last));
+                    }
+                }
+                kind => {
+                    for (k, &(i, a, x)) in placed.iter().enumerate() {
+                        for &(j, b, y) in &placed[k + 1..] {
+                            let broken = match kind {
+                                LineKind::Thermometer => y < x + (j - i) as u32,
+                                LineKind::Whisper => {
+                                    j == i + 1 && x.abs_diff(y) < self.size.div_ceil(2) as u32
+                                }
+                                _ => x == y || x.abs_diff(y) >= line.cells.len() as u32,
+                            };
+                            if broken {
+                                conflicts.push((a, b));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// 按行列宫以外的约束（如笼子和、相邻格的关系）缩小每个格子的候选数，格式同cage_candidates，
+    /// 返回false表示约束已经无法满足
+    pub fn prune(&self, domains: &mut [Candidate]) -> bool {
+        for (i, cage) in self.cages.iter().enumerate() {
+            let allowed = self.cage_candidates(i, domains);
+            for &idx in &cage.cells {
+                domains[idx] = domains[idx].intersect(&allowed);
+                if domains[idx].is_empty() {
+                    return false;
+                }
+            }
+        }
+        // 一格缩小后可能影响相邻的其他格子，重复直到不再变化
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for &(a, b, relation) in &self.relations {
+                for (from, to) in [(a, b), (b, a)] {
+                    let allowed = Rules::relation_candidates(relation, domains[from], domains[to]);
+                    if allowed != domains[to] {
+                        domains[to] = allowed;
+                        changed = true;
+                    }
+                    if allowed.is_empty() {
+                        return false;
+                    }
+                }
+            }
+            for (i, &(unit, _, _)) in self.sandwiches.iter().enumerate() {
+                let cells = self.unit_indexes(unit);
+                for (&idx, allowed) in cells.iter().zip(self.sandwich_candidates(i, domains)) {
+                    if allowed != domains[idx] {
+                        domains[idx] = allowed;
+                        changed = true;
+                    }
+                    if allowed.is_empty() {
+                        return false;
+                    }
+                }
+            }
+            for (i, line) in self.lines.iter().enumerate() {
+                for (&idx, allowed) in line.cells.iter().zip(self.line_candidates(i, domains)) {
+                    if allowed != domains[idx] {
+                        domains[idx] = allowed;
+                        changed = true;
+                    }
+                    if allowed.is_empty() {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// 区域图，每格一个字符，区域编号用value_to_char表示，可以再由Rules::jigsaw解析
+    pub fn regions(&self) -> String {
+        self.coords
+            .iter()
+            .map(|p| value_to_char(p.g as u8))
+            .collect()
+    }
+
+    /// 格子右侧是否为宫（区域）的边界，最右一列总是边界
+    pub fn thick_right(&self, rc: RCCoords) -> bool {
+        rc.c + 1 >= self.size
+            || self.coords(rc).g
+                != self
+                    .coords(RCCoords {
+                        r: rc.r,
+                        c: rc.c + 1,
+                    })
+                    .g
+    }
+
+    /// 格子下方是否为宫（区域）的边界，最下一行总是边界
+    pub fn thick_below(&self, rc: RCCoords) -> bool {
+        rc.r + 1 >= self.size
+            || self.coords(rc).g
+                != self
+                    .coords(RCCoords {
+                        r: rc.r + 1,
+                        c: rc.c,
+                    })
+                    .g
+    }
+
+    /// 是否为按边长默认的宫形状，此时序列化时可以省略规则
+    pub fn is_default_for_size(&self) -> bool {
+        Rules::with_size(self.size).is_ok_and(|r| r == *self)
+    }
+
+    /// 格子在行优先排列中的下标
+    pub fn index(&self, rc: RCCoords) -> usize {
+        rc.r * self.size + rc.c
+    }
+
+    pub fn rc(&self, idx: usize) -> RCCoords {
+        RCCoords {
+            r: idx / self.size,
+            c: idx % self.size,
+        }
+    }
+
+    /// 包含宫号和宫内序号的完整坐标
+    pub fn coords(&self, rc: RCCoords) -> Coords {
+        self.coords[self.index(rc)]
+    }
+
+    pub fn gn_to_rc(&self, gn: GNCoords) -> RCCoords {
+        self.rc(self.grids[gn.g][gn.n])
+    }
+
+    /// 所有单元，顺序为所有行、所有列、所有宫，之后为变体规则额外的单元
+    pub fn units(&self) -> &[Unit] {
+        &self.units
+    }
+
+    /// 变体规则在行列宫之外额外的单元
+    pub fn extra_units(&self) -> &[Unit] {
+        &self.units[self.size * 3..]
+    }
+
+    /// 所有单元的格子下标，顺序与units相同
+    pub fn unit_cells(&self) -> &[Vec<usize>] {
+        &self.unit_cells
+    }
+
+    /// 格子所在的所有单元，先行列宫，后额外的单元，最后是所在的笼子
+    pub fn units_of(&self, coords: Coords) -> Vec<Unit> {
+        let idx = coords.r * self.size + coords.c;
+        let mut units = Unit::units_of(coords).to_vec();
+        units.extend(
+            self.extra_units()
+                .iter()
+                .zip(&self.unit_cells[self.size * 3..])
+                .filter(|(_, cells)| cells.contains(&idx))
+                .map(|(&u, _)| u),
+        );
+        units.extend(self.cage_of(idx).map(Unit::Cage));
+        units
+    }
+
+    /// 单元内所有格子的下标
+    pub fn unit_indexes(&self, unit: Unit) -> Vec<usize> {
+        let size = self.size;
+        match unit {
+            Unit::Row(r) => (0..size).map(|c| r * size + c).collect(),
+            Unit::Column(c) => (0..size).map(|r| r * size + c).collect(),
+            Unit::Grid(g) => self.grids[g].clone(),
+            Unit::Diagonal(0) => (0..size).map(|i| i * size + i).collect(),
+            Unit::Diagonal(_) => (0..size).map(|i| i * size + size - 1 - i).collect(),
+            Unit::Window(w) => {
+                let (r0, c0) = self.window_origins()[w];
+                (0..size)
+                    .map(|i| (r0 + i / self.box_cols) * size + c0 + i % self.box_cols)
+                    .collect()
+            }
+            Unit::Cage(k) => self.cages[k].cells.clone(),
+        }
+    }
+
+    /// 单元内所有格子的坐标
+    pub fn unit_coords(&self, unit: Unit) -> Vec<Coords> {
+        self.unit_indexes(unit)
+            .into_iter()
+            .map(|idx| self.coords[idx])
+            .collect()
+    }
+
+    /// 相关格的下标，不含自身
+    pub fn peers(&self, idx: usize) -> &[usize] {
+        &self.peers[idx]
+    }
+
+    // 按序号交替取同列、同行、同宫、额外单元及笼子的格子，已经属于前面单元的格子留给前面的单元，
+    // 与9x9时逐个检查同行列宫的顺序保持一致，推理结论的顺序因此不变，无马、无王及连号线的相关格排在最后
+    fn compute_peers(&self, idx: usize) -> Vec<usize> {
+        let coords = self.coords[idx];
+        let mut units = self.units_of(coords);
+        units.swap(0, 1);
+        let units: Vec<Vec<usize>> = units.into_iter().map(|u| self.unit_indexes(u)).collect();
+        let longest = units.iter().map(Vec::len).max().unwrap_or(0);
+
+        let mut peers = vec![];
+        for i in 0..longest {
+            for (j, unit) in units.iter().enumerate() {
+                let Some(&p) = unit.get(i) else {
+                    continue;
+                };
+                if p != idx && !units[..j].iter().any(|u| u.contains(&p)) && !peers.contains(&p) {
+                    peers.push(p);
+                }
+            }
+        }
+        let renban = self
+            .lines
+            .iter()
+            .filter(|line| line.kind == LineKind::Renban && line.cells.contains(&idx))
+            .flat_map(|line| line.cells.iter().copied());
+        for p in self.chess_neighbors(idx).into_iter().chain(renban) {
+            if p != idx && !peers.contains(&p) {
+                peers.push(p);
+            }
+        }
+        peers
+    }
+
+    // 非连续规则作用于所有相邻的两格，圆点和XV标记只作用于所在的两格，反向约束作用于没有标记的相邻两格
+    fn compute_relations(&self) -> Vec<(usize, usize, Relation)> {
+        let size = self.size;
+        let mut relations = vec![];
+        let pairs = (0..size * size).flat_map(|idx| {
+            let right = (idx % size + 1 < size).then_some((idx, idx + 1));
+            let below = (idx / size + 1 < size).then_some((idx, idx + size));
+            [right, below].into_iter().flatten()
+        });
+        for (a, b) in pairs {
+            let dot = self
+                .dots
+                .iter()
+                .find(|d| d.cells == [a, b] || d.cells == [b, a]);
+            match dot {
+                Some(Dot {
+                    color: DotColor::White,
+                    ..
+                }) => relations.push((a, b, Relation::Consecutive)),
+                Some(Dot {
+                    color: DotColor::Black,
+                    ..
+                }) => relations.push((a, b, Relation::Double)),
+                None if self.negative_dots => {
+                    relations.push((a, b, Relation::NotConsecutive));
+                    relations.push((a, b, Relation::NotDouble));
+                }
+                None => {}
+            }
+            if self.non_consecutive && !relations.contains(&(a, b, Relation::NotConsecutive)) {
+                relations.push((a, b, Relation::NotConsecutive));
+            }
+            let mark = self
+                .xv_marks
+                .iter()
+                .find(|m| m.cells == [a, b] || m.cells == [b, a]);
+            match mark {
+                Some(mark) => relations.push((a, b, Relation::Sum(mark.sum))),
+                None if self.negative_xv => {
+                    relations.push((a, b, Relation::NotSum(10)));
+                    relations.push((a, b, Relation::NotSum(5)));
+                }
+                None => {}
+            }
+        }
+        relations
+    }
+
+    /// 每个格子内每行显示的草稿数，草稿按该宽度排成方阵
+    pub fn candidates_per_line(&self) -> usize {
+        (1..=self.size).find(|k| k * k >= self.size).unwrap_or(1)
+    }
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules::classic()
+    }
+}
+
+impl TryFrom<RulesSpec> for Rules {
+    type Error = String;
+
+    fn try_from(spec: RulesSpec) -> Result<Self, Self::Error> {
+        let rules = match spec {
+            RulesSpec {
+                regions: Some(ref regions),
+                ..
+            } => Rules::jigsaw(regions),
+            RulesSpec {
+                box_rows: Some(box_rows),
+                box_cols: Some(box_cols),
+                ..
+            } => Rules::new(box_rows, box_cols),
+            _ => Err("Missing Box Shape Or Regions.".to_string()),
+        }?;
+        let rules = if spec.diagonals {
+            rules.with_diagonals()
+        } else {
+            rules
+        };
+        let rules = if spec.windows {
+            rules.with_windows()
+        } else {
+            rules
+        };
+        let rules = if spec.anti_knight {
+            rules.with_anti_knight()
+        } else {
+            rules
+        };
+        let rules = if spec.anti_king {
+            rules.with_anti_king()
+        } else {
+            rules
+        };
+        let rules = if spec.non_consecutive {
+            rules.with_non_consecutive()
+        } else {
+            rules
+        };
+        let rules = if spec.dots.is_empty() && !spec.negative_dots {
+            rules
+        } else {
+            rules.with_dots(spec.dots, spec.negative_dots)?
+        };
+        let rules = if spec.lines.is_empty() {
+            rules
+        } else {
+            rules.with_lines(spec.lines)?
+        };
+        let rules = if spec.sandwich_rows.is_empty() && spec.sandwich_cols.is_empty() {
+            rules
+        } else {
+            rules.with_sandwiches(spec.sandwich_rows, spec.sandwich_cols)?
+        };
+        let rules = if spec.xv_marks.is_empty() && !spec.negative_xv {
+            rules
+        } else {
+            rules.with_xv(spec.xv_marks, spec.negative_xv)?
+        };
+        if spec.cages.is_empty() {
+            Ok(rules)
+        } else {
+            rules.with_cages(spec.cages)
+        }
+    }
+}
+
+impl From<Rules> for RulesSpec {
+    fn from(rules: Rules) -> Self {
+        if rules.jigsaw {
+            RulesSpec {
+                box_rows: None,
+                box_cols: None,
+                regions: Some(rules.regions()),
+                diagonals: rules.diagonals,
+                windows: rules.windows,
+                anti_knight: rules.anti_knight,
+                anti_king: rules.anti_king,
+                cages: rules.cages,
+                non_consecutive: rules.non_consecutive,
+                dots: rules.dots,
+                negative_dots: rules.negative_dots,
+                lines: rules.lines,
+                sandwich_rows: rules.sandwich_rows,
+                sandwich_cols: rules.sandwich_cols,
+                xv_marks: rules.xv_marks,
+                negative_xv: rules.negative_xv,
+            }
+        } else {
+            RulesSpec {
+                box_rows: Some(rules.box_rows),
+                box_cols: Some(rules.box_cols),
+                regions: None,
+                diagonals: rules.diagonals,
+                windows: rules.windows,
+                anti_knight: rules.anti_knight,
+                anti_king: rules.anti_king,
+                cages: rules.cages,
+                non_consecutive: rules.non_consecutive,
+                dots: rules.dots,
+                negative_dots: rules.negative_dots,
+                lines: rules.lines,
+                sandwich_rows: rules.sandwich_rows,
+                sandwich_cols: rules.sandwich_cols,
+                xv_marks: rules.xv_marks,
+                negative_xv: rules.negative_xv,
+            }
+        }
+    }
+}
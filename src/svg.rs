@@ -0,0 +1,415 @@
+// SVG绘制：输出矢量图，用于博客和文档中的插图，不依赖浏览器
+// 可以叠加一步推理的结构化说明，只画单元底色和按角色着色的草稿
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    explanation::{CellValue, Explanation, Role},
+    inferences::InferenceResult,
+    rules::{DotColor, LineKind},
+    types::{value_to_char, CellStatus, RCCoords, Sudoku},
+};
+
+/// 绘制选项，尺寸单位为像素
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SvgOptions {
+    pub cell_size: f64,
+    pub margin: f64,
+    // 是否绘制草稿，印刷题目时一般关闭
+    pub show_candidates: bool,
+    pub font_family: String,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        SvgOptions {
+            cell_size: 60.0,
+            margin: 10.0,
+            show_candidates: true,
+            font_family: "sans-serif".to_string(),
+        }
+    }
+}
+
+const BACKGROUND_COLOR: &str = "#ffffff";
+const GIVEN_COLOR: &str = "#000000";
+const VALUE_COLOR: &str = "#1565c0";
+const CANDIDATE_COLOR: &str = "#555555";
+const THIN_LINE_COLOR: &str = "#999999";
+const DIAGONAL_COLOR: &str = "#bdbdbd";
+const WINDOW_FILL: &str = "#eeeeee";
+const CAGE_COLOR: &str = "#424242";
+const THERMOMETER_COLOR: &str = "#bdbdbd";
+const ARROW_COLOR: &str = "#9e9e9e";
+const WHISPER_COLOR: &str = "#66bb6a";
+const RENBAN_COLOR: &str = "#ce93d8";
+const BASE_UNIT_FILL: &str = "#e3f2fd";
+const COVER_UNIT_FILL: &str = "#fff8e1";
+
+fn role_color(role: Role) -> &'static str {
+    match role {
+        Role::Base => "#64b5f6",
+        Role::Placement => "#81c784",
+        Role::Elimination => "#e57373",
+    }
+}
+
+struct Layout<'a> {
+    options: &'a SvgOptions,
+    // 格子内每行的草稿数
+    per_line: usize,
+}
+
+impl Layout<'_> {
+    fn cell_origin(&self, rc: RCCoords) -> (f64, f64) {
+        let size = self.options.cell_size;
+        (
+            self.options.margin + rc.c as f64 * size,
+            self.options.margin + rc.r as f64 * size,
+        )
+    }
+
+    fn cell_center(&self, rc: RCCoords) -> (f64, f64) {
+        let (x, y) = self.cell_origin(rc);
+        let half = self.options.cell_size / 2.0;
+        (x + half, y + half)
+    }
+
+    // 草稿数排成方阵，9x9时为3x3
+    fn candidate_center(&self, cv: CellValue) -> (f64, f64) {
+        let (x, y) = self.cell_origin(cv.coords);
+        let k = self.per_line;
+        let step = self.options.cell_size / k as f64;
+        let value = cv.value as usize;
+        (
+            x + (value % k) as f64 * step + step / 2.0,
+            y + (value / k) as f64 * step + step / 2.0,
+        )
+    }
+}
+
+impl Sudoku {
+    /// 使用默认选项绘制盘面
+    pub fn to_svg(&self) -> String {
+        self.to_svg_with(&SvgOptions::default(), None)
+    }
+
+    /// 绘制盘面，explanation不为空时叠加单元底色和草稿高亮
+    pub fn to_svg_with(&self, options: &SvgOptions, explanation: Option<&Explanation>) -> String {
+        // 三明治提示写在盘面左侧和上方，留白至少要放得下两位数
+        let widened;
+        let options = if self.rules().sandwiches().is_empty() {
+            options
+        } else {
+            widened = SvgOptions {
+                margin: options.margin.max(options.cell_size * 0.6),
+                ..options.clone()
+            };
+            &widened
+        };
+        let per_line = self.rules().candidates_per_line();
+        let layout = Layout { options, per_line };
+        let size = options.cell_size;
+        let cells = self.size();
+        let width = size * cells as f64 + options.margin * 2.0;
+        let third = size / per_line as f64;
+        let mut lines = vec![
+            format!(
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{w}" viewBox="0 0 {w} {w}" font-family="{}">"#,
+                options.font_family,
+                w = width
+            ),
+            format!(
+                r#"<rect width="{w}" height="{w}" fill="{}"/>"#,
+                BACKGROUND_COLOR,
+                w = width
+            ),
+        ];
+
+        // 窗口数独的窗口画成灰色底色，在推理单元的底色下面
+        let rules = self.rules();
+        if rules.has_windows() {
+            for (r, c) in rules.window_origins() {
+                let (x, y) = layout.cell_origin(RCCoords { r, c });
+                lines.push(format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x,
+                    y,
+                    size * rules.box_cols() as f64,
+                    size * rules.box_rows() as f64,
+                    WINDOW_FILL
+                ));
+            }
+        }
+
+        // 单元底色，先画覆盖集，再画基础集
+        if let Some(explanation) = explanation {
+            let units = [
+                (&explanation.cover_units, COVER_UNIT_FILL),
+                (&explanation.base_units, BASE_UNIT_FILL),
+            ];
+            for (units, fill) in units {
+                for coords in units.iter().flat_map(|&u| self.rules().unit_coords(u)) {
+                    let (x, y) = layout.cell_origin(coords.into());
+                    lines.push(format!(
+                        r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                        x, y, size, size, fill
+                    ));
+                }
+            }
+        }
+
+        // 草稿的高亮底色
+        if let Some(explanation) = explanation {
+            for h in &explanation.highlights {
+                let (cx, cy) = layout.candidate_center(CellValue {
+                    coords: h.coords,
+                    value: h.value,
+                });
+                lines.push(format!(
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    cx,
+                    cy,
+                    third * 0.45,
+                    role_color(h.role)
+                ));
+            }
+        }
+
+        // 对角线数独的两条对角线，画在网格线下面
+        if self.rules().has_diagonals() {
+            let (from, to) = (options.margin, options.margin + size * cells as f64);
+            for (x1, x2) in [(from, to), (to, from)] {
+                lines.push(format!(
+                    r#"<line x1="{x1}" y1="{from}" x2="{x2}" y2="{to}" stroke="{DIAGONAL_COLOR}" stroke-width="2"/>"#
+                ));
+            }
+        }
+
+        // 网格线，宫的边界加粗，宫不是正方形时横竖两个方向的间隔不同
+        // 不规则区域先画细线，再逐段加粗区域的边界
+        let line_style = |i: usize, step: usize| {
+            if (i.is_multiple_of(step) && !rules.is_jigsaw()) || i == 0 || i == cells {
+                (GIVEN_COLOR, 3)
+            } else {
+                (THIN_LINE_COLOR, 1)
+            }
+        };
+        let line = |(x1, y1): (f64, f64), (x2, y2): (f64, f64), (color, stroke): (&str, usize)| {
+            format!(
+                r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{color}" stroke-width="{stroke}" stroke-linecap="square"/>"#
+            )
+        };
+        let from = options.margin;
+        let to = options.margin + size * cells as f64;
+        for i in 0..=cells {
+            let p = options.margin + i as f64 * size;
+            lines.push(line((p, from), (p, to), line_style(i, rules.box_cols())));
+            lines.push(line((from, p), (to, p), line_style(i, rules.box_rows())));
+        }
+        if rules.is_jigsaw() {
+            for rc in self.iter_rc() {
+                let (x, y) = layout.cell_origin(rc);
+                if rules.thick_right(rc) {
+                    lines.push(line((x + size, y), (x + size, y + size), (GIVEN_COLOR, 3)));
+                }
+                if rules.thick_below(rc) {
+                    lines.push(line((x, y + size), (x + size, y + size), (GIVEN_COLOR, 3)));
+                }
+            }
+        }
+
+        // 杀手数独的笼子，虚线框向格内缩进，笼子和写在笼子第一个格子的左上角
+        let inset = 0.08;
+        let scale = |v: f64| options.margin + v * size;
+        for (i, cage) in rules.cages().iter().enumerate() {
+            for ((x1, y1), (x2, y2)) in rules.cage_outline(i, inset) {
+                let (x1, y1, x2, y2) = (scale(x1), scale(y1), scale(x2), scale(y2));
+                lines.push(format!(
+                    r#"<line x1="{x1}" y1="{y1}" x2="{x2}" y2="{y2}" stroke="{CAGE_COLOR}" stroke-width="1" stroke-dasharray="4 3"/>"#
+                ));
+            }
+            let first = cage.cells.iter().min().copied().unwrap_or_default();
+            let (x, y) = layout.cell_origin(rules.rc(first));
+            lines.push(format!(
+                r#"<text x="{}" y="{}" font-size="{}" fill="{}" dominant-baseline="hanging">{}</text>"#,
+                x + inset * size + 1.0,
+                y + inset * size + 1.0,
+                size * 0.2,
+                CAGE_COLOR,
+                cage.sum
+            ));
+        }
+
+        // 线条约束：温度计是带圆头的粗灰线，箭头从圆圈出发并在末端画出箭头，
+        // 德国悄悄话和连号线分别是绿色和紫色的线
+        for line in rules.lines() {
+            let points: Vec<(f64, f64)> = line
+                .cells
+                .iter()
+                .map(|&idx| layout.cell_center(rules.rc(idx)))
+                .collect();
+            let polyline = |points: &[(f64, f64)], color: &str, width: f64| {
+                let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                format!(
+                    r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"/>"#,
+                    points.join(" "),
+                    color,
+                    width
+                )
+            };
+            let (x0, y0) = points[0];
+            match line.kind {
+                LineKind::Thermometer => {
+                    lines.push(polyline(&points, THERMOMETER_COLOR, size * 0.25));
+                    lines.push(format!(
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                        x0,
+                        y0,
+                        size * 0.35,
+                        THERMOMETER_COLOR
+                    ));
+                }
+                LineKind::Arrow => {
+                    // 箭身从圆圈边缘开始，箭头的两翼沿最后一段反方向张开
+                    let radius = size * 0.4;
+                    let (x1, y1) = points[1];
+                    let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+                    let mut shaft = points.clone();
+                    shaft[0] = (
+                        x0 + (x1 - x0) * radius / length,
+                        y0 + (y1 - y0) * radius / length,
+                    );
+                    lines.push(polyline(&shaft, ARROW_COLOR, 2.0));
+                    let (xe, ye) = points[points.len() - 1];
+                    let (xp, yp) = points[points.len() - 2];
+                    let angle = (ye - yp).atan2(xe - xp);
+                    for wing in [0.5f64, -0.5] {
+                        let a = angle + std::f64::consts::PI + wing;
+                        let tip = (xe + a.cos() * size * 0.25, ye + a.sin() * size * 0.25);
+                        lines.push(polyline(&[tip, (xe, ye)], ARROW_COLOR, 2.0));
+                    }
+                    lines.push(format!(
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="2"/>"#,
+                        x0, y0, radius, ARROW_COLOR
+                    ));
+                }
+                LineKind::Whisper => lines.push(polyline(&points, WHISPER_COLOR, size * 0.12)),
+                LineKind::Renban => lines.push(polyline(&points, RENBAN_COLOR, size * 0.2)),
+            }
+        }
+
+        // 克罗普基圆点画在两个格子相邻的边的中点
+        for dot in rules.dots() {
+            let (x1, y1) = layout.cell_center(rules.rc(dot.cells[0]));
+            let (x2, y2) = layout.cell_center(rules.rc(dot.cells[1]));
+            let fill = match dot.color {
+                DotColor::White => "white",
+                DotColor::Black => GIVEN_COLOR,
+            };
+            lines.push(format!(
+                r#"<circle cx="{}" cy="{}" r="{}" fill="{}" stroke="{}" stroke-width="1"/>"#,
+                (x1 + x2) / 2.0,
+                (y1 + y2) / 2.0,
+                size * 0.1,
+                fill,
+                GIVEN_COLOR
+            ));
+        }
+
+        // XV标记写在两个格子相邻的边的中点，白底遮住边框
+        for mark in rules.xv_marks() {
+            let (x1, y1) = layout.cell_center(rules.rc(mark.cells[0]));
+            let (x2, y2) = layout.cell_center(rules.rc(mark.cells[1]));
+            let (cx, cy) = ((x1 + x2) / 2.0, (y1 + y2) / 2.0);
+            lines.push(format!(
+                r#"<rect x="{}" y="{}" width="{r}" height="{r}" fill="{}"/>"#,
+                cx - size * 0.12,
+                cy - size * 0.12,
+                BACKGROUND_COLOR,
+                r = size * 0.24
+            ));
+            lines.push(format!(
+                r#"<text x="{}" y="{}" font-size="{}" font-weight="bold" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                cx,
+                cy,
+                size * 0.25,
+                GIVEN_COLOR,
+                if mark.sum == 5 { "V" } else { "X" }
+            ));
+        }
+
+        // 三明治提示写在行的左侧和列的上方
+        let clues = [
+            (rules.sandwich_rows(), true),
+            (rules.sandwich_cols(), false),
+        ];
+        for (clues, is_row) in clues {
+            for (i, sum) in clues.iter().enumerate() {
+                let Some(sum) = sum else { continue };
+                let middle = options.margin + (i as f64 + 0.5) * size;
+                let (x, y) = if is_row {
+                    (options.margin / 2.0, middle)
+                } else {
+                    (middle, options.margin / 2.0)
+                };
+                lines.push(format!(
+                    r#"<text x="{}" y="{}" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                    x,
+                    y,
+                    size * 0.35,
+                    GIVEN_COLOR,
+                    sum
+                ));
+            }
+        }
+
+        // 数值和草稿
+        for rc in self.iter_rc() {
+            let cell = self.get_cell_ref_by_rc(rc);
+            match (cell.status, cell.value) {
+                (CellStatus::FIXED | CellStatus::SOLVE, Some(value)) => {
+                    let (cx, cy) = layout.cell_center(rc);
+                    let (color, weight) = if cell.status == CellStatus::FIXED {
+                        (GIVEN_COLOR, "bold")
+                    } else {
+                        (VALUE_COLOR, "normal")
+                    };
+                    lines.push(format!(
+                        r#"<text x="{}" y="{}" font-size="{}" font-weight="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                        cx,
+                        cy,
+                        size * 0.6,
+                        weight,
+                        color,
+                        value_to_char(value)
+                    ));
+                }
+                _ if options.show_candidates => {
+                    for value in cell.candidates.to_vec() {
+                        let (cx, cy) = layout.candidate_center(CellValue { coords: rc, value });
+                        lines.push(format!(
+                            r#"<text x="{}" y="{}" font-size="{}" fill="{}" text-anchor="middle" dominant-baseline="central">{}</text>"#,
+                            cx,
+                            cy,
+                            third * 0.75,
+                            CANDIDATE_COLOR,
+                            value_to_char(value)
+                        ));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        lines.push("</svg>".to_string());
+        lines.join("\n")
+    }
+}
+
+impl InferenceResult<'_> {
+    /// 在推理前的盘面上绘制这一步推理
+    pub fn to_svg(&self, field: &Sudoku, options: &SvgOptions) -> String {
+        field.to_svg_with(options, Some(&self.explain()))
+    }
+}
@@ -0,0 +1,311 @@
+// 终端游戏：在print的盘面上加入光标、颜色和按键操作，方便不打开网页前端直接试玩生成的题目
+// 这里只负责按键处理和绘制，终端的原始模式和读写由命令行工具负责
+
+use crate::{
+    explanation::{Explanation, Role},
+    game::Game,
+    inferences::InferenceSet,
+    locale::Catalog,
+    types::{CellStatus, Difficulty, RCCoords},
+    validator::{Mistake, MistakeKind},
+};
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const REVERSE: &str = "\x1b[7m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const CYAN: &str = "\x1b[36m";
+const RED_BACKGROUND: &str = "\x1b[41m";
+
+const DIFFICULTIES: [Difficulty; 5] = [
+    Difficulty::EASY,
+    Difficulty::NORMAL,
+    Difficulty::MIDIUM,
+    Difficulty::HARD,
+    Difficulty::EXPERT,
+];
+
+const HELP: &str = "方向键/hjkl 移动  1-9 填写  0/空格/退格 清除  p 草稿模式  u 撤销  r 重做\n\
+                    ? 提示（再按一次执行）  c 检查  n 新题  q 退出";
+
+/// 按键，数字从0开始
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Digit(u8),
+    Clear,
+    Char(char),
+}
+
+impl Key {
+    /// 解析从终端读到的字节，一次读取可能包含多个按键
+    pub fn parse(bytes: &[u8]) -> Vec<Key> {
+        let mut keys = vec![];
+        let mut i = 0;
+        while i < bytes.len() {
+            let (key, len) = match bytes[i..] {
+                [0x1b, b'[', b'A', ..] => (Key::Up, 3),
+                [0x1b, b'[', b'B', ..] => (Key::Down, 3),
+                [0x1b, b'[', b'C', ..] => (Key::Right, 3),
+                [0x1b, b'[', b'D', ..] => (Key::Left, 3),
+                [0x1b, b'[', b'3', b'~', ..] => (Key::Clear, 4),
+                [b @ b'1'..=b'9', ..] => (Key::Digit(b - b'1'), 1),
+                [b'0' | b' ' | b'x' | 0x7f | 0x08, ..] => (Key::Clear, 1),
+                [b'k', ..] => (Key::Up, 1),
+                [b'j', ..] => (Key::Down, 1),
+                [b'h', ..] => (Key::Left, 1),
+                [b'l', ..] => (Key::Right, 1),
+                // 回车来自没有原始模式的终端，忽略
+                [b'\r' | b'\n', ..] => {
+                    i += 1;
+                    continue;
+                }
+                [b, ..] => (Key::Char(b as char), 1),
+                [] => break,
+            };
+            keys.push(key);
+            i += len;
+        }
+        keys
+    }
+}
+
+pub struct Tui {
+    game: Game,
+    difficulty: Difficulty,
+    cursor: RCCoords,
+    // 草稿模式下数字键切换草稿，否则填写数值
+    pencil: bool,
+    // 当前显示的提示，再次按下提示键时执行
+    hint: Option<Explanation>,
+    // 最近一次检查的结果，盘面变化后清除
+    mistakes: Vec<Mistake>,
+    // 按下n之后等待选择难度
+    choosing_difficulty: bool,
+    message: String,
+    quit: bool,
+}
+
+impl Tui {
+    pub fn new(game: Game, difficulty: Difficulty) -> Tui {
+        Tui {
+            game,
+            difficulty,
+            cursor: RCCoords { r: 0, c: 0 },
+            pencil: false,
+            hint: None,
+            mistakes: vec![],
+            choosing_difficulty: false,
+            message: String::new(),
+            quit: false,
+        }
+    }
+
+    pub fn game(&self) -> &Game {
+        &self.game
+    }
+
+    pub fn cursor(&self) -> RCCoords {
+        self.cursor
+    }
+
+    pub fn is_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn handle_key(&mut self, key: Key) {
+        if self.choosing_difficulty {
+            self.choosing_difficulty = false;
+            match key {
+                Key::Digit(d) if (d as usize) < DIFFICULTIES.len() => {
+                    self.difficulty = DIFFICULTIES[d as usize];
+                    // 新题目沿用当前题目的规则
+                    let rules = self.game.field().rules().clone();
+                    *self = Tui::new(Game::generate_with(rules, self.difficulty), self.difficulty);
+                    self.message = format!("新题目：{:?}", self.difficulty);
+                }
+                _ => self.message = "已取消".to_string(),
+            }
+            return;
+        }
+
+        let RCCoords { r, c } = self.cursor;
+        let size = self.game.field().size();
+        match key {
+            Key::Up => self.cursor.r = (r + size - 1) % size,
+            Key::Down => self.cursor.r = (r + 1) % size,
+            Key::Left => self.cursor.c = (c + size - 1) % size,
+            Key::Right => self.cursor.c = (c + 1) % size,
+            Key::Digit(v) => {
+                let result = if !self.pencil {
+                    self.game.set_value(self.cursor, v)
+                } else if self
+                    .game
+                    .field()
+                    .get_cell_ref_by_rc(self.cursor)
+                    .candidates
+                    .contains(v)
+                {
+                    self.game.remove_candidate(self.cursor, v)
+                } else {
+                    self.game.add_candidate(self.cursor, v)
+                };
+                self.after_edit(result);
+            }
+            Key::Clear => {
+                let result = self.game.clear_value(self.cursor);
+                self.after_edit(result);
+            }
+            Key::Char('p') => {
+                self.pencil = !self.pencil;
+                self.message.clear();
+            }
+            Key::Char('u') => {
+                let action = self.game.undo();
+                self.message = action.map_or("没有可撤销的操作".to_string(), |a| {
+                    format!("撤销：{:?}", a)
+                });
+                self.after_edit(Ok(()));
+            }
+            Key::Char('r') => {
+                let action = self.game.redo();
+                self.message = action.map_or("没有可重做的操作".to_string(), |a| {
+                    format!("重做：{:?}", a)
+                });
+                self.after_edit(Ok(()));
+            }
+            Key::Char('?') => self.hint(),
+            Key::Char('c') => {
+                self.mistakes = self.game.check();
+                self.message = if self.game.is_solved() {
+                    "恭喜，已经完成！".to_string()
+                } else if self.mistakes.is_empty() {
+                    "没有发现错误".to_string()
+                } else {
+                    format!("发现{}处错误", self.mistakes.len())
+                };
+            }
+            Key::Char('n') => {
+                self.choosing_difficulty = true;
+                self.message = "选择难度：1 EASY  2 NORMAL  3 MIDIUM  4 HARD  5 EXPERT".to_string();
+            }
+            // Ctrl-C
+            Key::Char('q') | Key::Char('\x03') => self.quit = true,
+            Key::Char(_) => {}
+        }
+    }
+
+    // 盘面变化后原来的提示和检查结果都不再适用
+    fn after_edit(&mut self, result: Result<(), String>) {
+        self.hint = None;
+        self.mistakes.clear();
+        match result {
+            Err(e) => self.message = e,
+            Ok(()) if self.game.is_solved() => self.message = "恭喜，已经完成！".to_string(),
+            Ok(()) => {}
+        }
+    }
+
+    // 第一次按下时显示下一步的说明和高亮，第二次按下时执行
+    fn hint(&mut self) {
+        if self.hint.take().is_some() {
+            self.message = match self.game.apply_hint() {
+                Some(technique) => {
+                    format!("已执行：{}", Catalog::default().technique_name(technique))
+                }
+                None => "无法继续推导".to_string(),
+            };
+            self.after_edit(Ok(()));
+            return;
+        }
+        let ifs = InferenceSet::new();
+        match ifs.analyze(self.game.field()) {
+            Some(result) => {
+                let explanation = result.explain();
+                self.message = explanation.describe();
+                self.hint = Some(explanation);
+            }
+            None => self.message = "无法继续推导".to_string(),
+        }
+    }
+
+    // 某个草稿的颜色，由提示中的角色决定
+    fn candidate_color(&self, rc: RCCoords, value: u8) -> Option<&'static str> {
+        let hint = self.hint.as_ref()?;
+        let highlight = hint
+            .highlights
+            .iter()
+            .find(|h| h.coords == rc && h.value == value)?;
+        Some(match highlight.role {
+            Role::Placement => GREEN,
+            Role::Elimination => RED,
+            _ => CYAN,
+        })
+    }
+
+    /// 带颜色的整个画面：盘面、状态、提示信息和按键说明
+    pub fn render(&self) -> String {
+        let field = self.game.field();
+        let conflicts: Vec<RCCoords> = field
+            .find_conflict()
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|(a, b)| [a.coords.into(), b.coords.into()])
+            .collect();
+
+        let per_line = field.rules().candidates_per_line();
+        let grid = field.to_grid_string_with(|rc, m, text| {
+            let cell = field.get_cell_ref_by_rc(rc);
+            let mut style = String::new();
+            if rc == self.cursor {
+                style.push_str(REVERSE);
+            }
+            match cell.status {
+                CellStatus::FIXED => style.push_str(BOLD),
+                CellStatus::SOLVE if conflicts.contains(&rc) => style.push_str(RED),
+                _ => {}
+            }
+            let mistake = self.mistakes.iter().find(|m| m.coords == rc);
+            match mistake.map(|m| m.kind) {
+                Some(MistakeKind::WrongValue) => style.push_str(RED_BACKGROUND),
+                Some(MistakeKind::EliminatedAnswer) => style.push_str(YELLOW),
+                None => {}
+            }
+
+            let mut r = String::new();
+            for (n, ch) in text.chars().enumerate() {
+                r.push_str(RESET);
+                r.push_str(&style);
+                let value = (m * per_line + n) as u8;
+                if cell.status == CellStatus::DRAFT && ch != ' ' {
+                    if let Some(color) = self.candidate_color(rc, value) {
+                        r.push_str(color);
+                    }
+                }
+                r.push(ch);
+            }
+            r.push_str(RESET);
+            r
+        });
+
+        let mode = if self.pencil { "草稿" } else { "填写" };
+        [
+            grid,
+            format!(
+                "{:?}  {:?}  模式：{}  步数：{}",
+                self.cursor,
+                self.difficulty,
+                mode,
+                self.game.history().len()
+            ),
+            self.message.clone(),
+            HELP.to_string(),
+        ]
+        .join("\n")
+    }
+}
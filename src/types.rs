@@ -1,19 +1,19 @@
-use std::{
-    ops::DerefMut,
-    ptr::{addr_of, addr_of_mut},
-};
+use std::sync::Arc;
 
 use rand::{seq::SliceRandom, Rng};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use wasm_bindgen::prelude::wasm_bindgen;
 
-#[derive(Copy, Clone, PartialEq)]
+use crate::rules::{Cage, Rules, MAX_SIZE};
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub struct RCCoords {
     pub r: usize,
     pub c: usize,
 }
 
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub struct GNCoords {
     pub g: usize,
@@ -21,6 +21,7 @@ pub struct GNCoords {
 }
 
 impl RCCoords {
+    /// 9x9盘面中按行优先的下标，其他尺寸使用Rules::rc
     pub fn from_idx(idx: usize) -> RCCoords {
         RCCoords {
             r: idx / 9,
@@ -29,9 +30,10 @@ impl RCCoords {
     }
 }
 
-#[derive(Copy, Clone, PartialEq)]
+/// 兼容上述两种坐标系，反序列化时只读取r/c，g/n由r/c重新计算
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "RCCoords")]
 #[wasm_bindgen]
-/// 兼容上述两种坐标系
 pub struct Coords {
     pub r: usize,
     pub c: usize,
@@ -69,6 +71,7 @@ impl From<GNCoords> for Coords {
     }
 }
 
+// 以下的宫号换算都按标准9x9盘面，其他规则的格子坐标由Rules计算
 impl From<GNCoords> for RCCoords {
     fn from(gn: GNCoords) -> Self {
         let GNCoords { g, n } = gn;
@@ -89,47 +92,132 @@ impl From<RCCoords> for GNCoords {
     }
 }
 
+/// 单元：行、列、宫，以及变体规则额外的单元，单元内的数字不能重复，下标从0开始
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Unit {
+    Row(usize),
+    Column(usize),
+    Grid(usize),
+    // 对角线数独的对角线，0为左上到右下，1为右上到左下
+    Diagonal(usize),
+    // 窗口数独的窗口，按行优先编号，9x9时左上角分别为R2C2、R2C6、R6C2、R6C6
+    Window(usize),
+    // 杀手数独的笼子，下标为Rules::cages中的序号，格子数可以少于边长
+    Cage(usize),
+}
+
+impl Unit {
+    /// 单元内的所有格子坐标，按标准9x9盘面计算，其他规则使用Rules::unit_coords
+    /// 笼子的格子由规则决定，这里返回空
+    pub fn coords(&self) -> Vec<Coords> {
+        if let Unit::Cage(_) = self {
+            return vec![];
+        }
+        (0..9)
+            .map(|i| match *self {
+                Unit::Row(r) => RCCoords { r, c: i }.into(),
+                Unit::Column(c) => RCCoords { r: i, c }.into(),
+                Unit::Grid(g) => GNCoords { g, n: i }.into(),
+                Unit::Diagonal(0) => RCCoords { r: i, c: i }.into(),
+                Unit::Diagonal(_) => RCCoords { r: i, c: 8 - i }.into(),
+                Unit::Window(w) => RCCoords {
+                    r: 1 + w / 2 * 4 + i / 3,
+                    c: 1 + w % 2 * 4 + i % 3,
+                }
+                .into(),
+                Unit::Cage(_) => unreachable!(),
+            })
+            .collect()
+    }
+
+    /// 行号、列号、宫号、对角线序号、窗口序号或笼子序号
+    pub fn index(&self) -> usize {
+        match *self {
+            Unit::Row(i)
+            | Unit::Column(i)
+            | Unit::Grid(i)
+            | Unit::Diagonal(i)
+            | Unit::Window(i)
+            | Unit::Cage(i) => i,
+        }
+    }
+
+    /// 格子所在的行、列、宫，变体规则额外的单元见Rules::units_of
+    pub fn units_of(coords: Coords) -> [Unit; 3] {
+        [
+            Unit::Row(coords.r),
+            Unit::Column(coords.c),
+            Unit::Grid(coords.g),
+        ]
+    }
+}
+
+impl std::fmt::Debug for Unit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Row(r) => write!(f, "R{}", r + 1),
+            Unit::Column(c) => write!(f, "C{}", c + 1),
+            Unit::Grid(g) => write!(f, "G{}", g + 1),
+            Unit::Diagonal(d) => write!(f, "D{}", d + 1),
+            Unit::Window(w) => write!(f, "W{}", w + 1),
+            Unit::Cage(k) => write!(f, "K{}", k + 1),
+        }
+    }
+}
+
+/// 数值的显示字符：1-9，之后为A-P，用于超过9的盘面
+pub fn value_to_char(v: u8) -> char {
+    match v {
+        0..=8 => char::from(b'1' + v),
+        _ => char::from(b'A' + v - 9),
+    }
+}
+
+/// value_to_char的逆运算，字母不区分大小写
+pub fn char_to_value(ch: char) -> Option<u8> {
+    match ch.to_ascii_uppercase() {
+        ch @ '1'..='9' => Some(ch as u8 - b'1'),
+        ch @ 'A'..='P' => Some(ch as u8 - b'A' + 9),
+        _ => None,
+    }
+}
+
 #[derive(Default, Copy, Clone, PartialEq, Eq, Hash)]
 #[wasm_bindgen]
-pub struct Candidate(u16);
+pub struct Candidate(u32);
 
 impl Candidate {
     pub const FULL: Candidate = Candidate(0x1FF);
 
-    pub fn get_unique_candidate(&self) -> Option<u8> {
-        const fn bit_to_num() -> [u16; 9] {
-            let mut result = [0; 9];
-            let mut i = 0;
-            while i < 9 {
-                result[i] = 0x001 << i;
-                i += 1;
-            }
-            result
-        }
-        const BIT_TO_NUM: [u16; 9] = bit_to_num();
+    /// 边长为size的盘面中的全部候选数
+    pub fn full(size: usize) -> Candidate {
+        Candidate((1 << size) - 1)
+    }
 
-        BIT_TO_NUM
-            .iter()
-            .position(|p| *p == self.0)
-            .map(|r| r as u8)
+    pub fn get_unique_candidate(&self) -> Option<u8> {
+        (self.0.count_ones() == 1).then(|| self.0.trailing_zeros() as u8)
     }
 
     pub fn add(&mut self, v: u8) {
-        self.0 &= 0x001 << v as u16;
+        self.0 |= 0x001 << v as u32;
     }
 
     pub fn remove(&mut self, v: u8) {
-        self.0 &= !(0x001 << v as u16);
+        self.0 &= !(0x001 << v as u32);
     }
 
     pub fn contains(&self, v: u8) -> bool {
-        self.0 & (0x001 << v as u16) != 0
+        self.0 & (0x001 << v as u32) != 0
     }
 
     pub fn len(&self) -> u32 {
         self.0.count_ones()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
     pub fn union(&self, other: &Candidate) -> Candidate {
         Candidate(self.0 | other.0)
     }
@@ -144,7 +232,7 @@ impl Candidate {
 
     pub fn to_vec(&self) -> Vec<u8> {
         let mut values = Vec::new();
-        for i in 0..9 {
+        for i in 0..MAX_SIZE as u8 {
             if self.0 & (0x001 << i) != 0 {
                 values.push(i);
             }
@@ -153,7 +241,43 @@ impl Candidate {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+// 可读格式（如JSON）下序列化为候选数数组（与to_vec一致，从0开始），紧凑格式下直接使用位掩码
+impl Serialize for Candidate {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            self.to_vec().serialize(serializer)
+        } else {
+            self.0.serialize(serializer)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Candidate {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mask = if deserializer.is_human_readable() {
+            Vec::<u8>::deserialize(deserializer)?
+                .into_iter()
+                .try_fold(0u32, |mask, v| {
+                    if (v as usize) < MAX_SIZE {
+                        Ok(mask | (0x001 << v))
+                    } else {
+                        Err(de::Error::custom(format!("Invalid Candidate Value: {}", v)))
+                    }
+                })?
+        } else {
+            u32::deserialize(deserializer)?
+        };
+        if mask & !Candidate::full(MAX_SIZE).0 != 0 {
+            return Err(de::Error::custom(format!(
+                "Invalid Candidate Mask: {:#x}",
+                mask
+            )));
+        }
+        Ok(Candidate(mask))
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub enum CellStatus {
     // 固定数值
@@ -164,22 +288,23 @@ pub enum CellStatus {
     SOLVE,
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub struct Cell {
     pub coords: Coords,
     pub status: CellStatus,
     pub candidates: Candidate,
     pub value: Option<u8>, // None 0-9
-                           // pub answer: u8, // The Answer Of This Cell
+    // pub answer: u8, // The Answer Of This Cell
+    // 用户手动排除的草稿，清除填写值重新计算草稿时不会恢复
+    #[serde(default)]
+    pub eliminated: Candidate,
 }
 
 impl std::fmt::Debug for Candidate {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for i in 0..9 {
-            if self.0 & (0x001 << i) != 0 {
-                write!(f, "{}", i + 1)?;
-            }
+        for v in self.to_vec() {
+            write!(f, "{}", value_to_char(v))?;
         }
         write!(f, "")
     }
@@ -226,90 +351,157 @@ impl std::fmt::Debug for Cell {
 #[derive(Clone)]
 #[wasm_bindgen]
 pub struct Sudoku {
-    cells: [Cell; 81],
+    rules: Arc<Rules>,
+    cells: Vec<Cell>,
 }
 
 impl Sudoku {
+    /// 盘面的规则，决定尺寸、宫的形状和相关格
+    pub fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    /// 盘面边长，标准数独为9
+    pub fn size(&self) -> usize {
+        self.rules.size()
+    }
+
+    /// 按行优先的顺序遍历所有格子的坐标
+    pub fn iter_rc(&self) -> impl Iterator<Item = RCCoords> {
+        let size = self.size();
+        (0..size * size).map(move |idx| RCCoords {
+            r: idx / size,
+            c: idx % size,
+        })
+    }
+
+    /// 空白盘面，所有格子都是包含全部候选数的草稿
+    pub fn blank(rules: Rules) -> Sudoku {
+        let candidates = Candidate::full(rules.size());
+        let cells = (0..rules.cell_count())
+            .map(|idx| Cell {
+                coords: rules.coords(rules.rc(idx)),
+                status: CellStatus::DRAFT,
+                candidates,
+                value: None,
+                eliminated: Candidate::default(),
+            })
+            .collect();
+        Sudoku {
+            rules: Arc::new(rules),
+            cells,
+        }
+    }
+
+    // 由给定数构造题目，None为空格
+    fn from_givens(rules: Rules, givens: &[Option<u8>]) -> Sudoku {
+        let mut field = Sudoku::blank(rules);
+        for (cell, &given) in field.cells.iter_mut().zip(givens) {
+            if let Some(v) = given {
+                cell.status = CellStatus::FIXED;
+                cell.value = Some(v);
+            }
+        }
+        field.fill_drafts();
+        field
+    }
+
     pub fn get_cell_mut_by_rc(&mut self, rc: RCCoords) -> &mut Cell {
-        &mut self.cells[rc.r * 9 + rc.c]
+        let idx = self.rules.index(rc);
+        &mut self.cells[idx]
     }
 
     pub fn get_cell_ref_by_rc(&self, rc: RCCoords) -> &Cell {
-        &self.cells[rc.r * 9 + rc.c]
+        &self.cells[self.rules.index(rc)]
     }
 
     pub fn get_cell_mut_ptr_by_rc(&mut self, rc: RCCoords) -> *mut Cell {
-        &mut self.cells[rc.r * 9 + rc.c]
+        self.get_cell_mut_by_rc(rc)
     }
 
     pub fn get_cell_mut_by_gn(&mut self, gn: GNCoords) -> &mut Cell {
-        let RCCoords { r, c } = gn.into();
-        &mut self.cells[r * 9 + c]
+        let rc = self.rules.gn_to_rc(gn);
+        self.get_cell_mut_by_rc(rc)
     }
 
     pub fn get_cell_ref_by_gn(&self, gn: GNCoords) -> &Cell {
-        let RCCoords { r, c } = gn.into();
-        &self.cells[r * 9 + c]
+        self.get_cell_ref_by_rc(self.rules.gn_to_rc(gn))
     }
 
     pub fn get_cell_mut_ptr_by_gn(&mut self, gn: GNCoords) -> *mut Cell {
-        let RCCoords { r, c } = gn.into();
-        &mut self.cells[r * 9 + c]
+        self.get_cell_mut_by_gn(gn)
     }
 
     pub fn get_cell_mut_by_coords(&mut self, coords: Coords) -> &mut Cell {
         let Coords { r, c, g: _, n: _ } = coords;
-        &mut self.cells[r * 9 + c]
+        self.get_cell_mut_by_rc(RCCoords { r, c })
     }
 
     pub fn get_cell_ref_by_coords(&self, coords: Coords) -> &Cell {
         let Coords { r, c, g: _, n: _ } = coords;
-        &self.cells[r * 9 + c]
+        self.get_cell_ref_by_rc(RCCoords { r, c })
     }
 
     pub fn get_cell_mut_ptr_by_coords(&mut self, coords: Coords) -> *mut Cell {
-        let Coords { r, c, g: _, n: _ } = coords;
-        &mut self.cells[r * 9 + c]
+        self.get_cell_mut_by_coords(coords)
+    }
+
+    /// 包含宫号和宫内序号的完整坐标，宫的形状由规则决定
+    pub fn coords_of(&self, rc: RCCoords) -> Coords {
+        self.rules.coords(rc)
     }
 
     // 如果格子的内容有冲突，也说明有错误，可以不继续推理下去了
+    // 杀手数独的笼子内、无马无王数独相隔马步王步的格子同样不能有重复的数字
     pub fn find_conflict(&self) -> Option<Vec<(&Cell, &Cell)>> {
         let mut ret: Vec<(&Cell, &Cell)> = vec![];
-        for r in 0..9 {
-            for c in 0..9 {
-                let rc = RCCoords { r, c };
-                let p_cell = self.get_cell_ref_by_rc(rc);
-                if p_cell.status == CellStatus::FIXED || p_cell.status == CellStatus::SOLVE {
-                    let v = p_cell.value;
-                    let g = p_cell.coords.g;
-                    let n = p_cell.coords.n;
-                    for r_iter in (r + 1)..9 {
-                        let tmp = self.get_cell_ref_by_rc(RCCoords { r: r_iter, c });
-                        if (tmp.value == v)
-                            && (tmp.status == CellStatus::FIXED || tmp.status == CellStatus::SOLVE)
-                        {
-                            ret.push((p_cell, tmp));
-                        }
-                    }
-                    for c_iter in (c + 1)..9 {
-                        let tmp = self.get_cell_ref_by_rc(RCCoords { r, c: c_iter });
-                        if (tmp.value == v)
-                            && (tmp.status == CellStatus::FIXED || tmp.status == CellStatus::SOLVE)
-                        {
-                            ret.push((p_cell, tmp));
-                        }
-                    }
-                    for n_iter in (n + 1)..9 {
-                        let tmp = self.get_cell_ref_by_gn(GNCoords { g, n: n_iter });
-                        if (tmp.value == v)
-                            && (tmp.status == CellStatus::FIXED || tmp.status == CellStatus::SOLVE)
-                        {
-                            ret.push((p_cell, tmp));
-                        }
+        let cages = self.rules.cages().iter().map(|cage| &cage.cells);
+        for unit in self.rules.unit_cells().iter().chain(cages) {
+            let cells: Vec<&Cell> = unit
+                .iter()
+                .map(|&idx| &self.cells[idx])
+                .filter(|p| p.status == CellStatus::FIXED || p.status == CellStatus::SOLVE)
+                .collect();
+            for (i, &p_cell) in cells.iter().enumerate() {
+                for &tmp in &cells[i + 1..] {
+                    if tmp.value == p_cell.value {
+                        ret.push((p_cell, tmp));
                     }
                 }
             }
         }
+        let placed = |p: &Cell| p.status == CellStatus::FIXED || p.status == CellStatus::SOLVE;
+        for (idx, p_cell) in self.cells.iter().enumerate().filter(|(_, p)| placed(p)) {
+            for p in self
+                .rules
+                .chess_neighbors(idx)
+                .into_iter()
+                .filter(|&p| p > idx)
+            {
+                let tmp = &self.cells[p];
+                if placed(tmp) && tmp.value == p_cell.value {
+                    ret.push((p_cell, tmp));
+                }
+            }
+        }
+        // 相邻格之间的关系（非连续、克罗普基圆点）
+        for &(a, b, relation) in self.rules.relations() {
+            let (a, b) = (&self.cells[a], &self.cells[b]);
+            if let (true, true, Some(u), Some(v)) = (placed(a), placed(b), a.value, b.value) {
+                if !relation.allows(u, v) {
+                    ret.push((a, b));
+                }
+            }
+        }
+        // 温度计、箭头等线条约束
+        let value = |idx: usize| Some(&self.cells[idx]).filter(|p| placed(p))?.value;
+        for (a, b) in self.rules.line_conflicts(value) {
+            ret.push((&self.cells[a], &self.cells[b]));
+        }
+        // 三明治提示
+        for (a, b) in self.rules.sandwich_conflicts(value) {
+            ret.push((&self.cells[a], &self.cells[b]));
+        }
         if !ret.is_empty() {
             Some(ret)
         } else {
@@ -318,38 +510,19 @@ impl Sudoku {
     }
 
     // 仅在初始化时使用，补充所有可能的草稿数
-    fn fill_drafts(&mut self) {
-        for r in 0..9 {
-            for c in 0..9 {
-                let cell = self.get_cell_mut_by_rc(RCCoords { r, c });
-                if cell.status == CellStatus::DRAFT {
-                    cell.candidates = Candidate::FULL;
-                }
+    pub(crate) fn fill_drafts(&mut self) {
+        let full = Candidate::full(self.size());
+        for cell in self.cells.iter_mut() {
+            if cell.status == CellStatus::DRAFT {
+                cell.candidates = full;
             }
         }
-        for r in 0..9 {
-            for c in 0..9 {
-                let rc = RCCoords { r, c };
-                let &Cell {
-                    status,
-                    value,
-                    coords,
-                    ..
-                } = self.get_cell_ref_by_rc(rc);
-                if status == CellStatus::FIXED {
-                    let g = coords.g;
-                    let value = value.unwrap();
-                    for i in 0..9 {
-                        self.get_cell_mut_by_rc(RCCoords { r: i, c })
-                            .candidates
-                            .remove(value);
-                        self.get_cell_mut_by_rc(RCCoords { r, c: i })
-                            .candidates
-                            .remove(value);
-                        self.get_cell_mut_by_gn(GNCoords { g, n: i })
-                            .candidates
-                            .remove(value);
-                    }
+        for idx in 0..self.cells.len() {
+            let Cell { status, value, .. } = self.cells[idx];
+            if status == CellStatus::FIXED {
+                let value = value.unwrap();
+                for &p in self.rules.peers(idx) {
+                    self.cells[p].candidates.remove(value);
                 }
             }
         }
@@ -357,64 +530,121 @@ impl Sudoku {
 
     // 打印数独，用特殊效果显示草稿、固定值、填写值
     pub fn print(&self) {
-        const TOP_BORDER: &str = "╔═══╤═══╤═══╦═══╤═══╤═══╦═══╤═══╤═══╗";
-        const BOTTOM_BORDER: &str = "╚═══╧═══╧═══╩═══╧═══╧═══╩═══╧═══╧═══╝";
-        const MIDDLE_BORDER: &str = "╠═══╪═══╪═══╬═══╪═══╪═══╬═══╪═══╪═══╣";
-        const SUB_BORDER: &str = "╟───┼───┼───╫───┼───┼───╫───┼───┼───╢";
+        println!("{}", self.to_grid_string());
+    }
+
+    /// 用制表符绘制的盘面，与print的输出相同
+    pub fn to_grid_string(&self) -> String {
+        self.to_grid_string_with(|_, _, text| text)
+    }
+
+    /// 与to_grid_string相同，decorate可以给每个格子加上样式（如终端颜色）
+    /// 9x9时每个格子占3行，每行3个字符，其他尺寸的行数和字符数见Rules::candidates_per_line
+    /// decorate的参数为格子坐标、格内行号和该行的原始文字
+    pub fn to_grid_string_with<F>(&self, decorate: F) -> String
+    where
+        F: Fn(RCCoords, usize, String) -> String,
+    {
+        let size = self.size();
+        let k = self.rules.candidates_per_line();
+        let rc = |r: usize, c: usize| RCCoords { r, c };
+        let thick_right = |r: usize, c: usize| self.rules.thick_right(rc(r, c));
+        let thick_below = |r: usize, c: usize| self.rules.thick_below(rc(r, c));
+
+        // 上下边框由左端、格子上的线、宫内的交叉、宫之间的交叉和右端组成，r为相邻的行
+        let border = |r: usize, left: &str, thin: &str, thick: &str, right: &str| {
+            let mut line = left.to_string();
+            for c in 0..size {
+                line += &"═".repeat(k);
+                line += if c == size - 1 {
+                    right
+                } else if thick_right(r, c) {
+                    thick
+                } else {
+                    thin
+                };
+            }
+            line
+        };
 
-        fn format_draft(p: &Cell, m: usize) -> String {
-            (0..3)
+        // 第r行与第r+1行之间的分隔线，宫的边界用双线，不规则区域的交叉处取两侧较粗的线
+        let separator = |r: usize| {
+            let mut line = if thick_below(r, 0) { "╠" } else { "╟" }.to_string();
+            for c in 0..size {
+                let horizontal = thick_below(r, c);
+                line += &if horizontal { "═" } else { "─" }.repeat(k);
+                if c == size - 1 {
+                    line += if horizontal { "╣" } else { "╢" };
+                    continue;
+                }
+                let vertical = thick_right(r, c) || thick_right(r + 1, c);
+                let horizontal = horizontal || thick_below(r, c + 1);
+                line += match (vertical, horizontal) {
+                    (false, false) => "┼",
+                    (true, false) => "╫",
+                    (false, true) => "╪",
+                    (true, true) => "╬",
+                };
+            }
+            line
+        };
+
+        let format_draft = |p: &Cell, m: usize| -> String {
+            (0..k)
                 .map(|n| {
-                    if p.candidates.0 & (0x001 << m * 3 + n) != 0 {
-                        (m * 3 + n + 1).to_string()
+                    let v = (m * k + n) as u8;
+                    if p.candidates.contains(v) {
+                        value_to_char(v)
                     } else {
-                        " ".to_string()
+                        ' '
                     }
                 })
                 .collect()
-        }
-
-        fn format_fixed(p: &Cell, m: usize) -> String {
-            match m {
-                0 => "\\ /".to_string(),
-                1 => format!(" {} ", p.value.unwrap() + 1),
-                _ => "/ \\".to_string(),
-            }
-        }
+        };
 
-        fn format_solve(p: &Cell, m: usize) -> String {
-            match m {
-                0 => "***".to_string(),
-                1 => format!("*{}*", p.value.unwrap() + 1),
-                _ => "***".to_string(),
+        // 数值显示在中间一行，其余行为装饰
+        let format_value = |p: &Cell, m: usize, fill: char| -> String {
+            let left = (k - 1) / 2;
+            let right = k - 1 - left;
+            if m == k / 2 {
+                let mut text = fill.to_string().repeat(left);
+                text.push(value_to_char(p.value.unwrap()));
+                text + &fill.to_string().repeat(right)
+            } else if fill != ' ' {
+                fill.to_string().repeat(k)
+            } else if m < k / 2 {
+                format!("\\{}/", " ".repeat(k - 2))
+            } else {
+                format!("/{}\\", " ".repeat(k - 2))
             }
-        }
+        };
 
-        println!("{}", TOP_BORDER);
+        let mut lines = vec![border(0, "╔", "╤", "╦", "╗")];
 
-        for r in 0..9 {
-            for m in 0..3 {
+        for r in 0..size {
+            for m in 0..k {
                 let mut line = String::from("║");
-                for c in 0..9 {
-                    let p = &self.cells[r * 9 + c];
-                    line += &match p.status {
+                for c in 0..size {
+                    let p = self.get_cell_ref_by_rc(RCCoords { r, c });
+                    let text = match p.status {
                         CellStatus::DRAFT => format_draft(p, m),
-                        CellStatus::FIXED => format_fixed(p, m),
-                        CellStatus::SOLVE => format_solve(p, m),
+                        CellStatus::FIXED => format_value(p, m, ' '),
+                        CellStatus::SOLVE => format_value(p, m, '*'),
                     };
-                    line += if c % 3 == 2 { "║" } else { "│" };
+                    line += &decorate(RCCoords { r, c }, m, text);
+                    line += if thick_right(r, c) { "║" } else { "│" };
                 }
-                println!("{}", line);
+                lines.push(line);
             }
 
-            if r == 8 {
-                println!("{}", BOTTOM_BORDER);
-            } else if r % 3 == 2 {
-                println!("{}", MIDDLE_BORDER);
+            if r == size - 1 {
+                lines.push(border(r, "╚", "╧", "╩", "╝"));
             } else {
-                println!("{}", SUB_BORDER);
+                lines.push(separator(r));
             }
         }
+
+        lines.join("\n")
     }
 
     // TODO: Backtrace Solve
@@ -428,53 +658,101 @@ impl Sudoku {
     // fn backtrack_solve(&mut self, count: &mut u32) -> bool {
     //     // 实现带计数器的回溯算法...
     // }
+    // 回溯求解，返回true表示已经找到两个解，不需要继续搜索
+    // 每次优先填写隐性唯一的数值，否则选择可填数值最少的草稿格，16x16以上的盘面逐格尝试会非常慢
     fn self_solve_field(field: &mut Sudoku, solutions: &mut Vec<Sudoku>) -> bool {
-        fn is_valid(r: usize, c: usize, v: Option<u8>, field: &Sudoku) -> bool {
-            let GNCoords { g, n: _ } = RCCoords { r, c }.into();
-            for i in 0..9 {
-                if field.get_cell_ref_by_rc(RCCoords { r, c: i }).value == v {
-                    return false;
+        // 每个草稿格当前可以填写的数值，如果某个格子没有有效的数字，则返回false
+        let Some(domains) = field.allowed_values(|cell| cell.candidates) else {
+            return false;
+        };
+        let mut allowed: Vec<Option<Candidate>> = vec![None; field.cells.len()];
+        let mut best: Option<(usize, Candidate)> = None;
+        for (idx, cell) in field.cells.iter().enumerate() {
+            if cell.value.is_some() {
+                continue;
+            }
+            let candidates = domains[idx];
+            if best.is_none_or(|(_, b)| candidates.len() < b.len()) {
+                best = Some((idx, candidates));
+            }
+            allowed[idx] = Some(candidates);
+        }
+
+        // 所有单元格都已解决，记录这个解，找到两个解后返回true停止搜索
+        let Some((mut idx, mut candidates)) = best else {
+            solutions.push(field.clone());
+            return solutions.len() >= 2;
+        };
+
+        // 单元内某个数值只剩一个位置时直接填写，没有位置时说明无解
+        if candidates.len() > 1 {
+            let full = Candidate::full(field.size());
+            for unit in field.rules.unit_cells() {
+                let mut placed = Candidate::default();
+                let mut once = Candidate::default();
+                let mut twice = Candidate::default();
+                for &p in unit {
+                    match (field.cells[p].value, allowed[p]) {
+                        (Some(v), _) => placed.add(v),
+                        (None, Some(a)) => {
+                            twice = twice.union(&once.intersect(&a));
+                            once = once.union(&a);
+                        }
+                        (None, None) => {}
+                    }
                 }
-                if field.get_cell_ref_by_rc(RCCoords { r: i, c }).value == v {
+                if !full.subtract(&placed).subtract(&once).is_empty() {
                     return false;
                 }
-                if field.get_cell_ref_by_gn(GNCoords { g, n: i }).value == v {
-                    return false;
+                if let Some(&v) = once.subtract(&twice).to_vec().first() {
+                    idx = *unit
+                        .iter()
+                        .find(|&&p| allowed[p].is_some_and(|a| a.contains(v)))
+                        .unwrap();
+                    candidates = Candidate::default();
+                    candidates.add(v);
+                    break;
                 }
             }
-            true
-        }
-        unsafe {
-            // 找到第一个草稿状态的单元格
-            for r in 0..9 {
-                for c in 0..9 {
-                    let idx = r * 9 + c;
-                    let cell: *mut Cell = core::ptr::addr_of_mut!(field.cells[idx]);
-                    if (*cell).status == CellStatus::DRAFT {
-                        for &num in &(*cell).candidates.to_vec() {
-                            if is_valid(r, c, Some(num), &field) {
-                                (*cell).value = Some(num);
-                                (*cell).status = CellStatus::SOLVE;
-
-                                if Self::self_solve_field(field, solutions) {
-                                    solutions.push(field.clone());
-                                    if solutions.len() >= 2 {
-                                        return true;
-                                    }
-                                }
-
-                                // 回溯
-                                (*cell).status = CellStatus::DRAFT;
-                                (*cell).value = None; // 重置值
-                            }
-                        }
-                        return false; // 如果没有找到有效的数字，则返回false
-                    }
-                }
+        }
+
+        for num in candidates.to_vec() {
+            field.cells[idx].value = Some(num);
+            field.cells[idx].status = CellStatus::SOLVE;
+            if Self::self_solve_field(field, solutions) {
+                return true;
             }
         }
 
-        true // 如果所有单元格都已解决，则返回true
+        // 回溯
+        field.cells[idx].status = CellStatus::DRAFT;
+        field.cells[idx].value = None; // 重置值
+        false
+    }
+
+    // 每个格子当前可以填写的数值：已填的格子只有所填的数值，未填的格子从unplaced开始，
+    // 去掉相关格已填的数值，再按笼子等额外约束缩小，有格子无值可填时返回None
+    pub(crate) fn allowed_values(
+        &self,
+        unplaced: impl Fn(&Cell) -> Candidate,
+    ) -> Option<Vec<Candidate>> {
+        let mut domains = self.cell_domains();
+        for (idx, domain) in domains.iter_mut().enumerate() {
+            let cell = &self.cells[idx];
+            if cell.value.is_some() {
+                continue;
+            }
+            *domain = unplaced(cell);
+            for &p in self.rules.peers(idx) {
+                if let Some(v) = self.cells[p].value {
+                    domain.remove(v);
+                }
+            }
+        }
+        if !self.rules.prune(&mut domains) || domains.iter().any(|d| d.is_empty()) {
+            return None;
+        }
+        Some(domains)
     }
 
     pub fn sovle(&self) -> Vec<Sudoku> {
@@ -498,13 +776,11 @@ impl Sudoku {
 
     /// 按行遍历草稿单元格
     pub fn iter_all_drafts_cells_by_rc(&self) -> <Vec<Vec<&Cell>> as IntoIterator>::IntoIter {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|r| {
-                (0..9)
-                    .into_iter()
+                (0..self.size())
                     .map(|c| self.get_cell_ref_by_rc(RCCoords { r, c }))
-                    .filter(|&p| (*p).status == CellStatus::DRAFT)
+                    .filter(|&p| p.status == CellStatus::DRAFT)
                     .collect()
             })
             .collect::<Vec<Vec<&Cell>>>()
@@ -513,13 +789,11 @@ impl Sudoku {
 
     /// 按列遍历草稿单元格
     pub fn iter_all_drafts_cells_by_cr(&self) -> <Vec<Vec<&Cell>> as IntoIterator>::IntoIter {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|c| {
-                (0..9)
-                    .into_iter()
+                (0..self.size())
                     .map(|r| self.get_cell_ref_by_rc(RCCoords { r, c }))
-                    .filter(|&p| (*p).status == CellStatus::DRAFT)
+                    .filter(|&p| p.status == CellStatus::DRAFT)
                     .collect()
             })
             .collect::<Vec<Vec<&Cell>>>()
@@ -528,13 +802,11 @@ impl Sudoku {
 
     /// 按宫遍历草稿单元格
     pub fn iter_all_drafts_cells_by_gn(&self) -> <Vec<Vec<&Cell>> as IntoIterator>::IntoIter {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|g| {
-                (0..9)
-                    .into_iter()
+                (0..self.size())
                     .map(|n| self.get_cell_ref_by_gn(GNCoords { g, n }))
-                    .filter(|&p| (*p).status == CellStatus::DRAFT)
+                    .filter(|&p| p.status == CellStatus::DRAFT)
                     .collect()
             })
             .collect::<Vec<Vec<&Cell>>>()
@@ -543,28 +815,51 @@ impl Sudoku {
 
     /// 在指定行按列遍历单元格
     pub fn collect_all_drafts_cells_in_r(&self, r: usize) -> Vec<&Cell> {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|c| self.get_cell_ref_by_rc(RCCoords { r, c }))
-            .filter(|&p| (*p).status == CellStatus::DRAFT)
+            .filter(|&p| p.status == CellStatus::DRAFT)
             .collect()
     }
 
     /// 在指定列按行遍历单元格
     pub fn collect_all_drafts_cells_in_c(&self, c: usize) -> Vec<&Cell> {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|r| self.get_cell_ref_by_rc(RCCoords { r, c }))
-            .filter(|&p| (*p).status == CellStatus::DRAFT)
+            .filter(|&p| p.status == CellStatus::DRAFT)
+            .collect()
+    }
+
+    /// 在指定单元按序遍历单元格，包括变体规则额外的单元
+    pub fn collect_all_drafts_cells_in_unit(&self, unit: Unit) -> Vec<&Cell> {
+        self.rules
+            .unit_indexes(unit)
+            .into_iter()
+            .map(|idx| &self.cells[idx])
+            .filter(|p| p.status == CellStatus::DRAFT)
+            .collect()
+    }
+
+    /// 按行优先的顺序得到每个格子当前的可能值：已填的格子只有所填的数值，草稿格为草稿，
+    /// 笼子等额外约束的推理都基于它
+    pub fn cell_domains(&self) -> Vec<Candidate> {
+        self.cells
+            .iter()
+            .map(|cell| match cell.value {
+                Some(v) => {
+                    let mut single = Candidate::default();
+                    single.add(v);
+                    single
+                }
+                None => cell.candidates,
+            })
             .collect()
     }
 
     /// 在指定宫按序遍历单元格
     pub fn collect_all_drafts_cells_in_g(&self, g: usize) -> Vec<&Cell> {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|n| self.get_cell_ref_by_gn(GNCoords { g, n }))
-            .filter(|&p| (*p).status == CellStatus::DRAFT)
+            .filter(|&p| p.status == CellStatus::DRAFT)
             .collect()
     }
 
@@ -573,62 +868,169 @@ impl Sudoku {
         &self,
         v: u8,
     ) -> <Vec<Vec<&Cell>> as IntoIterator>::IntoIter {
-        (0..9)
-            .into_iter()
+        (0..self.size())
             .map(|r| {
-                (0..9)
-                    .into_iter()
+                (0..self.size())
                     .map(|c| self.get_cell_ref_by_rc(RCCoords { r, c }))
-                    .filter(|&p| (*p).status == CellStatus::DRAFT && (*p).candidates.contains(v))
+                    .filter(|&p| p.status == CellStatus::DRAFT && p.candidates.contains(v))
                     .collect()
             })
             .collect::<Vec<Vec<&Cell>>>()
             .into_iter()
     }
 
-    /// 给定一个坐标和值，根据坐标遍历同一行、同一列、同一宫所有含有这个值的单元格
+    /// 给定一个坐标和值，根据坐标遍历所有相关格（同一行、同一列、同一宫及变体规则的相关格）中含有这个值的草稿格，不含自身
     pub fn collect_all_drafts_coords_by_coords_and_value(
         &self,
         coords: Coords,
         value: u8,
     ) -> Vec<Coords> {
-        let Coords { r, c, g, n: _ } = coords;
+        let idx = self.rules.index(RCCoords {
+            r: coords.r,
+            c: coords.c,
+        });
+        self.rules
+            .peers(idx)
+            .iter()
+            .map(|&p| &self.cells[p])
+            .filter(|p| p.status == CellStatus::DRAFT && p.candidates.contains(value))
+            .map(|p| p.coords)
+            .collect()
+    }
+
+    /// 检查是否都填写完毕了
+    pub fn check_if_finish(&self) -> bool {
+        self.collect_all_drafts_cells().is_empty()
+    }
 
-        let mut coords = vec![];
+    /// 给定一个坐标，返回同一行、同一列、同一宫的所有其他格子坐标（不含自身）
+    pub fn collect_peers_coords(&self, coords: Coords) -> Vec<Coords> {
+        let idx = self.rules.index(RCCoords {
+            r: coords.r,
+            c: coords.c,
+        });
+        self.rules
+            .peers(idx)
+            .iter()
+            .map(|&p| self.cells[p].coords)
+            .collect()
+    }
 
-        for i in 0..9 {
-            let p_cell = self.get_cell_ref_by_rc(RCCoords { r: i, c });
-            if p_cell.status == CellStatus::DRAFT && p_cell.candidates.contains(value) {
-                coords.push(p_cell.coords);
+    // 同行列宫中是否已经有格子填写（或固定）了这个值
+    fn peers_contain_value(&self, coords: Coords, value: u8) -> bool {
+        self.collect_peers_coords(coords).into_iter().any(|p| {
+            let cell = self.get_cell_ref_by_coords(p);
+            cell.status != CellStatus::DRAFT && cell.value == Some(value)
+        })
+    }
+
+    fn check_editable(&self, rc: RCCoords) -> Result<(), String> {
+        if rc.r >= self.size() || rc.c >= self.size() {
+            return Err(format!("Invalid Coords: {:?}", rc));
+        }
+        if self.get_cell_ref_by_rc(rc).status == CellStatus::FIXED {
+            return Err("Cannot Modify A Fixed Cell.".to_string());
+        }
+        Ok(())
+    }
+
+    /// 在格子中填写数值，同时移除同行列宫其他格子的该草稿数
+    /// 如果该格已经填写了其他数值，会先清除原数值
+    pub fn place(&mut self, rc: RCCoords, value: u8) -> Result<(), String> {
+        self.check_editable(rc)?;
+        if value as usize >= self.size() {
+            return Err(format!("Invalid Value: {}", value));
+        }
+        if self.get_cell_ref_by_rc(rc).status == CellStatus::SOLVE {
+            self.unplace(rc, false)?;
+        }
+
+        let cell = self.get_cell_mut_by_rc(rc);
+        cell.status = CellStatus::SOLVE;
+        cell.value = Some(value);
+        let coords = cell.coords;
+        for p in self.collect_peers_coords(coords) {
+            let peer = self.get_cell_mut_by_coords(p);
+            if peer.status == CellStatus::DRAFT {
+                peer.candidates.remove(value);
             }
+        }
+        Ok(())
+    }
 
-            let p_cell = self.get_cell_ref_by_rc(RCCoords { r, c: i });
-            if p_cell.coords.c != c
-                && p_cell.status == CellStatus::DRAFT
-                && p_cell.candidates.contains(value)
-            {
-                coords.push(p_cell.coords);
+    /// 清除格子的填写值，重新计算该格及同行列宫其他格子的草稿数
+    /// 用户手动排除过的草稿不会恢复，除非restore_eliminated为true，此时同时清除排除记录
+    pub fn unplace(&mut self, rc: RCCoords, restore_eliminated: bool) -> Result<(), String> {
+        self.check_editable(rc)?;
+        let cell = *self.get_cell_ref_by_rc(rc);
+        let Some(value) = cell.value else {
+            return Ok(());
+        };
+
+        let coords = cell.coords;
+        {
+            let cell = self.get_cell_mut_by_rc(rc);
+            cell.status = CellStatus::DRAFT;
+            cell.value = None;
+        }
+
+        // 该格自身：所有同行列宫未出现的数值
+        let mut candidates = Candidate::full(self.size());
+        for p in self.collect_peers_coords(coords) {
+            let peer = self.get_cell_ref_by_coords(p);
+            if let (CellStatus::FIXED | CellStatus::SOLVE, Some(v)) = (peer.status, peer.value) {
+                candidates.remove(v);
             }
+        }
+        let cell = self.get_cell_mut_by_rc(rc);
+        if restore_eliminated {
+            cell.eliminated = Candidate::default();
+        }
+        cell.candidates = candidates.subtract(&cell.eliminated);
 
-            let p_cell = self.get_cell_ref_by_gn(GNCoords { g, n: i });
-            if p_cell.coords.r != r
-                && p_cell.coords.c != c
-                && p_cell.status == CellStatus::DRAFT
-                && p_cell.candidates.contains(value)
-            {
-                coords.push(p_cell.coords);
+        // 同行列宫的其他格子：只有在自己的同行列宫都没有该值时才恢复
+        for p in self.collect_peers_coords(coords) {
+            let peer = *self.get_cell_ref_by_coords(p);
+            if peer.status != CellStatus::DRAFT || self.peers_contain_value(p, value) {
+                continue;
+            }
+            let peer = self.get_cell_mut_by_coords(p);
+            if restore_eliminated {
+                peer.eliminated.remove(value);
+            }
+            if !peer.eliminated.contains(value) {
+                peer.candidates.add(value);
             }
         }
+        Ok(())
+    }
 
-        coords
+    /// 用户手动排除一个草稿数，并记录下来
+    pub fn eliminate_candidate(&mut self, rc: RCCoords, value: u8) -> Result<(), String> {
+        self.check_editable(rc)?;
+        if value as usize >= self.size() {
+            return Err(format!("Invalid Value: {}", value));
+        }
+        let cell = self.get_cell_mut_by_rc(rc);
+        cell.candidates.remove(value);
+        cell.eliminated.add(value);
+        Ok(())
     }
 
-    /// 检查是否都填写完毕了
-    pub fn check_if_finish(&self) -> bool {
-        self.collect_all_drafts_cells().is_empty()
+    /// 用户手动恢复一个草稿数，同时清除排除记录
+    pub fn restore_candidate(&mut self, rc: RCCoords, value: u8) -> Result<(), String> {
+        self.check_editable(rc)?;
+        if value as usize >= self.size() {
+            return Err(format!("Invalid Value: {}", value));
+        }
+        let cell = self.get_cell_mut_by_rc(rc);
+        cell.candidates.add(value);
+        cell.eliminated.remove(value);
+        Ok(())
     }
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 #[wasm_bindgen]
 pub enum Difficulty {
     EASY,
@@ -638,14 +1040,54 @@ pub enum Difficulty {
     EXPERT,
 }
 
+impl std::str::FromStr for Difficulty {
+    type Err = String;
+
+    /// 不区分大小写，如"easy"、"Expert"，MIDIUM也可以写作medium
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "easy" => Ok(Difficulty::EASY),
+            "normal" => Ok(Difficulty::NORMAL),
+            "medium" | "midium" => Ok(Difficulty::MIDIUM),
+            "hard" => Ok(Difficulty::HARD),
+            "expert" => Ok(Difficulty::EXPERT),
+            _ => Err(format!("Invalid Difficulty: {}", s)),
+        }
+    }
+}
+
 impl Difficulty {
-    fn empty_cells(&self) -> (usize, usize) {
+    /// 用于显示的名称，如"Easy"、"Medium"
+    pub fn name(&self) -> &'static str {
         match self {
+            Self::EASY => "Easy",
+            Self::NORMAL => "Normal",
+            Self::MIDIUM => "Medium",
+            Self::HARD => "Hard",
+            Self::EXPERT => "Expert",
+        }
+    }
+
+    // 9x9时的挖空数量，其他尺寸按格子总数等比例换算
+    pub(crate) fn empty_cells(&self, cell_count: usize) -> (usize, usize) {
+        let (min, max) = match self {
             Self::EASY => (35, 40),
             Self::NORMAL => (40, 45),
             Self::MIDIUM => (45, 50),
             Self::HARD => (50, 55),
             Self::EXPERT => (55, 64), // 81-17=64
+        };
+        (min * cell_count / 81, max * cell_count / 81)
+    }
+
+    // 杀手数独中笼子的最大格数
+    fn max_cage_size(&self) -> usize {
+        match self {
+            Self::EASY => 2,
+            Self::NORMAL => 3,
+            Self::MIDIUM => 4,
+            Self::HARD => 5,
+            Self::EXPERT => 6,
         }
     }
 
@@ -659,321 +1101,571 @@ impl Difficulty {
 
 // #[wasm_bindgen]
 impl Sudoku {
-    // 从字符串初始化数独，要求输入字符串长度必须为81，且仅为0-9的数字
+    // 从字符串初始化数独，要求输入字符串长度必须为边长的平方（如81），0表示空格，
+    // 超过9的数值用字母A-P表示
     pub fn initial_by_string(input: String) -> Result<Sudoku, String> {
-        if input.len() != 81 {
-            return Err("Invalid String Length.".to_string());
-        }
+        let (rules, givens) = parse_values(&input)?;
+        Ok(Sudoku::from_givens(rules, &givens))
+    }
 
-        let mut field: Sudoku = unsafe {
-            let mut field = std::mem::MaybeUninit::<Sudoku>::uninit();
-            let p_field: *mut Sudoku = field.as_mut_ptr();
-            let p_cell: *mut Cell = (*p_field).cells.as_mut_ptr();
+    // 采用洗牌算法+随机挖空生成随机数独
+    pub fn new(difficulty: Difficulty) -> Self {
+        Sudoku::generate(Rules::classic(), difficulty)
+    }
 
-            for (index, item) in input.chars().enumerate() {
-                let tmp = item.to_digit(10).expect("Invalid Character.");
-                let rc = RCCoords::from_idx(index);
-                let coords = rc.into();
-                let status = if tmp == 0 {
-                    CellStatus::DRAFT
-                } else {
-                    CellStatus::FIXED
-                };
-                let value = if tmp == 0 {
-                    None
-                } else {
-                    Some((tmp - 1) as u8)
-                };
-                std::ptr::write(
-                    p_cell.offset(index as isize),
-                    Cell {
-                        coords,
-                        status,
-                        candidates: Candidate::FULL,
-                        value,
-                    },
-                );
-            }
+    /// 按规则生成随机数独，16x16以上的盘面挖空时需要反复求解，耗时较长
+    /// 只有行列宫的盘面由基础终盘随机变换得到，锯齿数独和变体规则的终盘由随机回溯得到
+    pub fn generate(rules: Rules, difficulty: Difficulty) -> Self {
+        let mut rng = rand::thread_rng();
+        let (min_digs, max_digs) = difficulty.empty_cells(rules.cell_count());
+
+        let mut sudoku = solved_grid(rules, &mut rng);
+        dig_holes(&mut sudoku, rng.gen_range(min_digs..=max_digs), &mut rng);
+        sudoku.fill_drafts();
+
+        sudoku
+    }
 
-            field.assume_init()
+    /// 生成杀手数独：先得到随机终盘，再把终盘划分成数字不重复的随机笼子，最后尽量挖空所有格子
+    /// 难度决定笼子的最大格数，笼子越大组合越多，推理越难，规则中原有的笼子会被替换
+    pub fn generate_killer(rules: Rules, difficulty: Difficulty) -> Self {
+        let mut rng = rand::thread_rng();
+        let solution = solved_grid(rules, &mut rng);
+        let cages = random_cages(&solution, difficulty.max_cage_size(), &mut rng);
+        let rules = Rules::clone(&solution.rules)
+            .with_cages(cages)
+            .expect("cages from a solution are valid");
+
+        let mut sudoku = Sudoku {
+            rules: Arc::new(rules),
+            cells: solution.cells,
         };
+        let cell_count = sudoku.cells.len();
+        dig_holes(&mut sudoku, cell_count, &mut rng);
+        sudoku.fill_drafts();
 
-        field.fill_drafts();
+        sudoku
+    }
+}
 
-        Ok(field)
+// 随机终盘，所有格子都是固定值
+fn solved_grid(rules: Rules, rng: &mut impl Rng) -> Sudoku {
+    if rules.is_regular() {
+        return shuffle(&create_base(rules), rng);
     }
+    let budget = rules.cell_count() * 4;
+    let mut sudoku = Sudoku::blank(rules.clone());
+    while !fill_random(&mut sudoku, rng, &mut budget.clone()) {
+        sudoku = Sudoku::blank(rules.clone());
+    }
+    for cell in sudoku.cells.iter_mut() {
+        cell.status = CellStatus::FIXED;
+        cell.candidates = Candidate::default();
+    }
+    sudoku
+}
 
-    // 采用洗牌算法+随机挖空生成随机数独
-    pub fn new(difficulty: Difficulty) -> Self {
-        fn create_base() -> Sudoku {
-            let mut cells = [Cell {
-                coords: Coords {
-                    r: 0,
-                    c: 0,
-                    g: 0,
-                    n: 0,
-                },
-                status: CellStatus::FIXED,
-                candidates: Candidate::default(),
-                value: None,
-            }; 81];
-            for r in 0..9 {
-                for c in 0..9 {
-                    let index = r * 9 + c;
-                    let rc = RCCoords { r, c };
-                    let value: Option<u8> = Some(((r * 3 + r / 3 + c) % 9) as u8);
-                    cells[index].coords = rc.into();
-                    if (r * 3) % 9 + r / 3 == c {
-                        cells[index].candidates.add(value.unwrap());
-                        cells[index].status = CellStatus::DRAFT;
-                        // cells[index].value = None;
-                    } else {
-                        // cells[index].candidates No Need To Add;
-                        cells[index].status = CellStatus::FIXED;
-                        cells[index].value = value;
-                    };
-                }
+// 基础终盘，格子(r,c)的答案为(宫列数 * (r % 宫行数) + r / 宫行数 + c) % 边长
+fn create_base(rules: Rules) -> Sudoku {
+    let size = rules.size();
+    let (box_rows, box_cols) = (rules.box_rows(), rules.box_cols());
+    let mut sudoku = Sudoku::blank(rules);
+    for (idx, cell) in sudoku.cells.iter_mut().enumerate() {
+        let (r, c) = (idx / size, idx % size);
+        cell.status = CellStatus::FIXED;
+        cell.candidates = Candidate::default();
+        cell.value = Some(((box_cols * (r % box_rows) + r / box_rows + c) % size) as u8);
+    }
+    sudoku
+}
+
+// 对终盘做保持数独性质的随机变换：宫内行/列交换、整行宫/列宫交换、数字替换
+fn shuffle(sudoku: &Sudoku, rng: &mut impl Rng) -> Sudoku {
+    // groups组，每组lines条线
+    fn shuffled_lines(groups: usize, lines: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let mut bands: Vec<usize> = (0..groups).collect();
+        bands.shuffle(rng);
+        bands
+            .into_iter()
+            .flat_map(|band| {
+                let mut lines: Vec<usize> = (band * lines..band * lines + lines).collect();
+                lines.shuffle(rng);
+                lines
+            })
+            .collect()
+    }
+
+    let size = sudoku.size();
+    let (box_rows, box_cols) = (sudoku.rules.box_rows(), sudoku.rules.box_cols());
+    let rows = shuffled_lines(size / box_rows, box_rows, rng);
+    let cols = shuffled_lines(size / box_cols, box_cols, rng);
+    let mut numbers: Vec<u8> = (0..size as u8).collect();
+    numbers.shuffle(rng);
+
+    let mut result = sudoku.clone();
+    for (r, &from_r) in rows.iter().enumerate() {
+        for (c, &from_c) in cols.iter().enumerate() {
+            let value = sudoku
+                .get_cell_ref_by_rc(RCCoords {
+                    r: from_r,
+                    c: from_c,
+                })
+                .value
+                .map(|v| numbers[v as usize]);
+            result.get_cell_mut_by_rc(RCCoords { r, c }).value = value;
+        }
+    }
+    result
+}
+
+// 不规则区域和变体规则无法通过交换行列保持数独性质，改为随机顺序回溯填满整个盘面
+// 每次选择可填数值最少的格子，9x9的锯齿数独通常很快就能填满，笼子和等额外约束同样参与筛选
+// 非连续等约束较强的规则下随机回溯容易陷入很深的无解分支，搜索次数超过budget时放弃并重新开始
+fn fill_random(sudoku: &mut Sudoku, rng: &mut impl Rng, budget: &mut usize) -> bool {
+    if *budget == 0 {
+        return false;
+    }
+    *budget -= 1;
+    let Some(domains) = sudoku.allowed_values(|_| Candidate::full(sudoku.size())) else {
+        return false;
+    };
+    let best = (0..sudoku.cells.len())
+        .filter(|&idx| sudoku.cells[idx].value.is_none())
+        .min_by_key(|&idx| domains[idx].len());
+    let Some(mut idx) = best else {
+        return true;
+    };
+
+    // 单元内某个数字无处可填时回溯，只剩一个位置时直接填写
+    let mut forced = None;
+    let full = Candidate::full(sudoku.size());
+    for unit in sudoku.rules.unit_cells() {
+        let mut once = Candidate::default();
+        let mut twice = Candidate::default();
+        for &p in unit {
+            twice = twice.union(&once.intersect(&domains[p]));
+            once = once.union(&domains[p]);
+        }
+        if once != full {
+            return false;
+        }
+        let single = once.subtract(&twice);
+        if let Some(&p) = unit.iter().find(|&&p| {
+            sudoku.cells[p].value.is_none() && !domains[p].intersect(&single).is_empty()
+        }) {
+            if forced.is_none() {
+                idx = p;
+                forced = Some(domains[p].intersect(&single));
             }
-            Sudoku { cells }
-        }
-
-        fn dig_holes(sudoku: *mut Sudoku, max_digs: usize) {
-            unsafe {
-                let mut candidate_cells: Vec<*mut Cell> = (*sudoku)
-                    .cells
-                    .iter_mut()
-                    .filter(|c| c.status == CellStatus::FIXED)
-                    .map(|p| p as *mut Cell)
-                    .collect();
-
-                candidate_cells.shuffle(&mut rand::thread_rng());
-
-                for cell in candidate_cells.into_iter().take(max_digs) {
-                    let original = (*cell).value.take().unwrap();
-                    (*cell).status = CellStatus::DRAFT;
-
-                    // 更新相关候选数
-                    update_peers_candidates(sudoku, cell, original);
-                    // (*sudoku).print();
-                    let mut bak = (*sudoku).clone();
-                    let mut cnt: usize = 0;
-                    if !is_unique(addr_of_mut!(bak), &mut cnt) {
-                        (*cell).status = CellStatus::FIXED;
-                        (*cell).value = Some(original);
-                    }
-                    // println!("{:?}", cnt)
-                }
+        }
+    }
+
+    let mut values = forced.unwrap_or(domains[idx]).to_vec();
+    values.shuffle(rng);
+    for v in values {
+        sudoku.cells[idx].value = Some(v);
+        if fill_random(sudoku, rng, budget) {
+            return true;
+        }
+    }
+    sudoku.cells[idx].value = None;
+    false
+}
+
+// 把终盘划分成随机的笼子：从随机的格子出发，每次并入一个相邻且数字不重复的格子，
+// 笼子至少两格，周围没有可并入的格子时才会出现单格笼子
+fn random_cages(sudoku: &Sudoku, max_size: usize, rng: &mut impl Rng) -> Vec<Cage> {
+    let rules = sudoku.rules();
+    let size = rules.size();
+    let mut taken = vec![false; sudoku.cells.len()];
+    let mut starts: Vec<usize> = (0..sudoku.cells.len()).collect();
+    starts.shuffle(rng);
+
+    let mut cages = vec![];
+    for start in starts {
+        if taken[start] {
+            continue;
+        }
+        let target = rng.gen_range(2..=max_size.max(2));
+        let mut cells = vec![start];
+        taken[start] = true;
+        while cells.len() < target {
+            let value_of = |idx: usize| sudoku.cells[idx].value;
+            let mut options: Vec<usize> = cells
+                .iter()
+                .flat_map(|&idx| {
+                    let (r, c) = (idx / size, idx % size);
+                    [
+                        (r > 0).then(|| idx - size),
+                        (r + 1 < size).then(|| idx + size),
+                        (c > 0).then(|| idx - 1),
+                        (c + 1 < size).then(|| idx + 1),
+                    ]
+                })
+                .flatten()
+                .filter(|&p| !taken[p] && cells.iter().all(|&q| value_of(q) != value_of(p)))
+                .collect();
+            options.sort();
+            options.dedup();
+            let Some(&next) = options.choose(rng) else {
+                break;
+            };
+            taken[next] = true;
+            cells.push(next);
+        }
+        cells.sort();
+        let sum = cells
+            .iter()
+            .map(|&idx| sudoku.cells[idx].value.map_or(0, |v| v as u32 + 1))
+            .sum();
+        cages.push(Cage { cells, sum });
+    }
+    cages.sort_by_key(|cage| cage.cells[0]);
+    cages
+}
+
+// 随机挖空，每挖一个格子都要保证数独仍然只有唯一解，否则还原
+fn dig_holes(sudoku: &mut Sudoku, max_digs: usize, rng: &mut impl Rng) {
+    let mut indexes: Vec<usize> = (0..sudoku.cells.len()).collect();
+    indexes.shuffle(rng);
+
+    let mut digs = 0;
+    for idx in indexes {
+        if digs >= max_digs {
+            break;
+        }
+        let original = sudoku.cells[idx].value.take();
+        sudoku.cells[idx].status = CellStatus::DRAFT;
+        sudoku.fill_drafts();
+        if sudoku.sovle().len() == 1 {
+            digs += 1;
+        } else {
+            sudoku.cells[idx].status = CellStatus::FIXED;
+            sudoku.cells[idx].value = original;
+        }
+    }
+}
+
+// 解析一行题目，格子数决定盘面尺寸，0和.表示空格
+fn parse_values(input: &str) -> Result<(Rules, Vec<Option<u8>>), String> {
+    let rules = Rules::with_cell_count(input.chars().count())?;
+    let values = input
+        .chars()
+        .map(|ch| match (ch, char_to_value(ch)) {
+            ('0' | '.', _) => Ok(None),
+            (_, Some(v)) if (v as usize) < rules.size() => Ok(Some(v)),
+            _ => Err(format!("Invalid Character: {}", ch)),
+        })
+        .collect::<Result<Vec<Option<u8>>, String>>()?;
+    Ok((rules, values))
+}
+
+/// 数独的可读序列化格式：给定数、用户填写值均为81位0-9字符串（同initial_by_string），
+/// 候选数为81个字符串，每个字符串是该格草稿的数字（1-9），非草稿格为空字符串。
+/// 反序列化时若省略candidates，则按给定数重新计算所有草稿。
+/// 其他尺寸的盘面长度为格子总数，超过9的数值用字母表示，宫的形状不是默认形状时记录rules，
+/// 锯齿数独的rules中记录区域图，杀手数独的rules中记录笼子。
+#[derive(Serialize, Deserialize)]
+struct SudokuState {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rules: Option<Rules>,
+    givens: String,
+    values: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    candidates: Option<Vec<String>>,
+    // 用户手动排除的草稿，格式同candidates，没有任何排除记录时省略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    eliminated: Option<Vec<String>>,
+}
+
+impl Sudoku {
+    // 将每个格子转换为一个字符，0表示不满足filter条件
+    fn to_digit_string(&self, filter: CellStatus) -> String {
+        self.cells
+            .iter()
+            .map(|p| match p.value {
+                Some(v) if p.status == filter => value_to_char(v),
+                _ => '0',
+            })
+            .collect()
+    }
+
+    /// 每格一个字符，包含固定数和已填写的数，与initial_by_string的输入格式相同
+    pub fn to_value_string(&self) -> String {
+        self.cells
+            .iter()
+            .map(|p| match p.value {
+                Some(v) => value_to_char(v),
+                None => '0',
+            })
+            .collect()
+    }
+
+    /// 从一行字符初始化，长度为81时为标准数独，其他尺寸见initial_by_string
+    /// 空格可以用0或.表示，非法输入返回错误而不是panic
+    pub fn from_line(input: &str) -> Result<Sudoku, String> {
+        Sudoku::initial_by_string(input.trim().to_string())
+    }
+
+    /// 按指定的规则解析一行字符，用于宫的形状不是默认形状的盘面（如3x2的宫）
+    pub fn from_line_with(input: &str, rules: Rules) -> Result<Sudoku, String> {
+        let (default_rules, givens) = parse_values(input.trim())?;
+        if default_rules.size() != rules.size() {
+            return Err("Invalid String Length.".to_string());
+        }
+        Ok(Sudoku::from_givens(rules, &givens))
+    }
+
+    fn to_state(&self) -> SudokuState {
+        SudokuState {
+            rules: (!self.rules.is_default_for_size()).then(|| self.rules().clone()),
+            givens: self.to_digit_string(CellStatus::FIXED),
+            values: self.to_digit_string(CellStatus::SOLVE),
+            candidates: Some(
+                self.cells
+                    .iter()
+                    .map(|p| match p.status {
+                        CellStatus::DRAFT => format!("{:?}", p.candidates),
+                        _ => String::new(),
+                    })
+                    .collect(),
+            ),
+            eliminated: self
+                .cells
+                .iter()
+                .any(|p| !p.eliminated.is_empty())
+                .then(|| {
+                    self.cells
+                        .iter()
+                        .map(|p| format!("{:?}", p.eliminated))
+                        .collect()
+                }),
+        }
+    }
+
+    fn from_state(state: SudokuState) -> Result<Sudoku, String> {
+        fn parse_digits(input: &str, size: usize) -> Result<Vec<Option<u8>>, String> {
+            if input.chars().count() != size * size {
+                return Err("Invalid String Length.".to_string());
             }
+            input
+                .chars()
+                .map(|ch| match char_to_value(ch) {
+                    _ if ch == '0' => Ok(None),
+                    Some(v) if (v as usize) < size => Ok(Some(v)),
+                    _ => Err(format!("Invalid Character: {}", ch)),
+                })
+                .collect()
         }
 
-        unsafe fn is_unique(sudoku: *mut Sudoku, count: &mut usize) -> bool {
-            // 假设格子的位置是r,c，那么这个格子的答案一定是(r * 3 + r / 3 + c) % 9)
-            // 这里要想办法最快的推出矛盾来，假设挖空了这个格子，一定不能填其他数字
-            // 从所有未填写的格子中，假设这个并非其意料之中的答案，排除候选数
-            // 然后，观察是否可以得到矛盾——有个格子完全没有候选数，或者有个格子只能填写意料之外的数字
-            unsafe fn is_valid(r: usize, c: usize, v: Option<u8>, field: *const Sudoku) -> bool {
-                let GNCoords { g, n: _ } = RCCoords { r, c }.into();
-                for i in 0..9 {
-                    if (*field).get_cell_ref_by_rc(RCCoords { r, c: i }).value == v {
-                        return false;
-                    }
-                    if (*field).get_cell_ref_by_rc(RCCoords { r: i, c }).value == v {
-                        return false;
+        fn parse_candidate(input: &str, size: usize) -> Result<Candidate, String> {
+            input.chars().try_fold(
+                Candidate::default(),
+                |mut candidate, ch| match char_to_value(ch) {
+                    Some(v) if (v as usize) < size => {
+                        candidate.add(v);
+                        Ok(candidate)
                     }
-                    if (*field).get_cell_ref_by_gn(GNCoords { g, n: i }).value == v {
-                        return false;
-                    }
-                }
-                true
+                    _ => Err(format!("Invalid Candidate Character: {}", ch)),
+                },
+            )
+        }
+
+        let rules = match state.rules {
+            Some(rules) => rules,
+            None => Rules::with_cell_count(state.givens.chars().count())?,
+        };
+        let size = rules.size();
+        let givens = parse_digits(&state.givens, size)?;
+        let values = parse_digits(&state.values, size)?;
+        let parse_candidates = |candidates: &Option<Vec<String>>| match candidates {
+            Some(candidates) if candidates.len() != size * size => {
+                Err("Invalid Candidates Length.".to_string())
+            }
+            Some(candidates) => candidates
+                .iter()
+                .map(|s| parse_candidate(s, size))
+                .collect::<Result<Vec<Candidate>, String>>()
+                .map(Some),
+            None => Ok(None),
+        };
+        let candidates = parse_candidates(&state.candidates)?;
+        let eliminated = parse_candidates(&state.eliminated)?;
+
+        let mut field = Sudoku::blank(rules);
+        let full = Candidate::full(size);
+        for (idx, cell) in field.cells.iter_mut().enumerate() {
+            cell.candidates = Candidate::default();
+            if let Some(eliminated) = &eliminated {
+                cell.eliminated = eliminated[idx];
             }
-            // 找到第一个草稿状态的单元格
-            for r in 0..9 {
-                for c in 0..9 {
-                    let idx = r * 9 + c;
-                    let cell: *mut Cell = addr_of_mut!((*sudoku).cells[idx]);
-                    if (*cell).status == CellStatus::DRAFT {
-                        for &num in &(*cell).candidates.to_vec() {
-                            if is_valid(r, c, Some(num), sudoku) {
-                                (*cell).value = Some(num);
-                                (*cell).status = CellStatus::SOLVE;
-
-                                if is_unique(sudoku, count) {
-                                    *count += 1;
-                                    if *count >= 2 {
-                                        return false;
-                                    };
-                                }
-
-                                // 回溯
-                                (*cell).status = CellStatus::DRAFT;
-                                (*cell).value = None; // 重置值
-                            }
+            match (givens[idx], values[idx]) {
+                (Some(_), Some(_)) => {
+                    return Err(format!(
+                        "Cell {:?} Is Both Given And Solved.",
+                        RCCoords {
+                            r: idx / size,
+                            c: idx % size
                         }
-                        return false; // 如果没有找到有效的数字，则返回false
-                    }
+                    ))
+                }
+                (Some(v), None) => {
+                    cell.status = CellStatus::FIXED;
+                    cell.value = Some(v);
+                }
+                (None, Some(v)) => {
+                    cell.status = CellStatus::SOLVE;
+                    cell.value = Some(v);
+                }
+                (None, None) => {
+                    cell.candidates = candidates.as_ref().map_or(full, |c| c[idx]);
                 }
             }
+        }
 
-            true // 如果所有单元格都已解决，则返回true
-        }
-
-        unsafe fn update_peers_candidates(sudoku: *mut Sudoku, mid_cell: *const Cell, value: u8) {
-            for i in 0..9 {
-                // 更新行候选
-                maybe_add_candidate(
-                    sudoku,
-                    (*sudoku).get_cell_mut_ptr_by_rc(RCCoords {
-                        r: (*mid_cell).coords.r,
-                        c: i,
-                    }),
-                    value,
-                );
-                // 更新列候选
-                maybe_add_candidate(
-                    sudoku,
-                    (*sudoku).get_cell_mut_ptr_by_rc(RCCoords {
-                        r: i,
-                        c: (*mid_cell).coords.c,
-                    }),
-                    value,
-                );
-                // 更新宫候选
-                maybe_add_candidate(
-                    sudoku,
-                    (*sudoku).get_cell_mut_ptr_by_gn(GNCoords {
-                        g: (*mid_cell).coords.g,
-                        n: i,
-                    }),
-                    value,
-                );
-            }
+        if candidates.is_none() {
+            field.fill_drafts();
         }
+        Ok(field)
+    }
 
-        unsafe fn maybe_add_candidate(sudoku: *mut Sudoku, cell: *mut Cell, value: u8) {
-            if ((*cell).coords.r == 0 && (*cell).coords.c == 0) {
-                (*sudoku).print();
-                println!(
-                    "{:?},{:?},{:?}",
-                    value,
-                    (*cell).status,
-                    conflict_exists(sudoku, cell, value)
-                )
-            }
-            if (*cell).status == CellStatus::DRAFT && !conflict_exists(sudoku, cell, value) {
-                (*cell).candidates.add(value);
-            }
+    // 紧凑格式中每个位掩码的十六进制位数，9x9时为3位
+    fn hex_width(&self) -> usize {
+        self.size().div_ceil(4)
+    }
+
+    /// 紧凑字符串格式：`给定数:填写值:候选数[:手动排除][|规则]`，给定数和填写值各81位0-9字符，
+    /// 候选数为81组3位十六进制位掩码（非草稿格为000），没有手动排除记录时省略最后一段
+    /// 其他尺寸的盘面每组位数为边长除以4向上取整，如16x16为4位
+    /// 规则不是该尺寸的默认规则时（如锯齿、对角线、非默认形状的宫），在`|`后附加规则的JSON
+    pub fn to_compact_string(&self) -> String {
+        let state = self.to_state();
+        let width = self.hex_width();
+        let candidates: String = self
+            .cells
+            .iter()
+            .map(|p| match p.status {
+                CellStatus::DRAFT => format!("{:0width$x}", p.candidates.0),
+                _ => "0".repeat(width),
+            })
+            .collect();
+        let mut ret = format!("{}:{}:{}", state.givens, state.values, candidates);
+        if state.eliminated.is_some() {
+            ret.push(':');
+            ret.extend(
+                self.cells
+                    .iter()
+                    .map(|p| format!("{:0width$x}", p.eliminated.0)),
+            );
+        }
+        if let Some(rules) = state.rules {
+            ret.push('|');
+            ret.push_str(&serde_json::to_string(&rules).expect("rules serialize to JSON"));
         }
+        ret
+    }
 
-        unsafe fn conflict_exists(sudoku: *mut Sudoku, cell: *const Cell, value: u8) -> bool {
-            for i in 0..9 {
-                let Cell {
-                    status: cell_status,
-                    value: cell_value,
-                    ..
-                } = (*sudoku).get_cell_ref_by_rc(RCCoords {
-                    r: (*cell).coords.r,
-                    c: i,
-                });
-                if *cell_status == CellStatus::FIXED && *cell_value == Some(value) {
-                    return true;
-                };
-                let Cell {
-                    status: cell_status,
-                    value: cell_value,
-                    ..
-                } = (*sudoku).get_cell_ref_by_rc(RCCoords {
-                    r: i,
-                    c: (*cell).coords.c,
-                });
-                if *cell_status == CellStatus::FIXED && *cell_value == Some(value) {
-                    return true;
-                };
-                let Cell {
-                    status: cell_status,
-                    value: cell_value,
-                    ..
-                } = (*sudoku).get_cell_ref_by_gn(GNCoords {
-                    g: (*cell).coords.g,
-                    n: i,
-                });
-                if *cell_status == CellStatus::FIXED && *cell_value == Some(value) {
-                    return true;
-                };
+    /// 从紧凑字符串还原数独，候选数部分可以省略（`给定数:填写值`），此时重新计算草稿
+    pub fn from_compact_string(input: &str) -> Result<Sudoku, String> {
+        Sudoku::parse_compact(input, None)
+    }
+
+    /// 按指定的规则解析紧凑字符串，字符串中附带规则时以字符串中的规则为准
+    pub fn from_compact_string_with(input: &str, rules: Rules) -> Result<Sudoku, String> {
+        Sudoku::parse_compact(input, Some(rules))
+    }
+
+    fn parse_compact(input: &str, rules: Option<Rules>) -> Result<Sudoku, String> {
+        fn parse_hex_masks(hex: Option<&str>, size: usize) -> Result<Option<Vec<String>>, String> {
+            let Some(hex) = hex else {
+                return Ok(None);
+            };
+            let width = size.div_ceil(4);
+            let count = size * size;
+            if hex.len() != count * width || !hex.is_ascii() {
+                return Err("Invalid Candidates Length.".to_string());
             }
-            false
-        }
-
-        let mut sudoku = create_base();
-        sudoku.print();
-        // dig_holes(addr_of_mut!(sudoku), difficulty.empty_cells().1);
-
-        // let mut rng = rand::thread_rng();
-
-        // // 行交换
-        // for _ in 0..3 {
-        //     let block = rng.gen_range(0..3);
-        //     let mut rows: Vec<usize> = (block * 3..(block + 1) * 3).collect();
-        //     rows.shuffle(&mut rng);
-        //     if let [r1, r2] = rows[..2] {
-        //         puzzle.swap(r1, r2);
-        //         solution.swap(r1, r2);
-        //     }
-        // }
-
-        // // 列交换
-        // for _ in 0..3 {
-        //     let block = rng.gen_range(0..3);
-        //     let mut cols: Vec<usize> = (block * 3..(block + 1) * 3).collect();
-        //     cols.shuffle(&mut rng);
-        //     if let [c1, c2] = cols[..2] {
-        //         for row in puzzle.iter_mut() {
-        //             row.swap(c1, c2);
-        //         }
-        //         for row in solution.iter_mut() {
-        //             row.swap(c1, c2);
-        //         }
-        //     }
-        // }
-
-        // // 数字替换
-        // let mut numbers: Vec<u8> = (1..=9).collect();
-        // numbers.shuffle(&mut rng);
-        // let replace_map: Vec<u8> = (1..=9).map(|i| numbers[i as usize - 1]).collect();
-
-        // for i in 0..9 {
-        //     for j in 0..9 {
-        //         solution[i][j] = replace_map[solution[i][j] as usize - 1];
-        //         if puzzle[i][j] != 0 {
-        //             puzzle[i][j] = replace_map[puzzle[i][j] as usize - 1];
-        //         }
-        //     }
-        // }
-
-        //     field.assume_init()
-        // };
+            (0..count)
+                .map(|i| {
+                    let mask = u32::from_str_radix(&hex[i * width..(i + 1) * width], 16)
+                        .map_err(|e| e.to_string())?;
+                    if mask & !Candidate::full(size).0 != 0 {
+                        return Err(format!("Invalid Candidate Mask: {:#x}", mask));
+                    }
+                    Ok(format!("{:?}", Candidate(mask)))
+                })
+                .collect::<Result<Vec<String>, String>>()
+                .map(Some)
+        }
 
-        sudoku.fill_drafts();
+        let (input, rules) = match input.trim().split_once('|') {
+            Some((input, spec)) => (
+                input,
+                Some(
+                    serde_json::from_str::<Rules>(spec)
+                        .map_err(|e| format!("Invalid Rules: {}.", e))?,
+                ),
+            ),
+            None => (input.trim(), rules),
+        };
+        let mut parts = input.split(':');
+        let givens = parts.next().unwrap_or_default().to_string();
+        let values = parts
+            .next()
+            .ok_or_else(|| "Missing Values Section.".to_string())?
+            .to_string();
+        let size = match &rules {
+            Some(rules) => rules.size(),
+            None => Rules::with_cell_count(givens.chars().count())?.size(),
+        };
+        let candidates = parse_hex_masks(parts.next(), size)?;
+        let eliminated = parse_hex_masks(parts.next(), size)?;
+        if parts.next().is_some() {
+            return Err("Too Many Sections.".to_string());
+        }
+        Sudoku::from_state(SudokuState {
+            rules,
+            givens,
+            values,
+            candidates,
+            eliminated,
+        })
+    }
+}
 
-        sudoku
+// 可读格式（如JSON、serde-wasm-bindgen）使用SudokuState，其他格式使用紧凑字符串
+impl Serialize for Sudoku {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            self.to_state().serialize(serializer)
+        } else {
+            serializer.serialize_str(&self.to_compact_string())
+        }
+    }
+}
 
-        // let mut sudoku = Sudoku::create_base();
+impl<'de> Deserialize<'de> for Sudoku {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            Sudoku::from_state(SudokuState::deserialize(deserializer)?).map_err(de::Error::custom)
+        } else {
+            Sudoku::from_compact_string(&String::deserialize(deserializer)?)
+                .map_err(de::Error::custom)
+        }
+    }
+}
 
-        // // 第一阶段：基础挖空
-        // sudoku.dig_holes(27);  // 固定挖空27格
+/// 配合 `#[serde(with = "lib_sudoku_solver::types::compact")]` 使用，在JSON中也强制使用紧凑字符串
+pub mod compact {
+    use serde::{de, Deserialize, Deserializer, Serializer};
 
-        // // 第二阶段：难度相关挖空
-        // let extra_digs = match difficulty {
-        //     Difficulty::Easy => 13,
-        //     Difficulty::Medium => 18,
-        //     Difficulty::Hard => 23,
-        //     // ...
-        // };
-        // sudoku.dig_holes(extra_digs);
+    use super::Sudoku;
 
-        // // 随机变换
-        // sudoku.shuffle_rows_columns();
-        // sudoku.remap_numbers();
+    pub fn serialize<S: Serializer>(field: &Sudoku, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&field.to_compact_string())
+    }
 
-        // sudoku
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Sudoku, D::Error> {
+        Sudoku::from_compact_string(&String::deserialize(deserializer)?).map_err(de::Error::custom)
     }
 }
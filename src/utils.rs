@@ -2,7 +2,7 @@
 
 use crate::{
     inferences::TheCoordsAndTheValue,
-    types::{Cell, Coords, GNCoords, RCCoords, Sudoku},
+    types::{Coords, GNCoords, RCCoords, Sudoku},
 };
 
 // 定义子函数，将一个集合拆分成X和剩余部分的两个集合，且 2<=X<=4
@@ -30,7 +30,7 @@ pub fn generate_combinations(
     }
 }
 
-// 当某个格子设置某个值的时候，将同行列宫的该值的草稿值移除，输入值在vec_set_value.cells内，且value唯一
+// 当某个格子设置某个值的时候，将同行列宫的该值的草稿值移除（不包括该格子本身），输入值在vec_set_value.cells内，且value唯一
 pub fn make_simple_conclusion_when_set_value<'a>(
     field: &'a Sudoku,
     coords: &'a Coords,
@@ -39,6 +39,7 @@ pub fn make_simple_conclusion_when_set_value<'a>(
     let ret: Vec<TheCoordsAndTheValue> = field
         .collect_all_drafts_coords_by_coords_and_value(*coords, value)
         .iter()
+        .filter(|p| p.r != coords.r || p.c != coords.c)
         .map(|&p| create_simple_cell_and_value(p, value))
         .collect();
 
@@ -49,7 +50,7 @@ pub fn make_simple_conclusion_when_set_value<'a>(
     }
 }
 
-pub fn create_simple_cell_and_value<'a>(coords: Coords, v: u8) -> TheCoordsAndTheValue {
+pub fn create_simple_cell_and_value(coords: Coords, v: u8) -> TheCoordsAndTheValue {
     TheCoordsAndTheValue {
         the_coords: coords,
         the_value: vec![v],
@@ -0,0 +1,57 @@
+// 错误检查：将用户的填写值和草稿与唯一解对比，或者只根据逻辑判断当前盘面是否仍然有解
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{CellStatus, RCCoords, Sudoku};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum MistakeKind {
+    // 填写值与答案不符
+    WrongValue,
+    // 手动排除了正确答案的草稿
+    EliminatedAnswer,
+}
+
+/// 一处错误，value是用户填写的值，或被错误排除的草稿
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Mistake {
+    pub coords: RCCoords,
+    pub value: u8,
+    pub kind: MistakeKind,
+}
+
+impl Sudoku {
+    /// 与答案对比，返回所有填错的格子和手动排除了正确答案的草稿格
+    /// 填错的值自动移除的草稿不单独报告，错误已经记在填错的格子上
+    pub fn check_against(&self, solution: &Sudoku) -> Vec<Mistake> {
+        self.iter_rc()
+            .filter_map(|rc| {
+                let cell = self.get_cell_ref_by_rc(rc);
+                let answer = solution.get_cell_ref_by_rc(rc).value?;
+                match cell.status {
+                    CellStatus::SOLVE if cell.value != Some(answer) => Some(Mistake {
+                        coords: rc,
+                        value: cell.value?,
+                        kind: MistakeKind::WrongValue,
+                    }),
+                    CellStatus::DRAFT if cell.eliminated.contains(answer) => Some(Mistake {
+                        coords: rc,
+                        value: answer,
+                        kind: MistakeKind::EliminatedAnswer,
+                    }),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// 不借助答案，判断当前的填写值和草稿是否仍然可能得到一个解
+    pub fn is_solvable(&self) -> bool {
+        self.find_conflict().is_none()
+            && self
+                .collect_all_drafts_cells()
+                .iter()
+                .all(|p| !p.candidates.is_empty())
+            && !self.sovle().is_empty()
+    }
+}
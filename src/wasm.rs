@@ -0,0 +1,395 @@
+// 提供给JS使用的接口，行列下标和数值均与Rust侧一致，从0开始
+// 复杂的返回值通过serde-wasm-bindgen转换为普通的JS对象
+
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    game::Game,
+    hint::HintLevel,
+    inferences::InferenceSet,
+    locale::Catalog,
+    rules::Rules,
+    types::{CellStatus, Difficulty, RCCoords, Sudoku},
+};
+
+fn check_rc(field: &Sudoku, r: usize, c: usize) -> Result<RCCoords, JsValue> {
+    if r < field.size() && c < field.size() {
+        Ok(RCCoords { r, c })
+    } else {
+        Err(JsValue::from_str(&format!(
+            "Invalid Coords: ({}, {})",
+            r, c
+        )))
+    }
+}
+
+fn check_value(field: &Sudoku, v: u8) -> Result<u8, JsValue> {
+    if (v as usize) < field.size() {
+        Ok(v)
+    } else {
+        Err(JsValue::from_str(&format!("Invalid Value: {}", v)))
+    }
+}
+
+#[wasm_bindgen]
+impl Sudoku {
+    /// 从81位0-9字符串初始化，其他尺寸的长度为格子总数，超过9的数值用字母A-P表示
+    #[wasm_bindgen(js_name = fromString)]
+    pub fn js_from_string(input: String) -> Result<Sudoku, JsValue> {
+        Sudoku::initial_by_string(input).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// 按难度随机生成
+    #[wasm_bindgen(js_name = generate)]
+    pub fn js_generate(difficulty: Difficulty) -> Sudoku {
+        Sudoku::new(difficulty)
+    }
+
+    /// 生成由boxRows行、boxCols列的宫组成的数独，如2x3的宫为6x6数独
+    #[wasm_bindgen(js_name = generateWithBoxes)]
+    pub fn js_generate_with_boxes(
+        box_rows: usize,
+        box_cols: usize,
+        difficulty: Difficulty,
+    ) -> Result<Sudoku, JsValue> {
+        let rules = Rules::new(box_rows, box_cols).map_err(|e| JsValue::from_str(&e))?;
+        Ok(Sudoku::generate(rules, difficulty))
+    }
+
+    /// 锯齿数独，regions为区域图，每格一个字符，相同字符的格子属于同一区域
+    #[wasm_bindgen(js_name = fromStringWithRegions)]
+    pub fn js_from_string_with_regions(input: &str, regions: &str) -> Result<Sudoku, JsValue> {
+        Rules::jigsaw(regions)
+            .and_then(|rules| Sudoku::from_line_with(input, rules))
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// 按区域图生成锯齿数独
+    #[wasm_bindgen(js_name = generateWithRegions)]
+    pub fn js_generate_with_regions(
+        regions: &str,
+        difficulty: Difficulty,
+    ) -> Result<Sudoku, JsValue> {
+        let rules = Rules::jigsaw(regions).map_err(|e| JsValue::from_str(&e))?;
+        Ok(Sudoku::generate(rules, difficulty))
+    }
+
+    /// 区域图，每格一个字符，同一个宫（区域）的格子字符相同
+    #[wasm_bindgen(getter = regions)]
+    pub fn js_regions(&self) -> String {
+        self.rules().regions()
+    }
+
+    /// 按规则生成，rules为Rules序列化得到的对象，如 { box_rows: 3, box_cols: 3, diagonals: true }
+    #[wasm_bindgen(js_name = generateWithRules)]
+    pub fn js_generate_with_rules(
+        rules: JsValue,
+        difficulty: Difficulty,
+    ) -> Result<Sudoku, JsValue> {
+        let rules: Rules = serde_wasm_bindgen::from_value(rules)?;
+        Ok(Sudoku::generate(rules, difficulty))
+    }
+
+    /// 按规则生成杀手数独，笼子随机生成，通过rules getter的cages得到，
+    /// 每个笼子为 { cells: [按行优先的格子下标], sum: 笼子和 }
+    #[wasm_bindgen(js_name = generateKiller)]
+    pub fn js_generate_killer(rules: JsValue, difficulty: Difficulty) -> Result<Sudoku, JsValue> {
+        let rules: Rules = serde_wasm_bindgen::from_value(rules)?;
+        Ok(Sudoku::generate_killer(rules, difficulty))
+    }
+
+    /// 按规则解析一行字符，rules的格式同generateWithRules
+    #[wasm_bindgen(js_name = fromStringWithRules)]
+    pub fn js_from_string_with_rules(input: &str, rules: JsValue) -> Result<Sudoku, JsValue> {
+        let rules: Rules = serde_wasm_bindgen::from_value(rules)?;
+        Sudoku::from_line_with(input, rules).map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// 当前的规则，格式同generateWithRules
+    #[wasm_bindgen(getter = rules)]
+    pub fn js_rules(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(self.rules())?)
+    }
+
+    /// 盘面边长
+    #[wasm_bindgen(getter = size)]
+    pub fn js_size(&self) -> usize {
+        self.size()
+    }
+
+    /// 从toJS()得到的对象还原
+    #[wasm_bindgen(js_name = fromJS)]
+    pub fn js_from_js(value: JsValue) -> Result<Sudoku, JsValue> {
+        Ok(serde_wasm_bindgen::from_value(value)?)
+    }
+
+    #[wasm_bindgen(js_name = toJS)]
+    pub fn js_to_js(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(self)?)
+    }
+
+    #[wasm_bindgen(js_name = fromCompactString)]
+    pub fn js_from_compact_string(input: &str) -> Result<Sudoku, JsValue> {
+        Sudoku::from_compact_string(input).map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(js_name = toCompactString)]
+    pub fn js_to_compact_string(&self) -> String {
+        self.to_compact_string()
+    }
+
+    /// 获取单元格，返回 { coords, status, candidates, value }
+    #[wasm_bindgen(js_name = getCell)]
+    pub fn js_get_cell(&self, r: usize, c: usize) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(
+            self.get_cell_ref_by_rc(check_rc(self, r, c)?),
+        )?)
+    }
+
+    /// 填写数值并更新同行列宫的草稿，传入undefined时清空，不允许修改固定数值
+    #[wasm_bindgen(js_name = setValue)]
+    pub fn js_set_value(&mut self, r: usize, c: usize, value: Option<u8>) -> Result<(), JsValue> {
+        let rc = check_rc(self, r, c)?;
+        match value {
+            Some(v) => self.place(rc, v),
+            None => self.unplace(rc, false),
+        }
+        .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// 清空填写值并重新计算草稿，restoreEliminated为true时同时恢复手动排除的草稿
+    #[wasm_bindgen(js_name = clearValue)]
+    pub fn js_clear_value(
+        &mut self,
+        r: usize,
+        c: usize,
+        restore_eliminated: bool,
+    ) -> Result<(), JsValue> {
+        self.unplace(check_rc(self, r, c)?, restore_eliminated)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// 切换草稿数，返回切换后该草稿是否存在
+    #[wasm_bindgen(js_name = toggleCandidate)]
+    pub fn js_toggle_candidate(&mut self, r: usize, c: usize, value: u8) -> Result<bool, JsValue> {
+        let rc = check_rc(self, r, c)?;
+        let value = check_value(self, value)?;
+        let cell = self.get_cell_ref_by_rc(rc);
+        if cell.status != CellStatus::DRAFT {
+            return Err(JsValue::from_str("Cell Is Not A Draft."));
+        }
+        if cell.candidates.contains(value) {
+            self.eliminate_candidate(rc, value)
+        } else {
+            self.restore_candidate(rc, value)
+        }
+        .map_err(|e| JsValue::from_str(&e))?;
+        Ok(self.get_cell_ref_by_rc(rc).candidates.contains(value))
+    }
+
+    /// 下一步提示，无法推导时返回null
+    #[wasm_bindgen(js_name = nextHint)]
+    pub fn js_next_hint(&self) -> Result<JsValue, JsValue> {
+        let ifs = InferenceSet::new();
+        match ifs.analyze(self) {
+            Some(result) => Ok(serde_wasm_bindgen::to_value(&result)?),
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// 下一步的文字说明，catalog可以是内置语言代码如"en"，也可以是自定义的文字表对象，无法推导时返回undefined
+    #[wasm_bindgen(js_name = explainNextStep)]
+    pub fn js_explain_next_step(&self, catalog: JsValue) -> Result<Option<String>, JsValue> {
+        let catalog = match catalog.as_string() {
+            Some(language) => Catalog::builtin(&language)
+                .ok_or_else(|| JsValue::from_str(&format!("Unknown Language: {}", language)))?,
+            None => serde_wasm_bindgen::from_value(catalog)?,
+        };
+        let ifs = InferenceSet::new();
+        Ok(ifs
+            .analyze(self)
+            .map(|result| result.explanation_with(&catalog)))
+    }
+
+    /// 分级提示，只透露指定等级的内容，无法推导时返回null
+    #[wasm_bindgen(js_name = hint)]
+    pub fn js_hint(&self, level: HintLevel) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.hint(level))?)
+    }
+
+    /// 执行下一步提示，返回执行的推理结果，无法推导时返回null
+    #[wasm_bindgen(js_name = applyHint)]
+    pub fn js_apply_hint(&mut self) -> Result<JsValue, JsValue> {
+        let ifs = InferenceSet::new();
+        let old_field = self.clone();
+        match ifs.analyze(&old_field) {
+            Some(result) => {
+                let ret = serde_wasm_bindgen::to_value(&result)?;
+                InferenceSet::apply(self, result);
+                Ok(ret)
+            }
+            None => Ok(JsValue::NULL),
+        }
+    }
+
+    /// 暴力求解，最多返回两个解，长度为1时说明解唯一
+    #[wasm_bindgen(js_name = solve)]
+    pub fn js_solve(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.sovle())?)
+    }
+
+    /// 评估难度，返回 { solved, steps, score, hardest, difficulty }
+    #[wasm_bindgen(js_name = rate)]
+    pub fn js_rate(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(
+            &InferenceSet::new().rate(self),
+        )?)
+    }
+
+    /// 返回所有冲突的格子对坐标
+    #[wasm_bindgen(js_name = findConflicts)]
+    pub fn js_find_conflicts(&self) -> Result<JsValue, JsValue> {
+        let conflicts: Vec<(RCCoords, RCCoords)> = self
+            .find_conflict()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(p1, p2)| (p1.coords.into(), p2.coords.into()))
+            .collect();
+        Ok(serde_wasm_bindgen::to_value(&conflicts)?)
+    }
+
+    /// 不借助答案，判断当前盘面是否仍然有解
+    #[wasm_bindgen(js_name = isSolvable)]
+    pub fn js_is_solvable(&self) -> bool {
+        self.is_solvable()
+    }
+
+    #[wasm_bindgen(js_name = isFinished)]
+    pub fn js_is_finished(&self) -> bool {
+        self.check_if_finish()
+    }
+}
+
+#[wasm_bindgen]
+impl Game {
+    #[wasm_bindgen(constructor)]
+    pub fn js_new(puzzle: &Sudoku) -> Result<Game, JsValue> {
+        Game::new(puzzle.clone()).map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(js_name = generate)]
+    pub fn js_generate(difficulty: Difficulty) -> Game {
+        Game::generate(difficulty)
+    }
+
+    /// 从toJS()得到的对象还原存档
+    #[wasm_bindgen(js_name = fromJS)]
+    pub fn js_from_js(value: JsValue) -> Result<Game, JsValue> {
+        Ok(serde_wasm_bindgen::from_value(value)?)
+    }
+
+    #[wasm_bindgen(js_name = toJS)]
+    pub fn js_to_js(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(self)?)
+    }
+
+    /// 当前盘面的副本
+    #[wasm_bindgen(getter = field)]
+    pub fn js_field(&self) -> Sudoku {
+        self.field().clone()
+    }
+
+    #[wasm_bindgen(js_name = setValue)]
+    pub fn js_set_value(&mut self, r: usize, c: usize, value: u8) -> Result<(), JsValue> {
+        self.set_value(check_rc(self.field(), r, c)?, value)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(js_name = clearValue)]
+    pub fn js_clear_value(&mut self, r: usize, c: usize) -> Result<(), JsValue> {
+        self.clear_value(check_rc(self.field(), r, c)?)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(js_name = addCandidate)]
+    pub fn js_add_candidate(&mut self, r: usize, c: usize, value: u8) -> Result<(), JsValue> {
+        self.add_candidate(check_rc(self.field(), r, c)?, value)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(js_name = removeCandidate)]
+    pub fn js_remove_candidate(&mut self, r: usize, c: usize, value: u8) -> Result<(), JsValue> {
+        self.remove_candidate(check_rc(self.field(), r, c)?, value)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    /// 执行下一步提示，返回使用的策略名，无法推导时返回null
+    #[wasm_bindgen(js_name = applyHint)]
+    pub fn js_apply_hint(&mut self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.apply_hint())?)
+    }
+
+    /// 撤销，返回被撤销的操作，没有可撤销的操作时返回null
+    #[wasm_bindgen(js_name = undo)]
+    pub fn js_undo(&mut self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.undo())?)
+    }
+
+    #[wasm_bindgen(js_name = redo)]
+    pub fn js_redo(&mut self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.redo())?)
+    }
+
+    #[wasm_bindgen(js_name = canUndo)]
+    pub fn js_can_undo(&self) -> bool {
+        self.can_undo()
+    }
+
+    #[wasm_bindgen(js_name = canRedo)]
+    pub fn js_can_redo(&self) -> bool {
+        self.can_redo()
+    }
+
+    #[wasm_bindgen(js_name = isSolved)]
+    pub fn js_is_solved(&self) -> bool {
+        self.is_solved()
+    }
+
+    /// 检查进度，返回 [{ coords, value, kind }]
+    #[wasm_bindgen(js_name = check)]
+    pub fn js_check(&self) -> Result<JsValue, JsValue> {
+        Ok(serde_wasm_bindgen::to_value(&self.check())?)
+    }
+
+    #[wasm_bindgen(js_name = setDetectEarly)]
+    pub fn js_set_detect_early(&mut self, detect_early: bool) {
+        self.set_detect_early(detect_early)
+    }
+
+    /// 让数独变为无解的操作在操作记录中的下标
+    #[wasm_bindgen(js_name = flaggedMoves)]
+    pub fn js_flagged_moves(&self) -> Vec<usize> {
+        self.flagged_moves()
+    }
+
+    #[wasm_bindgen(js_name = addBookmark)]
+    pub fn js_add_bookmark(&mut self, name: &str) {
+        self.add_bookmark(name)
+    }
+
+    #[wasm_bindgen(js_name = removeBookmark)]
+    pub fn js_remove_bookmark(&mut self, name: &str) -> bool {
+        self.remove_bookmark(name)
+    }
+
+    #[wasm_bindgen(js_name = restoreBookmark)]
+    pub fn js_restore_bookmark(&mut self, name: &str) -> Result<(), JsValue> {
+        self.restore_bookmark(name)
+            .map_err(|e| JsValue::from_str(&e))
+    }
+
+    #[wasm_bindgen(js_name = bookmarks)]
+    pub fn js_bookmarks(&self) -> Vec<String> {
+        self.bookmarks().into_iter().map(String::from).collect()
+    }
+}